@@ -0,0 +1,29 @@
+-- Migration 004: Per-page document text
+-- Stores the plain text of every PDF page, extracted by the Rust backend on import,
+-- so search, retrieval and concept linking can work on the full document contents
+
+-- ============================================================================
+-- Document Pages Table
+-- One row per page, keyed by document and page number
+-- ============================================================================
+CREATE TABLE document_pages (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    page_number INTEGER NOT NULL,
+    content TEXT NOT NULL DEFAULT '',
+    char_count INTEGER NOT NULL DEFAULT 0,
+    extracted_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT document_pages_page_number_positive CHECK (page_number > 0),
+    CONSTRAINT document_pages_char_count_positive CHECK (char_count >= 0),
+    CONSTRAINT document_pages_unique UNIQUE (document_id, page_number)
+);
+
+-- ============================================================================
+-- Indexes for Performance
+-- ============================================================================
+
+CREATE INDEX idx_document_pages_document_id ON document_pages(document_id);
+
+COMMENT ON TABLE document_pages IS 'Plain text of each PDF page, extracted on import';
+COMMENT ON COLUMN document_pages.char_count IS 'Number of characters in the extracted text (0 for image-only pages)';
@@ -0,0 +1,11 @@
+-- Migration 005: Full-text search over document page text
+-- Adds a stored tsvector to document_pages so library search can use a GIN index
+-- instead of re-parsing every page on each query
+
+ALTER TABLE document_pages
+    ADD COLUMN search_vector TSVECTOR
+    GENERATED ALWAYS AS (to_tsvector('english', content)) STORED;
+
+CREATE INDEX idx_document_pages_search_vector ON document_pages USING GIN(search_vector);
+
+COMMENT ON COLUMN document_pages.search_vector IS 'English tsvector of the page text, maintained by PostgreSQL';
@@ -0,0 +1,27 @@
+-- Migration 006: Unified search index
+-- Extends search_index (migration 001) so the Rust backend can index documents,
+-- chat messages, highlighted contexts and concepts in one place
+
+-- Chat messages and concepts don't belong to a single document
+ALTER TABLE search_index ALTER COLUMN document_id DROP NOT NULL;
+
+-- Entries created from a chat disappear together with the chat session
+ALTER TABLE search_index
+    ADD COLUMN chat_session_id UUID REFERENCES chat_sessions(id) ON DELETE CASCADE;
+
+-- Display title for the indexed entity (document title, concept name, ...)
+ALTER TABLE search_index ADD COLUMN title TEXT;
+
+ALTER TABLE search_index DROP CONSTRAINT search_entity_type_valid;
+ALTER TABLE search_index ADD CONSTRAINT search_entity_type_valid CHECK (entity_type IN (
+    'knowledge-entry', 'user-note', 'ai-response', 'document',
+    'chat-message', 'highlighted-context', 'concept'
+));
+
+-- Each entity is indexed at most once so writes can upsert
+ALTER TABLE search_index ADD CONSTRAINT search_entity_unique UNIQUE (entity_type, entity_id);
+
+CREATE INDEX idx_search_chat_session_id ON search_index(chat_session_id);
+
+COMMENT ON TABLE search_index IS 'Full-text index over documents, chats, highlights and concepts, maintained by the Rust backend';
+COMMENT ON COLUMN search_index.weight IS 'Relevance multiplier applied to the text rank for this entity type';
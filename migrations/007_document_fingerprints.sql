@@ -0,0 +1,9 @@
+-- Migration 007: Document content fingerprints
+-- A SHA-256 of the file bytes identifies a document independently of its path,
+-- so moved or renamed PDFs can be relinked to their existing record
+
+ALTER TABLE documents ADD COLUMN content_hash TEXT;
+
+CREATE INDEX idx_documents_content_hash ON documents(content_hash);
+
+COMMENT ON COLUMN documents.content_hash IS 'Hex-encoded SHA-256 of the PDF bytes, computed on import';
@@ -0,0 +1,20 @@
+-- Migration 008: Library roots
+-- Folders that the Rust backend scans recursively for PDFs and watches for
+-- additions, removals and renames
+
+-- ============================================================================
+-- Library Roots Table
+-- One row per configured folder
+-- ============================================================================
+CREATE TABLE library_roots (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    path TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_scanned_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT library_roots_path_unique UNIQUE (path),
+    CONSTRAINT library_roots_path_not_empty CHECK (LENGTH(TRIM(path)) > 0)
+);
+
+COMMENT ON TABLE library_roots IS 'Folders scanned and watched for PDFs to keep documents in sync';
+COMMENT ON COLUMN library_roots.last_scanned_at IS 'Completion time of the last full scan (NULL if never scanned)';
@@ -0,0 +1,26 @@
+-- Migration 009: Document outlines
+-- Stores the PDF outline (bookmarks) extracted on import, and records on each
+-- highlighted context the outline section it was taken from
+
+-- ============================================================================
+-- Document Outline Table
+-- Outline entries flattened in reading order; nesting is given by level
+-- ============================================================================
+CREATE TABLE document_outline (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    level INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    page_number INTEGER,
+
+    CONSTRAINT document_outline_level_positive CHECK (level > 0),
+    CONSTRAINT document_outline_page_number_positive CHECK (page_number IS NULL OR page_number > 0),
+    CONSTRAINT document_outline_unique UNIQUE (document_id, position)
+);
+
+ALTER TABLE highlighted_contexts ADD COLUMN section_title TEXT;
+
+COMMENT ON TABLE document_outline IS 'PDF outline entries of each document, in reading order';
+COMMENT ON COLUMN document_outline.page_number IS 'Target page of the entry (NULL when the destination could not be resolved)';
+COMMENT ON COLUMN highlighted_contexts.section_title IS 'Outline path of the section the text was highlighted in, e.g. "Chapter 2 > Gradient Descent"';
@@ -0,0 +1,15 @@
+-- Migration 010: Persistent highlights
+-- Turns text_selections (migration 001) into standalone, colored highlights
+-- with an optional note, independent of any chat session
+
+ALTER TABLE text_selections ADD COLUMN color VARCHAR(7) NOT NULL DEFAULT '#fde047';
+ALTER TABLE text_selections ADD COLUMN note TEXT;
+ALTER TABLE text_selections ADD COLUMN updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW();
+
+ALTER TABLE text_selections ADD CONSTRAINT selections_color_hex CHECK (color ~ '^#[0-9a-fA-F]{6}$');
+
+-- Highlights are redrawn page by page
+CREATE INDEX idx_selections_document_page ON text_selections(document_id, page_number);
+
+COMMENT ON COLUMN text_selections.color IS 'Highlight color as #rrggbb';
+COMMENT ON COLUMN text_selections.note IS 'Optional user note attached to the highlight';
@@ -0,0 +1,8 @@
+-- Migration 011: User notes search
+-- Full-text index over note text for note search; notes are also mirrored
+-- into search_index as 'user-note' entries for unified search
+
+CREATE INDEX idx_notes_text_search ON user_notes USING GIN(to_tsvector('english', note_text));
+
+-- Notes are listed per document in page order
+CREATE INDEX idx_notes_document_page ON user_notes(document_id, page_number);
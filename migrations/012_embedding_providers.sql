@@ -0,0 +1,28 @@
+-- Migration 012: Embedding providers
+-- Records which model produced each concept embedding and lets the user pick
+-- the embedding backend (OpenAI-compatible API, Ollama or local hashing)
+
+ALTER TABLE concepts ADD COLUMN embedding_model VARCHAR(200);
+ALTER TABLE concepts ADD COLUMN embedding_dimension INTEGER;
+
+-- Every embedding so far came from the Python OpenAI client
+UPDATE concepts
+SET embedding_model = 'text-embedding-3-small', embedding_dimension = 1536
+WHERE embedding IS NOT NULL;
+
+ALTER TABLE concepts ADD CONSTRAINT concepts_embedding_metadata
+    CHECK (embedding IS NULL OR (embedding_model IS NOT NULL AND embedding_dimension IS NOT NULL));
+
+CREATE INDEX idx_concepts_embedding_model ON concepts(embedding_model);
+
+ALTER TABLE user_preferences ADD COLUMN embedding_provider VARCHAR(20) NOT NULL DEFAULT 'openai';
+ALTER TABLE user_preferences ADD COLUMN embedding_base_url TEXT;
+ALTER TABLE user_preferences ADD COLUMN embedding_model VARCHAR(200);
+ALTER TABLE user_preferences ADD COLUMN embedding_dimension INTEGER;
+
+ALTER TABLE user_preferences ADD CONSTRAINT preferences_embedding_provider_valid
+    CHECK (embedding_provider IN ('openai', 'ollama', 'hashing'));
+ALTER TABLE user_preferences ADD CONSTRAINT preferences_embedding_dimension_positive
+    CHECK (embedding_dimension IS NULL OR embedding_dimension > 0);
+
+COMMENT ON COLUMN concepts.embedding IS 'Vector embedding of "name: description"; see embedding_model and embedding_dimension';
@@ -0,0 +1,24 @@
+-- Migration 013: Embedding cache
+-- Vectors keyed by model, dimension and the SHA-256 of the embedded text, so
+-- unchanged text is never sent to an embedding provider twice
+
+CREATE TABLE embedding_cache (
+    model VARCHAR(200) NOT NULL,
+    dimension INTEGER NOT NULL,
+    text_hash CHAR(64) NOT NULL,
+    embedding REAL[] NOT NULL,
+    hit_count BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (model, dimension, text_hash),
+    CONSTRAINT embedding_cache_dimension_matches CHECK (array_length(embedding, 1) = dimension)
+);
+
+-- Lookup totals per model, including lookups that missed
+CREATE TABLE embedding_cache_stats (
+    model VARCHAR(200) PRIMARY KEY,
+    hits BIGINT NOT NULL DEFAULT 0,
+    misses BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,33 @@
+-- Migration 014: Re-embedding jobs
+-- Re-embeds every concept with the configured model after a model or dimension
+-- change. New vectors are staged per job and swapped into concepts at once.
+
+CREATE TABLE embedding_jobs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    model VARCHAR(200) NOT NULL,
+    dimension INTEGER NOT NULL CHECK (dimension > 0),
+    status VARCHAR(20) NOT NULL DEFAULT 'running'
+        CHECK (status IN ('running', 'complete', 'failed', 'cancelled')),
+    total_items INTEGER NOT NULL DEFAULT 0,
+    processed_items INTEGER NOT NULL DEFAULT 0,
+    error_message TEXT,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+-- At most one job runs at a time
+CREATE UNIQUE INDEX idx_embedding_jobs_single_running ON embedding_jobs ((TRUE)) WHERE status = 'running';
+CREATE INDEX idx_embedding_jobs_started_at ON embedding_jobs(started_at DESC);
+
+-- Vectors computed by a job but not yet visible to searches. Plain arrays so
+-- they can be staged before the concepts.embedding column changes dimension.
+CREATE TABLE concept_embedding_staging (
+    job_id UUID NOT NULL REFERENCES embedding_jobs(id) ON DELETE CASCADE,
+    concept_id UUID NOT NULL REFERENCES concepts(id) ON DELETE CASCADE,
+    embedding REAL[] NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (job_id, concept_id)
+);
+
+CREATE INDEX idx_concept_embedding_staging_concept ON concept_embedding_staging(concept_id);
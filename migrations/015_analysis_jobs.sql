@@ -0,0 +1,16 @@
+-- Migration 015: Analysis job queue
+-- Concept extraction runs in the background, one job per langraph_processing row.
+-- Jobs wait as 'pending' in creation order and started_at is set once they run.
+
+ALTER TABLE langraph_processing ADD COLUMN created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW();
+ALTER TABLE langraph_processing ADD COLUMN result JSONB;
+
+ALTER TABLE langraph_processing ALTER COLUMN started_at DROP NOT NULL;
+ALTER TABLE langraph_processing ALTER COLUMN started_at DROP DEFAULT;
+UPDATE langraph_processing SET processing_stages = '[]'::jsonb WHERE processing_stages IS NULL;
+ALTER TABLE langraph_processing ALTER COLUMN processing_stages SET NOT NULL;
+
+-- A chat session is queued or analyzed by at most one job at a time
+CREATE UNIQUE INDEX idx_langraph_processing_active_chat ON langraph_processing(chat_session_id)
+    WHERE status IN ('pending', 'processing');
+CREATE INDEX idx_langraph_processing_created_at ON langraph_processing(created_at);
@@ -0,0 +1,8 @@
+-- Migration 016: Analysis recovery preference
+-- What startup does with analyses interrupted when the app quit:
+-- mark them failed ('fail') or put them back in the queue ('requeue')
+
+ALTER TABLE user_preferences ADD COLUMN interrupted_analysis_action VARCHAR(10) NOT NULL DEFAULT 'fail';
+
+ALTER TABLE user_preferences ADD CONSTRAINT preferences_interrupted_analysis_action_valid
+    CHECK (interrupted_analysis_action IN ('fail', 'requeue'));
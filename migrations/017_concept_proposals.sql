@@ -0,0 +1,33 @@
+-- Migration 017: Concept proposals
+-- With review turned on, an analysis stores the concepts it extracted as proposals
+-- that are only added to the knowledge base once the user accepts them
+
+ALTER TABLE user_preferences ADD COLUMN review_extracted_concepts BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE concept_proposals (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    analysis_job_id UUID NOT NULL REFERENCES langraph_processing(id) ON DELETE CASCADE,
+    chat_session_id UUID NOT NULL REFERENCES chat_sessions(id) ON DELETE CASCADE,
+    name VARCHAR(500) NOT NULL,
+    description TEXT NOT NULL,
+    tags JSONB NOT NULL DEFAULT '[]',
+    confidence_score FLOAT NOT NULL DEFAULT 0.5,
+    related_concepts JSONB NOT NULL DEFAULT '[]',
+    embedding REAL[],
+    embedding_model VARCHAR(200),
+    -- The existing concept to merge into; NULL proposes a new concept
+    target_concept_id UUID REFERENCES concepts(id) ON DELETE SET NULL,
+    similarity_score FLOAT,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    -- The concept an accepted proposal was stored as or merged into
+    concept_id UUID REFERENCES concepts(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    reviewed_at TIMESTAMP WITH TIME ZONE,
+
+    CONSTRAINT concept_proposals_status_valid CHECK (status IN ('pending', 'accepted', 'rejected')),
+    CONSTRAINT concept_proposals_confidence_range CHECK (confidence_score BETWEEN 0.0 AND 1.0)
+);
+
+CREATE INDEX idx_concept_proposals_status ON concept_proposals(status, created_at);
+CREATE INDEX idx_concept_proposals_chat_session_id ON concept_proposals(chat_session_id);
+CREATE INDEX idx_concept_proposals_analysis_job_id ON concept_proposals(analysis_job_id);
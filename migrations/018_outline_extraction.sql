@@ -0,0 +1,10 @@
+-- Migration 018: Outline extraction marker
+-- Records when a document's outline was last extracted, so a PDF without
+-- bookmarks isn't parsed again every time its outline is requested
+
+ALTER TABLE documents ADD COLUMN outline_extracted_at TIMESTAMP WITH TIME ZONE;
+
+UPDATE documents d SET outline_extracted_at = NOW()
+WHERE EXISTS (SELECT 1 FROM document_outline o WHERE o.document_id = d.id);
+
+COMMENT ON COLUMN documents.outline_extracted_at IS 'When the outline was extracted from the PDF (NULL until it has been tried)';
@@ -0,0 +1,17 @@
+-- Migration 019: Optional pgvector
+-- Migrating with `genius_reads.use_pgvector` set to 'off' stores concept embeddings
+-- as REAL[] and removes the vector extension, for machines where pgvector can't
+-- stay installed; the app then compares embeddings with an in-memory index.
+-- Without the setting nothing changes.
+DO $$
+BEGIN
+    IF COALESCE(current_setting('genius_reads.use_pgvector', true), '') = 'off'
+       AND EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'vector') THEN
+        DROP INDEX IF EXISTS idx_concepts_embedding_hnsw;
+        -- Unused since similarity queries are built in the app; its signature needs pgvector
+        EXECUTE 'DROP FUNCTION IF EXISTS find_similar_concepts(VECTOR, FLOAT, INTEGER)';
+        ALTER TABLE concepts ALTER COLUMN embedding TYPE REAL[] USING embedding::REAL[];
+        DROP EXTENSION vector;
+        RAISE NOTICE 'pgvector turned off; concepts.embedding is now REAL[]';
+    END IF;
+END $$;
@@ -0,0 +1,12 @@
+-- Migration 020: Concept proposal decisions
+-- Stores whether a proposal merges into an existing concept, so one whose target
+-- concept was deleted isn't mistaken for a proposal of a new concept
+
+ALTER TABLE concept_proposals ADD COLUMN decision VARCHAR(10) NOT NULL DEFAULT 'new';
+
+UPDATE concept_proposals SET decision = 'merge' WHERE target_concept_id IS NOT NULL;
+
+ALTER TABLE concept_proposals ADD CONSTRAINT concept_proposals_decision_valid
+    CHECK (decision IN ('new', 'merge'));
+
+COMMENT ON COLUMN concept_proposals.decision IS '''merge'' into target_concept_id, or a ''new'' concept; a merge whose target was deleted has no target_concept_id';
@@ -68,7 +68,9 @@ def parse_input_data(state: ConceptExtractionState) -> ConceptExtractionState:
             doc_title = context.get('document_title', 'Unknown Document')
             page_num = context.get('page_number', 0)
             selected_text = context.get('selected_text', '')
-            context_content.append(f"From '{doc_title}' (page {page_num}): {selected_text}")
+            section = context.get('section_title')
+            location = f"page {page_num}, section '{section}'" if section else f"page {page_num}"
+            context_content.append(f"From '{doc_title}' ({location}): {selected_text}")
         
         # Combine all content
         raw_content_parts = []
@@ -370,6 +372,7 @@ def test_concept_extraction():
             {
                 'document_title': 'AI Textbook',
                 'page_number': 15,
+                'section_title': 'Chapter 1 > Types of Learning',
                 'selected_text': 'Machine learning algorithms can be categorized into supervised learning, unsupervised learning, and reinforcement learning.'
             }
         ]
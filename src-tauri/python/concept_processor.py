@@ -22,7 +22,7 @@ from pathlib import Path
 from dotenv import load_dotenv
 
 from concept_similarity import find_best_concept_match, ConceptMatch
-from vector_embeddings import generate_concept_embedding, embedding_to_pgvector_format
+from vector_embeddings import embedding_to_pgvector_format
 
 # Configure logging
 logging.basicConfig(level=logging.INFO)
@@ -86,6 +86,8 @@ def process_and_store_concepts(chat_session_id: str, new_concepts_json: str, exi
         "success": False,
         "newConceptsCreated": 0,
         "conceptsLinked": 0,
+        # Extracted concept name -> id of the concept it was stored as or linked to
+        "conceptIds": {},
         "errorMessage": None
     }
 
@@ -93,6 +95,15 @@ def process_and_store_concepts(chat_session_id: str, new_concepts_json: str, exi
     try:
         conn = get_db_connection()
         with conn.cursor() as cur:
+            # Without the pgvector extension embeddings are stored as plain arrays
+            cur.execute(
+                """
+                SELECT format_type(atttypid, atttypmod) FROM pg_attribute
+                WHERE attrelid = 'concepts'::regclass AND attname = 'embedding';
+                """
+            )
+            stores_pgvector = cur.fetchone()[0].startswith('vector')
+
             for concept in new_concepts:
                 best_match = find_best_concept_match(concept, existing_concepts)
                 
@@ -110,34 +121,36 @@ def process_and_store_concepts(chat_session_id: str, new_concepts_json: str, exi
                         (best_match.existing_concept_id, chat_session_id, best_match.similarity_score)
                     )
 
-                    # Also, increment the source_chat_count for the existing concept
-                    cur.execute(
-                        """
-                        UPDATE concepts
-                        SET 
-                            source_chat_count = source_chat_count + 1,
-                            updated_at = NOW()
-                        WHERE id = %s;
-                        """,
-                        (best_match.existing_concept_id,)
-                    )
+                    # Count the chat only once, however many extracted concepts match the concept
+                    if cur.rowcount > 0:
+                        cur.execute(
+                            """
+                            UPDATE concepts
+                            SET 
+                                source_chat_count = source_chat_count + 1,
+                                updated_at = NOW()
+                            WHERE id = %s;
+                            """,
+                            (best_match.existing_concept_id,)
+                        )
                     
                     results["conceptsLinked"] += 1
+                    results["conceptIds"][concept.get('name')] = str(best_match.existing_concept_id)
                 else:
                     # No strong match, create a new concept
                     logger.info(f"Creating new concept: '{concept.get('name')}'")
                     
-                    # Generate embedding for the new concept
-                    embedding = generate_concept_embedding(
-                        concept.get('name', ''),
-                        concept.get('description', '')
-                    )
-                    embedding_str = embedding_to_pgvector_format(embedding) if embedding else None
+                    # The embedding (if any) was computed by the Rust embedding provider
+                    embedding = concept.get('embedding')
+                    embedding_dimension = concept.get('embedding_dimension')
+                    embedding_str = embedding_to_pgvector_format(embedding, embedding_dimension) if embedding else None
+                    has_embedding = embedding_str not in (None, "[]")
+                    stored_embedding = (embedding_str if stores_pgvector else embedding) if has_embedding else None
 
                     cur.execute(
                         """
-                        INSERT INTO concepts (name, description, tags, confidence_score, embedding, source_chat_count, created_at, updated_at)
-                        VALUES (%s, %s, %s, %s, %s, 1, NOW(), NOW())
+                        INSERT INTO concepts (name, description, tags, confidence_score, embedding, embedding_model, embedding_dimension, source_chat_count, created_at, updated_at)
+                        VALUES (%s, %s, %s, %s, %s, %s, %s, 1, NOW(), NOW())
                         RETURNING id;
                         """,
                         (
@@ -145,7 +158,9 @@ def process_and_store_concepts(chat_session_id: str, new_concepts_json: str, exi
                             concept.get('description'),
                             json.dumps(concept.get('tags', [])),
                             concept.get('confidence_score', 0.5),
-                            embedding_str
+                            stored_embedding,
+                            concept.get('embedding_model') if has_embedding else None,
+                            embedding_dimension if has_embedding else None
                         )
                     )
                     new_concept_id = cur.fetchone()[0]
@@ -159,6 +174,7 @@ def process_and_store_concepts(chat_session_id: str, new_concepts_json: str, exi
                         (new_concept_id, chat_session_id, concept.get('confidence_score', 0.5))
                     )
                     results["newConceptsCreated"] += 1
+                    results["conceptIds"][concept.get('name')] = str(new_concept_id)
 
             conn.commit()
             results["success"] = True
@@ -167,6 +183,7 @@ def process_and_store_concepts(chat_session_id: str, new_concepts_json: str, exi
     except Exception as e:
         logger.error(f"An error occurred during concept processing: {e}", exc_info=True)
         results["errorMessage"] = str(e)
+        results["conceptIds"] = {}
         if conn:
             conn.rollback()
     finally:
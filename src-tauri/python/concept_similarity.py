@@ -10,7 +10,6 @@ from typing import List, Dict, Any, Optional
 from dataclasses import dataclass
 
 from vector_embeddings import (
-    generate_concept_embedding,
     calculate_similarity,
     validate_embedding
 )
@@ -63,8 +62,10 @@ def find_best_concept_match(
     
     Args:
         new_concept: The newly extracted concept to match against.
-                     Expected keys: 'name', 'description'.
-        existing_concepts: A list of existing concepts from the database.
+                     Expected keys: 'name', 'description', and 'embedding' as
+                     computed by the Rust embedding provider.
+        existing_concepts: A list of existing concepts from the database,
+                           embedded by the same model.
                            Expected keys: 'id', 'name', 'embedding'.
         
     Returns:
@@ -73,13 +74,10 @@ def find_best_concept_match(
     logger.info(f"Finding best match for new concept: '{new_concept.get('name', 'Unknown')}'")
     
     try:
-        new_embedding = generate_concept_embedding(
-            new_concept.get('name', ''),
-            new_concept.get('description', '')
-        )
+        new_embedding = new_concept.get('embedding')
         
         if not new_embedding:
-            logger.warning("Failed to generate embedding for the new concept. Cannot find matches.")
+            logger.warning("New concept has no embedding. Cannot find matches.")
             return None
         
         best_match: Optional[ConceptMatch] = None
@@ -87,7 +85,7 @@ def find_best_concept_match(
         
         for existing_concept in existing_concepts:
             existing_embedding = existing_concept.get('embedding')
-            if not existing_embedding or not validate_embedding(existing_embedding):
+            if not existing_embedding or not validate_embedding(existing_embedding, len(new_embedding)):
                 continue
             
             similarity = calculate_similarity(new_embedding, existing_embedding)
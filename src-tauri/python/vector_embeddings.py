@@ -294,18 +294,19 @@ def normalize_embedding(embedding: List[float]) -> List[float]:
         logger.error(f"Error normalizing embedding: {str(e)}")
         return embedding
 
-def embedding_to_pgvector_format(embedding: List[float]) -> str:
+def embedding_to_pgvector_format(embedding: List[float], expected_dim: int = 1536) -> str:
     """
     Convert embedding to pgvector format string
     
     Args:
         embedding: Embedding vector
+        expected_dim: Dimension reported by the model that produced the embedding
         
     Returns:
         String in pgvector format: '[1.0,2.0,3.0]'
     """
     try:
-        if not validate_embedding(embedding):
+        if not validate_embedding(embedding, expected_dim):
             logger.warning("Invalid embedding provided for pgvector conversion")
             return "[]"
         
@@ -0,0 +1,125 @@
+"""
+GeniusReads Python Worker
+
+Runs the concept pipeline in a process of its own, supervised by the Rust backend,
+so a crash or hang in Python can't take the app down with it. Requests and
+responses are JSON-RPC 2.0 messages, one per line, on stdin and stdout.
+
+Anything the pipeline prints goes to stderr, which the backend logs, so it can't
+corrupt the protocol.
+"""
+
+import os
+import sys
+import json
+import inspect
+import logging
+import platform
+import traceback
+
+# Keep the real stdout for protocol messages only
+protocol_out = sys.stdout
+sys.stdout = sys.stderr
+
+logging.basicConfig(level=logging.INFO, stream=sys.stderr)
+logger = logging.getLogger("worker")
+
+sys.path.insert(0, os.path.dirname(os.path.abspath(__file__)))
+
+# A missing or conflicting dependency is reported by `ping` instead of crashing the
+# worker, so the backend sees the cause rather than a restart loop
+try:
+    import concept_extractor
+    import concept_processor
+    import concept_similarity  # noqa: F401
+    import vector_embeddings  # noqa: F401
+    import_error = None
+except Exception:
+    import_error = traceback.format_exc()
+    logger.error(f"Failed to import the concept pipeline:\n{import_error}")
+
+# JSON-RPC error codes
+PARSE_ERROR = -32700
+INVALID_REQUEST = -32600
+METHOD_NOT_FOUND = -32601
+INVALID_PARAMS = -32602
+PIPELINE_ERROR = -32000
+
+
+def ping() -> dict:
+    """Health check; fails when the pipeline couldn't be imported."""
+    if import_error:
+        raise RuntimeError(f"Failed to import the concept pipeline: {import_error.strip().splitlines()[-1]}")
+    return {"pid": os.getpid(), "python": platform.python_version()}
+
+
+def extract_concepts(input: dict, openai_api_key: str) -> dict:
+    os.environ["OPENAI_API_KEY"] = openai_api_key
+    return json.loads(concept_extractor.extract_concepts_from_chat(input))
+
+
+def process_and_store_concepts(chat_session_id: str, new_concepts: list, existing_concepts: list) -> dict:
+    return json.loads(concept_processor.process_and_store_concepts(
+        chat_session_id, json.dumps(new_concepts), json.dumps(existing_concepts)
+    ))
+
+
+METHODS = {
+    "ping": ping,
+    "extract_concepts": extract_concepts,
+    "process_and_store_concepts": process_and_store_concepts,
+}
+
+
+def error_response(request_id, code: int, message: str, data=None) -> dict:
+    error = {"code": code, "message": message}
+    if data is not None:
+        error["data"] = data
+    return {"jsonrpc": "2.0", "id": request_id, "error": error}
+
+
+def handle(line: str) -> dict:
+    try:
+        request = json.loads(line)
+    except json.JSONDecodeError as e:
+        return error_response(None, PARSE_ERROR, f"Parse error: {e}")
+
+    request_id = request.get("id") if isinstance(request, dict) else None
+    if not isinstance(request, dict) or not isinstance(request.get("method"), str):
+        return error_response(request_id, INVALID_REQUEST, "Invalid request")
+
+    method = METHODS.get(request["method"])
+    if method is None:
+        return error_response(request_id, METHOD_NOT_FOUND, f"Method not found: {request['method']}")
+
+    params = request.get("params") or {}
+    if not isinstance(params, dict):
+        return error_response(request_id, INVALID_PARAMS, "Params must be an object")
+
+    try:
+        inspect.signature(method).bind(**params)
+    except TypeError as e:
+        return error_response(request_id, INVALID_PARAMS, f"Invalid params: {e}")
+
+    try:
+        result = method(**params)
+    except Exception as e:
+        logger.error(f"{request['method']} failed: {e}", exc_info=True)
+        return error_response(request_id, PIPELINE_ERROR, str(e), traceback.format_exc())
+
+    return {"jsonrpc": "2.0", "id": request_id, "result": result}
+
+
+def main():
+    logger.info(f"Python worker {os.getpid()} ready")
+    for line in sys.stdin:
+        if not line.strip():
+            continue
+        response = handle(line)
+        protocol_out.write(json.dumps(response) + "\n")
+        protocol_out.flush()
+    logger.info(f"Python worker {os.getpid()} stopping")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,356 @@
+// Background concept analysis. Chat sessions are queued as `langraph_processing` rows
+// and analyzed one at a time off the command path, so the database and the rest of the
+// app stay usable while the language model runs.
+use anyhow::{anyhow, bail, Result};
+use std::collections::{HashMap, HashSet};
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::database::analysis_jobs::AnalysisStage;
+use crate::database::concept_proposals::NewConceptProposal;
+use crate::database::concept_reconcile::{extracted_embedding, match_linked_concepts, same_concept, ReextractedConcept};
+use crate::database::concepts::ConceptForMatching;
+use crate::database::{AnalysisConceptChange, AnalysisDiff, AnalysisJob, Database};
+use crate::embeddings::{concept_embedding_provider, concept_embedding_text};
+use crate::langraph_bridge::{ChatMessageForExtraction, ConceptExtractionInput, HighlightedContextForExtraction};
+use crate::state::{DbState, LangGraphState};
+
+/// Emitted with the job's `AnalysisJob` when it starts and at every stage
+pub const ANALYSIS_PROGRESS_EVENT: &str = "analysis-progress";
+/// Emitted when a job completes, fails or is cancelled, with its final `AnalysisJob`
+pub const ANALYSIS_COMPLETE_EVENT: &str = "analysis-complete";
+
+/// Only one task works through the queue at a time
+static RUN_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Work through the pending jobs in the background, oldest first
+pub fn spawn_worker<R: Runtime>(app: AppHandle<R>, database: Database, langraph: LangGraphState) {
+    tauri::async_runtime::spawn(async move {
+        let _running = RUN_LOCK.lock().await;
+
+        loop {
+            let job = match database.claim_next_analysis_job().await {
+                Ok(Some(job)) => job,
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::error!("Failed to take the next analysis job: {}", e);
+                    break;
+                }
+            };
+            let _ = app.emit(ANALYSIS_PROGRESS_EVENT, &job);
+
+            if let Err(e) = run_job(&app, &database, &langraph, &job).await {
+                tracing::error!("Analysis of chat session {} failed: {}", job.chat_session_id, e);
+                if let Err(e) = database.fail_analysis_job(job.id, &e.to_string()).await {
+                    tracing::error!("Failed to record analysis failure: {}", e);
+                }
+            }
+
+            match database.get_analysis_job(job.id).await {
+                Ok(Some(job)) => {
+                    let _ = app.emit(ANALYSIS_COMPLETE_EVENT, &job);
+                }
+                Ok(None) => {}
+                Err(e) => tracing::error!("Failed to load analysis job {}: {}", job.id, e),
+            }
+        }
+    });
+}
+
+/// Mark failed or requeue the analyses a previous run of the app didn't finish,
+/// as the user's preference says. Runs at startup, before any worker.
+pub async fn recover_interrupted(database: &Database) {
+    let action = match database.get_interrupted_analysis_action().await {
+        Ok(action) => action,
+        Err(e) => {
+            tracing::error!("Failed to read the interrupted analysis preference: {}", e);
+            return;
+        }
+    };
+
+    match database.recover_interrupted_analyses(action).await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("Recovered {} interrupted analyses ({})", count, action.as_str()),
+        Err(e) => tracing::error!("Failed to recover interrupted analyses: {}", e),
+    }
+}
+
+/// Work through jobs left in the queue once both the database and the LangGraph bridge
+/// are available. Called after each of them initializes, so whichever is last starts it.
+pub async fn start_queued<R: Runtime>(app: AppHandle<R>, db_state: &DbState, langraph: &LangGraphState) {
+    if langraph.lock().await.is_none() {
+        return;
+    }
+    let database = db_state.lock().await.clone();
+    if let Some(database) = database {
+        spawn_worker(app, database, langraph.clone());
+    }
+}
+
+/// Record the stage a job reached and report it. Returns false once the job was cancelled.
+async fn enter_stage<R: Runtime>(app: &AppHandle<R>, database: &Database, job_id: Uuid, stage: AnalysisStage) -> Result<bool> {
+    match database.record_analysis_stage(job_id, stage).await? {
+        Some(job) => {
+            let _ = app.emit(ANALYSIS_PROGRESS_EVENT, &job);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+async fn run_job<R: Runtime>(
+    app: &AppHandle<R>,
+    database: &Database,
+    langraph: &LangGraphState,
+    job: &AnalysisJob,
+) -> Result<()> {
+    let session_id = job.chat_session_id;
+
+    if !enter_stage(app, database, job.id, AnalysisStage::Loading).await? {
+        return Ok(());
+    }
+    let chat_session = database
+        .get_chat_session_for_analysis(session_id)
+        .await?
+        .ok_or_else(|| anyhow!("Chat session not found"))?;
+
+    let extraction_input = ConceptExtractionInput {
+        chat_session_id: session_id.to_string(),
+        messages: chat_session.messages.into_iter().map(|msg| ChatMessageForExtraction {
+            content: msg.content,
+            sender_type: msg.sender_type,
+            created_at: msg.created_at.to_rfc3339(),
+        }).collect(),
+        highlighted_contexts: chat_session.highlighted_contexts.into_iter().map(|ctx| HighlightedContextForExtraction {
+            document_title: ctx.document_title,
+            page_number: ctx.page_number,
+            selected_text: ctx.selected_text,
+            section_title: ctx.section_title,
+        }).collect(),
+    };
+
+    let openai_api_key = database
+        .get_user_preferences()
+        .await?
+        .and_then(|prefs| prefs.get("openai_api_key").and_then(|key| key.as_str().map(|s| s.to_string())))
+        .ok_or_else(|| anyhow!("OpenAI API key not configured. Please set your API key in Preferences."))?;
+
+    // A copy of the bridge, so the state isn't locked while Python runs
+    let bridge = langraph
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| anyhow!("LangGraph bridge not initialized. Please restart the application."))?;
+
+    if !enter_stage(app, database, job.id, AnalysisStage::Extracting).await? {
+        return Ok(());
+    }
+    // Python holds the GIL for the whole call, so it runs on a blocking thread
+    let extraction_result = {
+        let bridge = bridge.clone();
+        tauri::async_runtime::spawn_blocking(move || bridge.extract_concepts(&extraction_input, &openai_api_key)).await??
+    };
+
+    let success = extraction_result.get("success").and_then(|v| v.as_bool()).unwrap_or(false);
+    if !success {
+        let error_message = extraction_result
+            .get("error_message")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Unknown error during concept extraction");
+        bail!("Concept extraction failed: {}", error_message);
+    }
+
+    if !enter_stage(app, database, job.id, AnalysisStage::Embedding).await? {
+        return Ok(());
+    }
+    let mut new_concepts = extraction_result
+        .get("concepts")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    // Fetch the existing concepts embedded by the same model that the new concepts could
+    // match: the closest one to each, and the ones this chat is already linked to
+    let linked = database.get_linked_concepts(session_id).await?;
+    let linked_ids: HashSet<Uuid> = linked.iter().map(|concept| concept.id).collect();
+    let existing_concepts = match embed_new_concepts(database, &mut new_concepts).await {
+        Some(model) => {
+            let embeddings: Vec<Vec<f32>> = new_concepts
+                .iter()
+                .filter_map(|concept| extracted_embedding(concept).map(|embedding| embedding.vector))
+                .collect();
+            database.get_concepts_for_matching(&model, &embeddings, &linked_ids).await?
+        }
+        None => vec![],
+    };
+
+    if !enter_stage(app, database, job.id, AnalysisStage::Storing).await? {
+        return Ok(());
+    }
+    // Concepts this chat is already linked to from an earlier analysis are updated in
+    // place; only the rest go to the processor to be linked or created
+    let matched = match_linked_concepts(&new_concepts, &linked, &existing_concepts);
+    if database.get_review_extracted_concepts().await? {
+        return propose_concepts(database, job, &new_concepts, &matched, &existing_concepts).await;
+    }
+    let mut concept_ids: HashMap<String, Uuid> = HashMap::new();
+    let mut reextracted = Vec::new();
+    let mut to_store = Vec::new();
+    for (concept, linked_id) in new_concepts.iter().zip(matched) {
+        match linked_id {
+            Some(concept_id) => {
+                let name = concept.get("name").and_then(|v| v.as_str()).unwrap_or_default();
+                concept_ids.insert(name.to_string(), concept_id);
+                reextracted.push(ReextractedConcept::from_extracted(concept_id, concept));
+            }
+            None => to_store.push(concept.clone()),
+        }
+    }
+
+    let processing_result = tauri::async_runtime::spawn_blocking(move || {
+        bridge.process_concepts_and_save(&session_id, &to_store, &existing_concepts)
+    })
+    .await??;
+
+    if !processing_result.success {
+        let py_error = processing_result.error_message.unwrap_or_else(|| "Unknown Python error".to_string());
+        bail!("Concept processing failed in Python: {}", py_error);
+    }
+
+    // The processor may also have linked a concept to one the chat already had
+    let mut diff = AnalysisDiff::default();
+    for (name, concept_id) in &processing_result.concept_ids {
+        if !linked_ids.contains(concept_id) && !diff.added.iter().any(|c| c.concept_id == *concept_id) {
+            diff.added.push(AnalysisConceptChange { concept_id: *concept_id, name: name.clone() });
+        }
+    }
+    concept_ids.extend(processing_result.concept_ids.clone());
+    let supported: HashSet<Uuid> = concept_ids.values().copied().collect();
+    let unsupported: Vec<Uuid> = linked_ids.difference(&supported).copied().collect();
+    (diff.updated, diff.unlinked) = database
+        .reconcile_chat_concepts(session_id, &reextracted, &unsupported)
+        .await?;
+
+    // Concepts written by the Python processor still need to be searchable
+    if let Err(e) = database.index_concepts_for_chat_session(session_id).await {
+        tracing::warn!("Failed to index concepts for chat session {}: {}", session_id, e);
+    }
+    if let Err(e) = database.sync_vector_index().await {
+        tracing::warn!("Failed to update the concept similarity index: {}", e);
+    }
+    let relationships_created = store_related_concepts(database, &new_concepts, &concept_ids).await;
+
+    // End the chat session (mark as inactive)
+    database.end_chat_session(session_id).await?;
+
+    let result = serde_json::json!({
+        "newConceptsCreated": processing_result.new_concepts_created,
+        "conceptsLinked": processing_result.concepts_linked,
+        "relationshipsCreated": relationships_created,
+        "changes": diff
+    });
+    database.complete_analysis_job(job.id, new_concepts.len() as i32, &result).await?;
+
+    Ok(())
+}
+
+/// Store the extracted concepts for review instead of saving them. Each is proposed as
+/// a merge into the linked concept it matched, or into the existing concept it is most
+/// like, or else as a new concept. Links to the chat session are left as they are.
+async fn propose_concepts(
+    database: &Database,
+    job: &AnalysisJob,
+    new_concepts: &[serde_json::Value],
+    matched: &[Option<Uuid>],
+    existing_concepts: &[ConceptForMatching],
+) -> Result<()> {
+    let proposals: Vec<NewConceptProposal> = new_concepts
+        .iter()
+        .zip(matched)
+        .map(|(concept, linked_id)| {
+            let target = match linked_id {
+                Some(concept_id) => Some((*concept_id, None)),
+                None => extracted_embedding(concept)
+                    .and_then(|embedding| same_concept(&embedding.vector, existing_concepts))
+                    .map(|(concept_id, similarity)| (concept_id, Some(similarity as f64))),
+            };
+            NewConceptProposal::from_extracted(concept, target)
+        })
+        .filter(|proposal| !proposal.name.is_empty())
+        .collect();
+
+    let proposals_created = database.create_concept_proposals(job.id, job.chat_session_id, &proposals).await?;
+    database.end_chat_session(job.chat_session_id).await?;
+
+    let result = serde_json::json!({ "proposalsCreated": proposals_created });
+    database.complete_analysis_job(job.id, new_concepts.len() as i32, &result).await?;
+
+    Ok(())
+}
+
+/// Attach an embedding of "name: description" to every new concept, together with the
+/// model and dimension that produced it. Returns the model name, or `None` when the
+/// concepts have to be saved without embeddings.
+async fn embed_new_concepts(database: &Database, concepts: &mut [serde_json::Value]) -> Option<String> {
+    let provider = match concept_embedding_provider(database).await {
+        Ok(provider) => provider,
+        Err(e) => {
+            tracing::warn!("Saving concepts without embeddings: {}", e);
+            return None;
+        }
+    };
+
+    let texts: Vec<String> = concepts
+        .iter()
+        .map(|concept| {
+            let field = |key: &str| concept.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+            concept_embedding_text(field("name"), field("description"))
+        })
+        .collect();
+
+    match provider.embed(&texts).await {
+        Ok(embeddings) => {
+            for (concept, embedding) in concepts.iter_mut().zip(embeddings) {
+                concept["embedding"] = serde_json::json!(embedding);
+                concept["embedding_model"] = serde_json::json!(provider.model());
+                concept["embedding_dimension"] = serde_json::json!(provider.dimension());
+            }
+            Some(provider.model().to_string())
+        }
+        Err(e) => {
+            tracing::warn!("Saving concepts without embeddings: {}", e);
+            None
+        }
+    }
+}
+
+/// Store the `related_concepts` named by each extracted concept as relationships
+/// between the stored concepts. Returns the number of relationships added.
+async fn store_related_concepts(
+    database: &Database,
+    concepts: &[serde_json::Value],
+    concept_ids: &HashMap<String, Uuid>,
+) -> usize {
+    let known: HashMap<String, Uuid> = concept_ids
+        .iter()
+        .map(|(name, id)| (name.trim().to_lowercase(), *id))
+        .collect();
+
+    let mut created = 0;
+    for concept in concepts {
+        let name = concept.get("name").and_then(|v| v.as_str()).unwrap_or_default();
+        let Some(&concept_id) = known.get(&name.trim().to_lowercase()) else {
+            continue;
+        };
+        let related: Vec<String> = concept
+            .get("related_concepts")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default();
+        match database.link_related_concepts(concept_id, &related, &known).await {
+            Ok(added) => created += added,
+            Err(e) => tracing::warn!("Failed to store relationships of concept {}: {}", name, e),
+        }
+    }
+    created
+}
@@ -0,0 +1,162 @@
+// Review commands for concepts proposed by analyses
+use crate::commands::concepts::embed_concepts;
+use crate::database::concept_edits::ConceptUpdate;
+use crate::state::DbState;
+use serde_json;
+
+/// Proposals oldest first, optionally only those with a status ('pending', 'accepted'
+/// or 'rejected') or from one chat session
+#[tauri::command]
+pub async fn list_concept_proposals(
+    status: Option<String>,
+    chat_session_id: Option<String>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let chat_session_id = chat_session_id
+        .map(|id| uuid::Uuid::parse_str(&id))
+        .transpose()
+        .map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.list_concept_proposals(status.as_deref(), chat_session_id).await {
+            Ok(proposals) => Ok(serde_json::to_value(proposals).unwrap()),
+            Err(e) => Err(format!("Failed to list concept proposals: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Add a pending proposal to the knowledge base as it stands
+#[tauri::command]
+pub async fn accept_concept_proposal(
+    proposal_id: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let id = uuid::Uuid::parse_str(&proposal_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.accept_concept_proposal(id).await {
+            Ok(Some(proposal)) => Ok(serde_json::to_value(proposal).unwrap()),
+            Ok(None) => Err("Concept proposal not found".to_string()),
+            Err(e) => Err(format!("Failed to accept concept proposal: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Edit a pending proposal's name, description and/or tags before accepting it
+#[tauri::command]
+pub async fn update_concept_proposal(
+    proposal_id: String,
+    changes: ConceptUpdate,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let id = uuid::Uuid::parse_str(&proposal_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let current = {
+        let db_guard = db.lock().await;
+        let database = db_guard.as_ref().ok_or_else(|| "Database not initialized".to_string())?;
+        database.get_concept_proposal(id).await
+            .map_err(|e| format!("Failed to update concept proposal: {}", e))?
+            .ok_or_else(|| "Concept proposal not found".to_string())?
+    };
+
+    // Only a new name or description needs a new embedding
+    let name = changes.name.as_deref().map(str::trim).unwrap_or(&current.name);
+    let description = changes.description.as_deref().map(str::trim).unwrap_or(&current.description);
+    let embedding = if name != current.name || description != current.description {
+        embed_concepts(db.inner(), &[(name, description)]).await.pop().flatten()
+    } else {
+        None
+    };
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.update_concept_proposal(id, &changes, embedding.as_ref()).await {
+            Ok(Some(proposal)) => Ok(serde_json::to_value(proposal).unwrap()),
+            Ok(None) => Err("Concept proposal not found".to_string()),
+            Err(e) => Err(format!("Failed to update concept proposal: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Merge a pending proposal into another existing concept, or make it a new concept
+/// when no target is given
+#[tauri::command]
+pub async fn retarget_concept_proposal(
+    proposal_id: String,
+    target_concept_id: Option<String>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let id = uuid::Uuid::parse_str(&proposal_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    let target_concept_id = target_concept_id
+        .map(|id| uuid::Uuid::parse_str(&id))
+        .transpose()
+        .map_err(|e| format!("Invalid concept UUID: {}", e))?;
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.retarget_concept_proposal(id, target_concept_id).await {
+            Ok(Some(proposal)) => Ok(serde_json::to_value(proposal).unwrap()),
+            Ok(None) => Err("Concept proposal not found".to_string()),
+            Err(e) => Err(format!("Failed to retarget concept proposal: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn reject_concept_proposal(
+    proposal_id: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let id = uuid::Uuid::parse_str(&proposal_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.reject_concept_proposal(id).await {
+            Ok(Some(proposal)) => Ok(serde_json::to_value(proposal).unwrap()),
+            Ok(None) => Err("Concept proposal not found".to_string()),
+            Err(e) => Err(format!("Failed to reject concept proposal: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Whether analyses store extracted concepts for review instead of saving them
+#[tauri::command]
+pub async fn get_concept_review_enabled(
+    db: tauri::State<'_, DbState>,
+) -> Result<bool, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        database.get_review_extracted_concepts().await
+            .map_err(|e| format!("Failed to get concept review setting: {}", e))
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn save_concept_review_enabled(
+    enabled: bool,
+    db: tauri::State<'_, DbState>,
+) -> Result<(), String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.save_review_extracted_concepts(enabled).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("Failed to save concept review setting: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
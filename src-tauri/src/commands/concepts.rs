@@ -1,4 +1,8 @@
 // Concept extraction and similarity search commands
+use crate::database::concept_edits::{ConceptEmbedding, ConceptSplitPart, ConceptUpdate};
+use crate::database::concept_graph::RELATIONSHIP_TYPES;
+use crate::embeddings::{concept_embedding_provider, concept_embedding_text, provider_from_preferences};
+use crate::graph_export::{export_graph, GraphExportFormat};
 use crate::state::DbState;
 use serde_json;
 
@@ -61,6 +65,8 @@ pub async fn find_similar_concepts(
     }
 }
 
+/// Hybrid concept search: embedding similarity blended with full-text rank.
+/// Falls back to words only when no query embedding can be made.
 #[tauri::command]
 pub async fn search_concepts_by_text(
     query_text: String,
@@ -68,17 +74,35 @@ pub async fn search_concepts_by_text(
     max_results: Option<i32>,
     db: tauri::State<'_, DbState>,
 ) -> Result<serde_json::Value, String> {
-    let db_guard = db.lock().await;
-    if let Some(database) = db_guard.as_ref() {
-        let threshold = similarity_threshold.unwrap_or(0.6);
-        let limit = max_results.unwrap_or(10);
-        
-        match database.search_concepts_by_text(&query_text, threshold, limit).await {
-            Ok(matching_concepts) => Ok(serde_json::to_value(matching_concepts).unwrap()),
-            Err(e) => Err(format!("Failed to search concepts by text: {}", e)),
+    // Embedding the query is a network call, so it runs on a copy of the handle rather than under the lock
+    let Some(database) = db.lock().await.clone() else {
+        return Err("Database not initialized".to_string());
+    };
+    let threshold = similarity_threshold.unwrap_or(0.4);
+    let limit = max_results.unwrap_or(10);
+
+    let query_embedding = match provider_from_preferences(&database).await {
+        Ok(provider) => match provider.embed_one(&query_text).await {
+            Ok(embedding) => Some((provider.model().to_string(), embedding)),
+            Err(e) => {
+                tracing::warn!("Concept search falling back to full-text only: {}", e);
+                None
+            }
+        },
+        Err(e) => {
+            tracing::warn!("Concept search falling back to full-text only: {}", e);
+            None
         }
-    } else {
-        Err("Database not initialized".to_string())
+    };
+
+    match database.search_concepts_by_text(
+        &query_text,
+        query_embedding.as_ref().map(|(model, embedding)| (model.as_str(), embedding.as_slice())),
+        threshold,
+        limit,
+    ).await {
+        Ok(matching_concepts) => Ok(serde_json::to_value(matching_concepts).unwrap()),
+        Err(e) => Err(format!("Failed to search concepts by text: {}", e)),
     }
 }
 
@@ -141,4 +165,262 @@ pub async fn get_chats_for_concept(
     } else {
         Err("Database not initialized".to_string())
     }
-} 
\ No newline at end of file
+}
+
+// Concept relationship graph commands
+
+/// Reject relationship types the schema doesn't know
+fn check_relationship_types(relationship_types: &Option<Vec<String>>) -> Result<(), String> {
+    for relationship_type in relationship_types.iter().flatten() {
+        if !RELATIONSHIP_TYPES.contains(&relationship_type.as_str()) {
+            return Err(format!("Unknown relationship type: {}", relationship_type));
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_concept_neighbors(
+    concept_id: String,
+    relationship_types: Option<Vec<String>>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let concept_uuid = uuid::Uuid::parse_str(&concept_id)
+        .map_err(|e| format!("Invalid concept UUID: {}", e))?;
+    check_relationship_types(&relationship_types)?;
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.get_concept_neighbors(concept_uuid, relationship_types.as_deref()).await {
+            Ok(neighbors) => Ok(serde_json::to_value(neighbors).unwrap()),
+            Err(e) => Err(format!("Failed to get concept neighbors: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Concepts within `depth` relationships (default 2, at most 5) of a concept,
+/// capped at `max_nodes` (default 100)
+#[tauri::command]
+pub async fn get_concept_subgraph(
+    concept_id: String,
+    depth: Option<u32>,
+    max_nodes: Option<usize>,
+    relationship_types: Option<Vec<String>>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let concept_uuid = uuid::Uuid::parse_str(&concept_id)
+        .map_err(|e| format!("Invalid concept UUID: {}", e))?;
+    check_relationship_types(&relationship_types)?;
+    let depth = depth.unwrap_or(2).min(5);
+    let max_nodes = max_nodes.unwrap_or(100).max(1);
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.get_concept_subgraph(concept_uuid, depth, max_nodes, relationship_types.as_deref()).await {
+            Ok(graph) => Ok(serde_json::to_value(graph).unwrap()),
+            Err(e) => Err(format!("Failed to get concept subgraph: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Shortest relationship path between two concepts, or null if there is none
+/// within `max_depth` (default 6) steps
+#[tauri::command]
+pub async fn find_concept_path(
+    from_concept_id: String,
+    to_concept_id: String,
+    max_depth: Option<u32>,
+    relationship_types: Option<Vec<String>>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let from = uuid::Uuid::parse_str(&from_concept_id)
+        .map_err(|e| format!("Invalid concept UUID: {}", e))?;
+    let to = uuid::Uuid::parse_str(&to_concept_id)
+        .map_err(|e| format!("Invalid concept UUID: {}", e))?;
+    check_relationship_types(&relationship_types)?;
+    let max_depth = max_depth.unwrap_or(6).min(10);
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.find_concept_path(from, to, max_depth, relationship_types.as_deref()).await {
+            Ok(path) => Ok(serde_json::to_value(path).unwrap()),
+            Err(e) => Err(format!("Failed to find concept path: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Export every concept with its chats, their documents and the concept relationships
+/// as `graphml` (default), `dot` or `jsonld`
+#[tauri::command]
+pub async fn export_knowledge_graph(
+    format: Option<String>,
+    db: tauri::State<'_, DbState>,
+) -> Result<String, String> {
+    let format = GraphExportFormat::parse(format.as_deref().unwrap_or("graphml")).map_err(|e| e.to_string())?;
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.get_knowledge_graph().await {
+            Ok(graph) => Ok(export_graph(&graph, format)),
+            Err(e) => Err(format!("Failed to export knowledge graph: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Embed the (name, description) of edited concepts. Concepts that can't be embedded
+/// get `None` and stay without an embedding until the next re-embedding. The provider
+/// may call out over HTTP, so this runs without holding the database lock.
+pub(crate) async fn embed_concepts(db: &DbState, texts: &[(&str, &str)]) -> Vec<Option<ConceptEmbedding>> {
+    let Some(database) = db.lock().await.clone() else {
+        return texts.iter().map(|_| None).collect();
+    };
+    let embeddings = match concept_embedding_provider(&database).await {
+        Ok(provider) => {
+            let texts: Vec<String> = texts
+                .iter()
+                .map(|(name, description)| concept_embedding_text(name, description))
+                .collect();
+            provider.embed(&texts).await.map(|vectors| {
+                vectors
+                    .into_iter()
+                    .map(|vector| Some(ConceptEmbedding { model: provider.model().to_string(), vector }))
+                    .collect()
+            })
+        }
+        Err(e) => Err(e),
+    };
+
+    embeddings.unwrap_or_else(|e| {
+        tracing::warn!("Saving edited concepts without embeddings: {}", e);
+        texts.iter().map(|_| None).collect()
+    })
+}
+
+/// Edit a concept's name, description and/or tags
+#[tauri::command]
+pub async fn update_concept(
+    concept_id: String,
+    changes: ConceptUpdate,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let id = uuid::Uuid::parse_str(&concept_id)
+        .map_err(|e| format!("Invalid concept UUID: {}", e))?;
+
+    let current = {
+        let db_guard = db.lock().await;
+        let database = db_guard.as_ref().ok_or_else(|| "Database not initialized".to_string())?;
+        database.get_extracted_concept(id).await
+            .map_err(|e| format!("Failed to update concept: {}", e))?
+            .ok_or_else(|| "Concept not found".to_string())?
+    };
+
+    // Only a new name or description needs a new embedding
+    let (name, description) = changes.text_of(&current);
+    let embedding = if name != current.name || description != current.description {
+        embed_concepts(db.inner(), &[(name, description)]).await.pop().flatten()
+    } else {
+        None
+    };
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.update_concept(id, &changes, embedding.as_ref()).await {
+            Ok(Some(concept)) => Ok(serde_json::to_value(concept).unwrap()),
+            Ok(None) => Err("Concept not found".to_string()),
+            Err(e) => Err(format!("Failed to update concept: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Merge a duplicate concept into another one, optionally editing the result
+#[tauri::command]
+pub async fn merge_concepts(
+    target_concept_id: String,
+    source_concept_id: String,
+    changes: Option<ConceptUpdate>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let target = uuid::Uuid::parse_str(&target_concept_id)
+        .map_err(|e| format!("Invalid concept UUID: {}", e))?;
+    let source = uuid::Uuid::parse_str(&source_concept_id)
+        .map_err(|e| format!("Invalid concept UUID: {}", e))?;
+    let changes = changes.unwrap_or_default();
+
+    let current = {
+        let db_guard = db.lock().await;
+        let database = db_guard.as_ref().ok_or_else(|| "Database not initialized".to_string())?;
+        database.get_extracted_concept(target).await
+            .map_err(|e| format!("Failed to merge concepts: {}", e))?
+            .ok_or_else(|| "Concept not found".to_string())?
+    };
+    let embedding = embed_concepts(db.inner(), &[changes.text_of(&current)]).await.pop().flatten();
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.merge_concepts(target, source, &changes, embedding.as_ref()).await {
+            Ok(Some(concept)) => Ok(serde_json::to_value(concept).unwrap()),
+            Ok(None) => Err("Concept not found".to_string()),
+            Err(e) => Err(format!("Failed to merge concepts: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Split a concept into several; the first part keeps the concept's id
+#[tauri::command]
+pub async fn split_concept(
+    concept_id: String,
+    parts: Vec<ConceptSplitPart>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let id = uuid::Uuid::parse_str(&concept_id)
+        .map_err(|e| format!("Invalid concept UUID: {}", e))?;
+
+    let texts: Vec<(&str, &str)> = parts
+        .iter()
+        .map(|part| (part.name.trim(), part.description.trim()))
+        .collect();
+    let embeddings = embed_concepts(db.inner(), &texts).await;
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.split_concept(id, &parts, &embeddings).await {
+            Ok(Some(concepts)) => Ok(serde_json::to_value(concepts).unwrap()),
+            Ok(None) => Err("Concept not found".to_string()),
+            Err(e) => Err(format!("Failed to split concept: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Delete a concept with its chat links and relationships
+#[tauri::command]
+pub async fn delete_concept(
+    concept_id: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<bool, String> {
+    let id = uuid::Uuid::parse_str(&concept_id)
+        .map_err(|e| format!("Invalid concept UUID: {}", e))?;
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.delete_concept(id).await {
+            Ok(deleted) => Ok(deleted),
+            Err(e) => Err(format!("Failed to delete concept: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
@@ -1,7 +1,12 @@
 // Document management commands
+use crate::database::documents::NewDocument;
+use crate::database::outline::build_outline_tree;
+use crate::database::{Document, DocumentOutlineEntry, MissingDocument, RelocatedDocument, RelocationReport};
 use crate::state::DbState;
 use crate::pdf_handler::PDFHandler;
 use serde_json;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 // PDF file operations
 #[tauri::command]
@@ -28,66 +33,104 @@ pub async fn load_pdf_document(
     file_path: String,
     db: tauri::State<'_, DbState>,
 ) -> Result<serde_json::Value, String> {
-    let db_guard = db.lock().await;
-    if let Some(database) = db_guard.as_ref() {
-        // First, check if document already exists in database
-        match database.get_document_by_path(&file_path).await {
-            Ok(Some(existing_doc)) => {
-                // Document already exists, update last_accessed and return it
-                let _ = database.update_document_position(
-                    existing_doc.id, 
-                    existing_doc.current_page, 
-                    existing_doc.zoom_level
-                ).await; // This updates last_accessed
-                
-                return Ok(serde_json::json!({
-                    "id": existing_doc.id.to_string(),
-                    "title": existing_doc.title,
-                    "filePath": existing_doc.file_path,
-                    "fileName": existing_doc.file_name,
-                    "fileSize": existing_doc.file_size,
-                    "totalPages": existing_doc.total_pages,
-                    "currentPage": existing_doc.current_page,
-                    "zoomLevel": existing_doc.zoom_level,
-                    "lastAccessed": existing_doc.last_accessed.to_rfc3339(),
-                    "createdAt": existing_doc.created_at.to_rfc3339(),
-                    "updatedAt": existing_doc.updated_at.to_rfc3339(),
-                    "metadata": existing_doc.metadata
-                }));
+    // First, check if document already exists in database
+    let existing_doc = {
+        let db_guard = db.lock().await;
+        let database = db_guard.as_ref().ok_or_else(|| "Database not initialized".to_string())?;
+        let existing_doc = database.get_document_by_path(&file_path).await
+            .map_err(|e| format!("Failed to check existing document: {}", e))?;
+        
+        if let Some(existing_doc) = &existing_doc {
+            // Document already exists, update last_accessed and return it
+            let _ = database.update_document_position(
+                existing_doc.id, 
+                existing_doc.current_page, 
+                existing_doc.zoom_level
+            ).await; // This updates last_accessed
+            
+            // Documents imported before text extraction existed get their pages now
+            if let Ok(false) = database.has_document_pages(existing_doc.id).await {
+                spawn_text_extraction(db.inner().clone(), existing_doc.id, existing_doc.file_path.clone());
             }
-            Ok(None) => {
-                // Document doesn't exist, proceed to create it
+        }
+        existing_doc
+    };
+    
+    if let Some(existing_doc) = existing_doc {
+        // Documents imported before fingerprinting get their content hash now
+        if existing_doc.content_hash.is_none() {
+            match compute_content_hash(&file_path).await {
+                Ok(hash) => {
+                    let db_guard = db.lock().await;
+                    if let Some(database) = db_guard.as_ref() {
+                        let _ = database.set_document_content_hash(existing_doc.id, &hash).await;
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to fingerprint {}: {}", file_path, e),
             }
-            Err(e) => {
-                return Err(format!("Failed to check existing document: {}", e));
+        }
+        
+        return Ok(document_to_json(&existing_doc));
+    }
+
+    // Document doesn't exist at this path, check whether it was moved
+    let content_hash = compute_content_hash(&file_path).await
+        .map_err(|e| format!("Failed to load PDF: {}", e))?;
+    
+    // A known fingerprint at a new path means the file was moved or renamed:
+    // relink the existing record so its chats and highlights are kept. A copy of
+    // a file that still exists is imported as a document of its own.
+    {
+        let db_guard = db.lock().await;
+        let database = db_guard.as_ref().ok_or_else(|| "Database not initialized".to_string())?;
+        let known_doc = database.get_document_by_content_hash(&content_hash).await
+            .map_err(|e| format!("Failed to check existing document: {}", e))?;
+        
+        if let Some(moved_doc) = known_doc.filter(|doc| !Path::new(&doc.file_path).exists()) {
+            tracing::info!("Relinking document {} from {} to {}", moved_doc.id, moved_doc.file_path, file_path);
+            
+            let relinked = database
+                .relink_document(moved_doc.id, &file_path, &file_name_of(Path::new(&file_path)), None)
+                .await
+                .map_err(|e| format!("Failed to relink moved document: {}", e))?;
+            
+            if let Ok(false) = database.has_document_pages(relinked.id).await {
+                spawn_text_extraction(db.inner().clone(), relinked.id, relinked.file_path.clone());
             }
+            
+            return Ok(document_to_json(&relinked));
         }
-    } else {
-        return Err("Database not initialized".to_string());
     }
 
     // Create PDF document from file (only for new documents)
-    let pdf_doc = match PDFHandler::create_document(&file_path) {
-        Ok(doc) => doc,
+    let owned_path = file_path.clone();
+    let pdf_doc = match tauri::async_runtime::spawn_blocking(move || PDFHandler::create_document(&owned_path)).await {
+        Ok(Ok(doc)) => doc,
+        Ok(Err(e)) => return Err(format!("Failed to load PDF: {}", e)),
         Err(e) => return Err(format!("Failed to load PDF: {}", e)),
     };
     
     // Store new document in database
+    let db_guard = db.lock().await;
     if let Some(database) = db_guard.as_ref() {
-        match database.create_document(
-            &pdf_doc.title,
-            None, // author
-            &pdf_doc.file_path,
-            &pdf_doc.file_name,
-            pdf_doc.file_size as i64,
-            pdf_doc.total_pages as i32,
-            serde_json::json!(pdf_doc.metadata),
-        ).await {
+        match database.create_document(NewDocument {
+            title: &pdf_doc.title,
+            author: pdf_doc.metadata.author.as_deref(),
+            file_path: &pdf_doc.file_path,
+            file_name: &pdf_doc.file_name,
+            file_size: pdf_doc.file_size as i64,
+            total_pages: pdf_doc.total_pages as i32,
+            metadata: serde_json::json!(pdf_doc.metadata),
+            content_hash: Some(&content_hash),
+        }).await {
             Ok(new_id) => {
+                spawn_text_extraction(db.inner().clone(), new_id, pdf_doc.file_path.clone());
+                
                 // Return document info for frontend, using the ID from the database
                 Ok(serde_json::json!({
                     "id": new_id,
                     "title": pdf_doc.title,
+                    "author": pdf_doc.metadata.author,
                     "filePath": pdf_doc.file_path,
                     "fileName": pdf_doc.file_name,
                     "fileSize": pdf_doc.file_size,
@@ -107,6 +150,75 @@ pub async fn load_pdf_document(
     }
 }
 
+/// Frontend representation of a stored document
+fn document_to_json(document: &Document) -> serde_json::Value {
+    serde_json::json!({
+        "id": document.id.to_string(),
+        "title": document.title,
+        "author": document.author,
+        "filePath": document.file_path,
+        "fileName": document.file_name,
+        "fileSize": document.file_size,
+        "totalPages": document.total_pages,
+        "currentPage": document.current_page,
+        "zoomLevel": document.zoom_level,
+        "lastAccessed": document.last_accessed.to_rfc3339(),
+        "createdAt": document.created_at.to_rfc3339(),
+        "updatedAt": document.updated_at.to_rfc3339(),
+        "metadata": document.metadata
+    })
+}
+
+/// Fingerprint a PDF on the blocking pool, since large files take a while to read
+async fn compute_content_hash(file_path: &str) -> Result<String, String> {
+    let owned_path = file_path.to_string();
+    tauri::async_runtime::spawn_blocking(move || PDFHandler::compute_content_hash(&owned_path))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "unknown.pdf".to_string())
+}
+
+/// Extract page text and the outline in the background so importing a large PDF doesn't block the reader
+pub(crate) fn spawn_text_extraction(db: DbState, document_id: uuid::Uuid, file_path: String) {
+    tauri::async_runtime::spawn(async move {
+        let path = file_path.clone();
+        // Page text and outline come from a single parse of the file
+        let (pages, outline) = match tauri::async_runtime::spawn_blocking(move || {
+            let pdf = PDFHandler::load_document(&path)?;
+            anyhow::Ok((PDFHandler::page_texts(&pdf), PDFHandler::outline(&pdf)))
+        })
+        .await
+        {
+            Ok(Ok(extracted)) => extracted,
+            Ok(Err(e)) => {
+                tracing::warn!("Text extraction failed for {}: {}", file_path, e);
+                return;
+            }
+            Err(e) => {
+                tracing::error!("Text extraction task failed for {}: {}", file_path, e);
+                return;
+            }
+        };
+
+        let db_guard = db.lock().await;
+        if let Some(database) = db_guard.as_ref() {
+            match database.store_document_pages(document_id, &pages).await {
+                Ok(_) => tracing::info!("Stored text for {} pages of {}", pages.len(), file_path),
+                Err(e) => tracing::error!("Failed to store page text for {}: {}", file_path, e),
+            }
+            if let Err(e) = database.store_document_outline(document_id, &outline).await {
+                tracing::error!("Failed to store outline for {}: {}", file_path, e);
+            }
+        }
+    });
+}
+
 #[tauri::command]
 pub async fn read_pdf_file(file_path: String) -> Result<String, String> {
     use std::fs;
@@ -175,4 +287,215 @@ pub async fn get_recent_documents(
     } else {
         Err("Database not initialized".to_string())
     }
-} 
\ No newline at end of file
+}
+
+#[tauri::command]
+pub async fn get_document_page_text(
+    document_id: String,
+    page_number: i32,
+    db: tauri::State<'_, DbState>,
+) -> Result<Option<serde_json::Value>, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let doc_id = uuid::Uuid::parse_str(&document_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+        
+        match database.get_document_page(doc_id, page_number).await {
+            Ok(page) => Ok(page.map(|p| serde_json::to_value(p).unwrap())),
+            Err(e) => Err(format!("Failed to get document page text: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Find documents whose files no longer exist and search the given folders for them.
+/// Candidates are matched by content fingerprint, or by file name and size for
+/// documents imported before fingerprints were recorded.
+#[tauri::command]
+pub async fn relocate_missing_documents(
+    search_folders: Vec<String>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let (missing, known_paths) = {
+        let db_guard = db.lock().await;
+        if let Some(database) = db_guard.as_ref() {
+            match database.get_documents().await {
+                Ok(documents) => {
+                    let known_paths: HashSet<String> =
+                        documents.iter().map(|d| d.file_path.clone()).collect();
+                    let missing: Vec<Document> = documents
+                        .into_iter()
+                        .filter(|d| !Path::new(&d.file_path).exists())
+                        .collect();
+                    (missing, known_paths)
+                }
+                Err(e) => return Err(format!("Failed to get documents: {}", e)),
+            }
+        } else {
+            return Err("Database not initialized".to_string());
+        }
+    };
+
+    // Scanning folders can take a while, so the database lock isn't held meanwhile
+    let candidates = if missing.is_empty() || search_folders.is_empty() {
+        HashMap::new()
+    } else {
+        let documents: Vec<(uuid::Uuid, String, i64, Option<String>)> = missing
+            .iter()
+            .map(|d| (d.id, d.file_name.clone(), d.file_size, d.content_hash.clone()))
+            .collect();
+        tauri::async_runtime::spawn_blocking(move || {
+            find_relocation_candidates(&documents, &search_folders, &known_paths)
+        })
+        .await
+        .map_err(|e| format!("Failed to search folders: {}", e))?
+    };
+
+    let db_guard = db.lock().await;
+    let database = db_guard.as_ref().ok_or_else(|| "Database not initialized".to_string())?;
+
+    let mut report = RelocationReport { relocated: Vec::new(), still_missing: Vec::new() };
+    for document in missing {
+        let relinked = match candidates.get(&document.id) {
+            Some((path, hash)) => {
+                let new_path = path.to_string_lossy().to_string();
+                match database.relink_document(document.id, &new_path, &file_name_of(path), Some(hash)).await {
+                    Ok(_) => Some(new_path),
+                    Err(e) => {
+                        tracing::error!("Failed to relocate document {}: {}", document.id, e);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        match relinked {
+            Some(new_path) => report.relocated.push(RelocatedDocument {
+                document_id: document.id,
+                title: document.title,
+                old_path: document.file_path,
+                new_path,
+            }),
+            None => report.still_missing.push(MissingDocument {
+                document_id: document.id,
+                title: document.title,
+                file_path: document.file_path,
+            }),
+        }
+    }
+
+    Ok(serde_json::to_value(report).unwrap())
+}
+
+/// Match missing documents `(id, file name, size, fingerprint)` against the PDFs in `folders`.
+/// Only files of a matching size are hashed, and files already linked to a document are skipped.
+fn find_relocation_candidates(
+    documents: &[(uuid::Uuid, String, i64, Option<String>)],
+    folders: &[String],
+    known_paths: &HashSet<String>,
+) -> HashMap<uuid::Uuid, (PathBuf, String)> {
+    let sizes: HashSet<i64> = documents.iter().map(|(_, _, size, _)| *size).collect();
+    let mut candidates = HashMap::new();
+
+    for path in folders.iter().flat_map(|folder| PDFHandler::find_pdf_files(folder)) {
+        let path_str = path.to_string_lossy().to_string();
+        if known_paths.contains(&path_str) {
+            continue;
+        }
+        let Ok(size) = std::fs::metadata(&path).map(|m| m.len() as i64) else {
+            continue;
+        };
+        if !sizes.contains(&size) {
+            continue;
+        }
+        let Ok(hash) = PDFHandler::compute_content_hash(&path_str) else {
+            continue;
+        };
+        let file_name = file_name_of(&path);
+
+        let matched = documents.iter().find(|(id, name, doc_size, doc_hash)| {
+            !candidates.contains_key(id)
+                && *doc_size == size
+                && match doc_hash {
+                    Some(doc_hash) => *doc_hash == hash,
+                    None => *name == file_name,
+                }
+        });
+        if let Some((id, _, _, _)) = matched {
+            candidates.insert(*id, (path, hash));
+        }
+    }
+
+    candidates
+}
+
+#[tauri::command]
+pub async fn get_document_outline(
+    document_id: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let doc_id = uuid::Uuid::parse_str(&document_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    
+    let (entries, document) = {
+        let db_guard = db.lock().await;
+        let Some(database) = db_guard.as_ref() else {
+            return Err("Database not initialized".to_string());
+        };
+        let entries = database
+            .get_document_outline(doc_id)
+            .await
+            .map_err(|e| format!("Failed to get document outline: {}", e))?;
+        let document = if entries.is_empty() {
+            database.get_document(doc_id).await.ok().flatten()
+        } else {
+            None
+        };
+        (entries, document)
+    };
+    
+    // Documents imported before outlines were stored are extracted once, on first request
+    let entries = match document {
+        Some(document) if document.outline_extracted_at.is_none() => {
+            backfill_document_outline(db.inner(), doc_id, document.file_path)
+                .await
+                .unwrap_or(entries)
+        }
+        _ => entries,
+    };
+    
+    Ok(serde_json::to_value(build_outline_tree(&entries)).unwrap())
+}
+
+/// Extract and store the outline of an already imported document, returning the stored entries
+async fn backfill_document_outline(
+    db: &DbState,
+    document_id: uuid::Uuid,
+    file_path: String,
+) -> Option<Vec<DocumentOutlineEntry>> {
+    // A missing file may be relinked later, so it doesn't count as an attempt
+    if !Path::new(&file_path).exists() {
+        return None;
+    }
+    
+    let path = file_path.clone();
+    let outline = match tauri::async_runtime::spawn_blocking(move || PDFHandler::extract_outline(&path)).await {
+        Ok(Ok(outline)) => outline,
+        Ok(Err(e)) => {
+            tracing::warn!("Outline extraction failed for {}: {}", file_path, e);
+            Vec::new()
+        }
+        Err(e) => {
+            tracing::error!("Outline extraction task failed for {}: {}", file_path, e);
+            return None;
+        }
+    };
+    
+    let db_guard = db.lock().await;
+    let database = db_guard.as_ref()?;
+    if let Err(e) = database.store_document_outline(document_id, &outline).await {
+        tracing::error!("Failed to store outline for {}: {}", file_path, e);
+        return None;
+    }
+    database.get_document_outline(document_id).await.ok()
+}
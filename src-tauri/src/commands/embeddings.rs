@@ -0,0 +1,157 @@
+// Embedding provider settings, embedding cache maintenance and re-embedding commands
+use crate::embeddings::{provider_from_preferences, EmbeddingSettings};
+use crate::reembedding;
+use crate::state::DbState;
+use serde_json;
+
+#[tauri::command]
+pub async fn get_embedding_settings(
+    db: tauri::State<'_, DbState>,
+) -> Result<EmbeddingSettings, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        database.get_embedding_settings().await
+            .map_err(|e| format!("Failed to get embedding settings: {}", e))
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Save the embedding provider settings after checking that they describe a usable provider
+#[tauri::command]
+pub async fn save_embedding_settings(
+    settings: EmbeddingSettings,
+    db: tauri::State<'_, DbState>,
+) -> Result<(), String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        settings.validate()
+            .map_err(|e| format!("Invalid embedding settings: {}", e))?;
+
+        match database.save_embedding_settings(&settings).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("Failed to save embedding settings: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Embed a short text with the configured provider and report what it produces
+#[tauri::command]
+pub async fn test_embedding_provider(
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    // Other commands shouldn't wait on the lock while the provider answers
+    let Some(database) = db.lock().await.clone() else {
+        return Err("Database not initialized".to_string());
+    };
+    let provider = provider_from_preferences(&database).await
+        .map_err(|e| format!("Failed to create embedding provider: {}", e))?;
+    let embedding = provider.embed_one("GeniusReads embedding test").await
+        .map_err(|e| format!("Embedding provider test failed: {}", e))?;
+    let column_dimension = database.concept_embedding_column_dimension().await
+        .map_err(|e| format!("Failed to read concept embedding column: {}", e))?;
+
+    Ok(serde_json::json!({
+        "model": provider.model(),
+        "dimension": embedding.len(),
+        "conceptColumnDimension": column_dimension,
+        "compatible": column_dimension.is_none_or(|d| d == embedding.len())
+    }))
+}
+
+#[tauri::command]
+pub async fn get_embedding_cache_stats(
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.get_embedding_cache_stats().await {
+            Ok(stats) => Ok(serde_json::to_value(stats).unwrap()),
+            Err(e) => Err(format!("Failed to get embedding cache stats: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Remove cached vectors of models that are neither configured nor stored on any concept.
+/// Returns the number of vectors removed.
+#[tauri::command]
+pub async fn prune_embedding_cache(
+    db: tauri::State<'_, DbState>,
+) -> Result<u64, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let settings = database.get_embedding_settings().await
+            .map_err(|e| format!("Failed to get embedding settings: {}", e))?;
+        let mut models_in_use = database.get_concept_embedding_models().await
+            .map_err(|e| format!("Failed to get embedding models in use: {}", e))?;
+        models_in_use.push(settings.model_name().to_string());
+
+        match database.prune_embedding_cache(&models_in_use).await {
+            Ok(removed) => Ok(removed),
+            Err(e) => Err(format!("Failed to prune embedding cache: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Re-embed every concept with the configured model in the background.
+/// Resumes the matching unfinished job if there is one; progress is reported
+/// through the re-embedding events.
+#[tauri::command]
+pub async fn start_reembedding(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match reembedding::prepare_job(database).await {
+            Ok(job) => {
+                reembedding::spawn_job(app, database.clone(), job.id);
+                Ok(serde_json::to_value(job).unwrap())
+            }
+            Err(e) => Err(format!("Failed to start re-embedding: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// The most recent re-embedding job, if any
+#[tauri::command]
+pub async fn get_reembedding_status(
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.get_latest_embedding_job().await {
+            Ok(job) => Ok(serde_json::to_value(job).unwrap()),
+            Err(e) => Err(format!("Failed to get re-embedding status: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Cancel a running or failed re-embedding job; concepts keep their current vectors
+#[tauri::command]
+pub async fn cancel_reembedding(
+    job_id: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<bool, String> {
+    let job_id = uuid::Uuid::parse_str(&job_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.cancel_embedding_job(job_id).await {
+            Ok(cancelled) => Ok(cancelled),
+            Err(e) => Err(format!("Failed to cancel re-embedding: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
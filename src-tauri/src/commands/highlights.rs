@@ -0,0 +1,141 @@
+// Highlight commands
+use crate::database::highlights::NewHighlight;
+use crate::state::DbState;
+use serde_json;
+
+/// Colors are stored as `#rrggbb`
+fn validate_color(color: &str) -> Result<(), String> {
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid highlight color '{}', expected #rrggbb", color))
+    }
+}
+
+/// Highlight sent by the frontend when the user marks text
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightInput {
+    pub document_id: String,
+    pub page_number: i32,
+    pub selected_text: String,
+    pub start_coordinate: serde_json::Value,
+    pub end_coordinate: serde_json::Value,
+    pub bounding_boxes: serde_json::Value,
+    pub color: Option<String>,
+    pub note: Option<String>,
+}
+
+#[tauri::command]
+pub async fn create_highlight(
+    highlight: HighlightInput,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let doc_id = uuid::Uuid::parse_str(&highlight.document_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+        if let Some(color) = &highlight.color {
+            validate_color(color)?;
+        }
+
+        match database.create_highlight(NewHighlight {
+            document_id: doc_id,
+            page_number: highlight.page_number,
+            selected_text: &highlight.selected_text,
+            start_coordinate: highlight.start_coordinate,
+            end_coordinate: highlight.end_coordinate,
+            bounding_boxes: highlight.bounding_boxes,
+            color: highlight.color.as_deref(),
+            note: highlight.note.as_deref(),
+        }).await {
+            Ok(created) => Ok(serde_json::to_value(created).unwrap()),
+            Err(e) => Err(format!("Failed to create highlight: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn get_page_highlights(
+    document_id: String,
+    page_number: i32,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let doc_id = uuid::Uuid::parse_str(&document_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+        match database.get_page_highlights(doc_id, page_number).await {
+            Ok(highlights) => Ok(serde_json::to_value(highlights).unwrap()),
+            Err(e) => Err(format!("Failed to get page highlights: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn get_document_highlights(
+    document_id: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let doc_id = uuid::Uuid::parse_str(&document_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+        match database.get_document_highlights(doc_id).await {
+            Ok(highlights) => Ok(serde_json::to_value(highlights).unwrap()),
+            Err(e) => Err(format!("Failed to get document highlights: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Update the color and/or note of a highlight. Omitted fields are left unchanged;
+/// an empty note removes it.
+#[tauri::command]
+pub async fn update_highlight(
+    highlight_id: String,
+    color: Option<String>,
+    note: Option<String>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let id = uuid::Uuid::parse_str(&highlight_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+        if let Some(color) = &color {
+            validate_color(color)?;
+        }
+
+        match database.update_highlight(id, color.as_deref(), note.as_deref()).await {
+            Ok(Some(highlight)) => Ok(serde_json::to_value(highlight).unwrap()),
+            Ok(None) => Err("Highlight not found".to_string()),
+            Err(e) => Err(format!("Failed to update highlight: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn delete_highlight(
+    highlight_id: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<bool, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let id = uuid::Uuid::parse_str(&highlight_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+        match database.delete_highlight(id).await {
+            Ok(deleted) => Ok(deleted),
+            Err(e) => Err(format!("Failed to delete highlight: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
@@ -1,116 +1,191 @@
 // LangGraph concept extraction commands
+use crate::analysis::{self, ANALYSIS_COMPLETE_EVENT};
+use crate::database::analysis_jobs::InterruptedAnalysisAction;
 use crate::state::{DbState, LangGraphState};
-use crate::langraph_bridge::{ConceptExtractionInput, ChatMessageForExtraction, HighlightedContextForExtraction};
 use serde_json;
+use tauri::Emitter;
 
+/// Queue a chat session for concept analysis and return its job right away.
+/// Progress is reported through the `analysis-progress` and `analysis-complete` events.
 #[tauri::command]
 pub async fn analyze_chat_session(
+    app: tauri::AppHandle,
     chat_session_id: String,
     db: tauri::State<'_, DbState>,
     langraph: tauri::State<'_, LangGraphState>,
 ) -> Result<serde_json::Value, String> {
+    let session_id = uuid::Uuid::parse_str(&chat_session_id)
+        .map_err(|e| format!("Invalid UUID: {}", e))?;
+
     let db_guard = db.lock().await;
     if let Some(database) = db_guard.as_ref() {
-        let session_id = uuid::Uuid::parse_str(&chat_session_id)
-            .map_err(|e| format!("Invalid UUID: {}", e))?;
-        
-        // Update analysis status to 'processing'
-        database.update_chat_analysis_status(session_id, "processing").await
-            .map_err(|e| format!("Failed to update analysis status: {}", e))?;
-        
-        // Get chat session data for analysis
-        let chat_data = database.get_chat_session_for_analysis(session_id).await
-            .map_err(|e| format!("Failed to get chat session data: {}", e))?;
-        
-        if chat_data.is_none() {
-            return Err("Chat session not found".to_string());
+        match database.create_analysis_job(session_id).await {
+            Ok(Some(job)) => {
+                analysis::spawn_worker(app, database.clone(), langraph.inner().clone());
+                Ok(serde_json::to_value(job).unwrap())
+            }
+            Ok(None) => Err("Chat session not found".to_string()),
+            Err(e) => Err(format!("Failed to queue analysis: {}", e)),
         }
-        
-        let chat_session = chat_data.unwrap();
-        
-        // Perform concept extraction using LangGraph
-        let extraction_input = ConceptExtractionInput {
-            chat_session_id: chat_session_id.clone(),
-            messages: chat_session.messages.into_iter().map(|msg| ChatMessageForExtraction {
-                content: msg.content,
-                sender_type: msg.sender_type,
-                created_at: msg.created_at.to_rfc3339(),
-            }).collect(),
-            highlighted_contexts: chat_session.highlighted_contexts.into_iter().map(|ctx| HighlightedContextForExtraction {
-                document_title: ctx.document_title,
-                page_number: ctx.page_number,
-                selected_text: ctx.selected_text,
-            }).collect(),
-        };
-        
-        // Get OpenAI API key from user preferences
-        let preferences = database.get_user_preferences().await
-            .map_err(|e| format!("Failed to get user preferences: {}", e))?;
-        
-        let openai_api_key = preferences
-            .and_then(|prefs| prefs.get("openai_api_key").and_then(|key| key.as_str().map(|s| s.to_string())))
-            .ok_or_else(|| "OpenAI API key not configured. Please set your API key in Preferences.".to_string())?;
-        
-        // Perform concept extraction
-        let langraph_guard = langraph.lock().await;
-        let bridge = langraph_guard.as_ref()
-            .ok_or_else(|| "LangGraph bridge not initialized. Please restart the application.".to_string())?;
-        
-        let extraction_result = bridge.extract_concepts(&extraction_input, &openai_api_key)
-            .map_err(|e| format!("Concept extraction failed: {}", e))?;
-        
-        // Check if extraction was successful
-        let success = extraction_result.get("success")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-            
-        if success {
-            // Get the concepts array from the extraction result
-            let empty_vec = vec![];
-            let new_concepts = extraction_result.get("concepts")
-                .and_then(|v| v.as_array())
-                .unwrap_or(&empty_vec);
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn get_analysis_job(
+    job_id: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let job_id = uuid::Uuid::parse_str(&job_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.get_analysis_job(job_id).await {
+            Ok(job) => Ok(serde_json::to_value(job).unwrap()),
+            Err(e) => Err(format!("Failed to get analysis job: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Recent analysis jobs, newest first, optionally for one chat session
+#[tauri::command]
+pub async fn list_analysis_jobs(
+    chat_session_id: Option<String>,
+    limit: Option<i64>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let chat_session_id = chat_session_id
+        .map(|id| uuid::Uuid::parse_str(&id))
+        .transpose()
+        .map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.list_analysis_jobs(chat_session_id, limit.unwrap_or(50)).await {
+            Ok(jobs) => Ok(serde_json::to_value(jobs).unwrap()),
+            Err(e) => Err(format!("Failed to list analysis jobs: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Cancel a queued or running analysis. Returns false when the job already
+/// finished or is storing its concepts.
+#[tauri::command]
+pub async fn cancel_analysis_job(
+    app: tauri::AppHandle,
+    job_id: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<bool, String> {
+    let job_id = uuid::Uuid::parse_str(&job_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.cancel_analysis_job(job_id).await {
+            Ok(true) => {
+                // A running job reports its cancellation when it reaches its next stage
+                if let Ok(Some(job)) = database.get_analysis_job(job_id).await {
+                    if job.started_at.is_none() {
+                        let _ = app.emit(ANALYSIS_COMPLETE_EVENT, &job);
+                    }
+                }
+                Ok(true)
+            }
+            Ok(false) => Ok(false),
+            Err(e) => Err(format!("Failed to cancel analysis: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
 
-            // Fetch all existing concepts from the database for matching
-            let existing_concepts = database.get_all_concepts_for_matching().await
-                .map_err(|e| format!("Failed to fetch existing concepts: {}", e))?;
+/// Queue a failed or cancelled analysis again. Returns the new job, or the chat
+/// session's active job if it was queued in the meantime.
+#[tauri::command]
+pub async fn retry_analysis(
+    app: tauri::AppHandle,
+    job_id: String,
+    db: tauri::State<'_, DbState>,
+    langraph: tauri::State<'_, LangGraphState>,
+) -> Result<serde_json::Value, String> {
+    let job_id = uuid::Uuid::parse_str(&job_id).map_err(|e| format!("Invalid UUID: {}", e))?;
 
-            // Process new concepts against existing ones and save them
-            let processing_result = bridge.process_concepts_and_save(&session_id, new_concepts, &existing_concepts)
-                .map_err(|e| format!("Concept processing and saving failed: {}", e))?;
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let job = match database.get_analysis_job(job_id).await {
+            Ok(Some(job)) => job,
+            Ok(None) => return Err("Analysis job not found".to_string()),
+            Err(e) => return Err(format!("Failed to get analysis job: {}", e)),
+        };
+        if job.status != "failed" && job.status != "cancelled" {
+            return Err(format!("Only failed or cancelled analyses can be retried, this one is {}", job.status));
+        }
 
-            if processing_result.success {
-                // Update analysis status to 'complete'
-                database.update_chat_analysis_status(session_id, "complete").await
-                    .map_err(|e| format!("Failed to update analysis status: {}", e))?;
-                
-                // End the chat session (mark as inactive)
-                database.end_chat_session(session_id).await
-                    .map_err(|e| format!("Failed to end chat session: {}", e))?;
+        match database.create_analysis_job(job.chat_session_id).await {
+            Ok(Some(job)) => {
+                analysis::spawn_worker(app, database.clone(), langraph.inner().clone());
+                Ok(serde_json::to_value(job).unwrap())
+            }
+            Ok(None) => Err("Chat session not found".to_string()),
+            Err(e) => Err(format!("Failed to queue analysis: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
 
-                Ok(serde_json::json!({
-                    "success": true,
-                    "message": "Analysis completed successfully",
-                    "newConceptsCreated": processing_result.new_concepts_created,
-                    "conceptsLinked": processing_result.concepts_linked
-                }))
-            } else {
-                // The Python script failed, so update status to 'failed'
-                let py_error = processing_result.error_message.unwrap_or_else(|| "Unknown Python error".to_string());
-                database.update_chat_analysis_status(session_id, "failed").await
-                    .map_err(|e| format!("Failed to update analysis status: {}", e))?;
-                Err(format!("Concept processing failed in Python: {}", py_error))
+/// Queue every chat session whose analysis failed or was left unfinished.
+/// Returns the jobs that were queued.
+#[tauri::command]
+pub async fn analyze_all_pending(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, DbState>,
+    langraph: tauri::State<'_, LangGraphState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.queue_pending_analyses().await {
+            Ok(jobs) => {
+                if !jobs.is_empty() {
+                    analysis::spawn_worker(app, database.clone(), langraph.inner().clone());
+                }
+                Ok(serde_json::to_value(jobs).unwrap())
             }
-        } else {
-            // Initial concept extraction failed
-            database.update_chat_analysis_status(session_id, "failed").await
-                .map_err(|e| format!("Failed to update analysis status: {}", e))?;
-            
-            let error_message = extraction_result.get("error_message")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unknown error during concept extraction");
-            
-            Err(format!("Concept extraction failed: {}", error_message))
+            Err(e) => Err(format!("Failed to queue pending analyses: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Whether analyses interrupted by quitting the app are marked failed or requeued on the next start
+#[tauri::command]
+pub async fn get_interrupted_analysis_action(
+    db: tauri::State<'_, DbState>,
+) -> Result<InterruptedAnalysisAction, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        database.get_interrupted_analysis_action().await
+            .map_err(|e| format!("Failed to get interrupted analysis action: {}", e))
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn save_interrupted_analysis_action(
+    action: InterruptedAnalysisAction,
+    db: tauri::State<'_, DbState>,
+) -> Result<(), String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.save_interrupted_analysis_action(action).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("Failed to save interrupted analysis action: {}", e)),
         }
     } else {
         Err("Database not initialized".to_string())
@@ -122,14 +197,25 @@ pub async fn analyze_chat_session(
 pub async fn test_langraph_bridge(
     langraph: tauri::State<'_, LangGraphState>,
 ) -> Result<serde_json::Value, String> {
-    let langraph_guard = langraph.lock().await;
+    // A copy of the bridge, so the state isn't locked while Python answers
+    let bridge = langraph.lock().await.clone();
     
-    match langraph_guard.as_ref() {
-        Some(_bridge) => {
-            Ok(serde_json::json!({
-                "status": "initialized",
-                "message": "LangGraph bridge is available and ready"
-            }))
+    match bridge {
+        Some(bridge) => {
+            let health = tauri::async_runtime::spawn_blocking(move || bridge.health())
+                .await
+                .map_err(|e| format!("Failed to check LangGraph bridge: {}", e))?;
+            match health {
+                Ok(health) => Ok(serde_json::json!({
+                    "status": "initialized",
+                    "message": "LangGraph bridge is available and ready",
+                    "health": health
+                })),
+                Err(e) => Ok(serde_json::json!({
+                    "status": "unhealthy",
+                    "message": format!("LangGraph bridge is not answering: {}", e)
+                })),
+            }
         }
         None => {
             Ok(serde_json::json!({
@@ -138,4 +224,4 @@ pub async fn test_langraph_bridge(
             }))
         }
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,152 @@
+// Library root commands
+use crate::library::{self, LibraryWatcher};
+use crate::state::{DbState, LibraryWatcherState};
+use serde_json;
+use std::path::Path;
+
+#[tauri::command]
+pub async fn add_library_root(
+    path: String,
+    app: tauri::AppHandle,
+    db: tauri::State<'_, DbState>,
+    watcher: tauri::State<'_, LibraryWatcherState>,
+) -> Result<serde_json::Value, String> {
+    let folder = Path::new(&path)
+        .canonicalize()
+        .map_err(|e| format!("Invalid library folder: {}", e))?;
+    if !folder.is_dir() {
+        return Err("Library root must be a folder".to_string());
+    }
+    let folder = folder.to_string_lossy().to_string();
+
+    let root = {
+        let db_guard = db.lock().await;
+        if let Some(database) = db_guard.as_ref() {
+            match database.add_library_root(&folder).await {
+                Ok(root) => root,
+                Err(e) => return Err(format!("Failed to add library root: {}", e)),
+            }
+        } else {
+            return Err("Database not initialized".to_string());
+        }
+    };
+
+    if let Some(watcher) = watcher.lock().await.as_mut() {
+        if let Err(e) = watcher.watch(&root.path) {
+            tracing::warn!("Failed to watch library root {}: {}", root.path, e);
+        }
+    }
+
+    // The initial import reports progress through events instead of blocking the caller
+    let db = db.inner().clone();
+    let response = serde_json::to_value(&root).unwrap();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = library::scan_root(&app, &db, &root).await {
+            tracing::error!("Failed to scan library root {}: {}", root.path, e);
+        }
+    });
+
+    Ok(response)
+}
+
+#[tauri::command]
+pub async fn get_library_roots(
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.get_library_roots().await {
+            Ok(roots) => Ok(serde_json::to_value(roots).unwrap()),
+            Err(e) => Err(format!("Failed to get library roots: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn remove_library_root(
+    root_id: String,
+    db: tauri::State<'_, DbState>,
+    watcher: tauri::State<'_, LibraryWatcherState>,
+) -> Result<bool, String> {
+    let root_id = uuid::Uuid::parse_str(&root_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let removed_path = {
+        let db_guard = db.lock().await;
+        if let Some(database) = db_guard.as_ref() {
+            match database.remove_library_root(root_id).await {
+                Ok(path) => path,
+                Err(e) => return Err(format!("Failed to remove library root: {}", e)),
+            }
+        } else {
+            return Err("Database not initialized".to_string());
+        }
+    };
+
+    let Some(path) = removed_path else {
+        return Ok(false);
+    };
+    if let Some(watcher) = watcher.lock().await.as_mut() {
+        if let Err(e) = watcher.unwatch(&path) {
+            tracing::warn!("Failed to stop watching library root {}: {}", path, e);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Rescan all library roots in the background; progress arrives as events
+#[tauri::command]
+pub async fn scan_library_roots(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, DbState>,
+) -> Result<(), String> {
+    if db.lock().await.is_none() {
+        return Err("Database not initialized".to_string());
+    }
+
+    let db = db.inner().clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = library::scan_all_roots(&app, &db).await {
+            tracing::error!("Failed to scan library roots: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
+/// Start watching every configured library root and pick up changes made while the app was closed
+pub(crate) async fn start_library_sync(app: tauri::AppHandle, db: DbState, watcher_state: LibraryWatcherState) {
+    let roots = {
+        let db_guard = db.lock().await;
+        let Some(database) = db_guard.as_ref() else {
+            return;
+        };
+        match database.get_library_roots().await {
+            Ok(roots) => roots,
+            Err(e) => {
+                tracing::error!("Failed to load library roots: {}", e);
+                Vec::new()
+            }
+        }
+    };
+
+    match LibraryWatcher::start(app.clone(), db.clone()) {
+        Ok(mut watcher) => {
+            for root in &roots {
+                if let Err(e) = watcher.watch(&root.path) {
+                    tracing::warn!("Failed to watch library root {}: {}", root.path, e);
+                }
+            }
+            *watcher_state.lock().await = Some(watcher);
+        }
+        Err(e) => tracing::error!("Failed to start library watcher: {}", e),
+    }
+
+    if !roots.is_empty() {
+        if let Err(e) = library::scan_all_roots(&app, &db).await {
+            tracing::error!("Failed to scan library roots: {}", e);
+        }
+    }
+}
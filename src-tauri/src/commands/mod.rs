@@ -2,18 +2,30 @@
 pub mod app_info;
 pub mod database;
 pub mod documents;
+pub mod library;
 pub mod chat;
+pub mod highlights;
+pub mod notes;
 pub mod navigation;
 pub mod preferences;
 pub mod concepts;
+pub mod concept_proposals;
+pub mod embeddings;
 pub mod langraph;
+pub mod search;
 
 // Re-export all commands for easy access
 pub use app_info::*;
 pub use database::*;
 pub use documents::*;
+pub use library::*;
 pub use chat::*;
+pub use highlights::*;
+pub use notes::*;
 pub use navigation::*;
 pub use preferences::*;
 pub use concepts::*;
-pub use langraph::*; 
\ No newline at end of file
+pub use concept_proposals::*;
+pub use embeddings::*;
+pub use langraph::*;
+pub use search::*; 
\ No newline at end of file
@@ -0,0 +1,255 @@
+// User notes commands
+use crate::database::notes::{NewNote, NoteFilter, NoteUpdate};
+use crate::database::UserNote;
+use crate::state::DbState;
+use serde_json;
+use std::fmt::Write;
+
+/// Note sent by the frontend; either `page_number` or `selection_id` locates it
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteInput {
+    pub document_id: String,
+    pub selection_id: Option<String>,
+    pub page_number: Option<i32>,
+    pub note_text: String,
+    pub note_type: Option<String>,
+    pub position: Option<serde_json::Value>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn create_note(
+    note: NoteInput,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let doc_id = uuid::Uuid::parse_str(&note.document_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+        let selection_id = note
+            .selection_id
+            .as_deref()
+            .map(uuid::Uuid::parse_str)
+            .transpose()
+            .map_err(|e| format!("Invalid selection UUID: {}", e))?;
+
+        match database.create_note(NewNote {
+            document_id: doc_id,
+            selection_id,
+            page_number: note.page_number,
+            note_text: &note.note_text,
+            note_type: note.note_type.as_deref(),
+            position: note.position,
+            tags: &note.tags,
+        }).await {
+            Ok(created) => Ok(serde_json::to_value(created).unwrap()),
+            Err(e) => Err(format!("Failed to create note: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn get_note(
+    note_id: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<Option<serde_json::Value>, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let id = uuid::Uuid::parse_str(&note_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+        match database.get_note(id).await {
+            Ok(note) => Ok(note.map(|n| serde_json::to_value(n).unwrap())),
+            Err(e) => Err(format!("Failed to get note: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn update_note(
+    note_id: String,
+    changes: NoteUpdate,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let id = uuid::Uuid::parse_str(&note_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+        match database.update_note(id, &changes).await {
+            Ok(Some(note)) => Ok(serde_json::to_value(note).unwrap()),
+            Ok(None) => Err("Note not found".to_string()),
+            Err(e) => Err(format!("Failed to update note: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn delete_note(
+    note_id: String,
+    db: tauri::State<'_, DbState>,
+) -> Result<bool, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let id = uuid::Uuid::parse_str(&note_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+        match database.delete_note(id).await {
+            Ok(deleted) => Ok(deleted),
+            Err(e) => Err(format!("Failed to delete note: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// List notes by document, page, type, tag and/or creation date
+#[tauri::command]
+pub async fn list_notes(
+    filter: Option<NoteFilter>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.list_notes(&filter.unwrap_or_default()).await {
+            Ok(notes) => Ok(serde_json::to_value(notes).unwrap()),
+            Err(e) => Err(format!("Failed to list notes: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn search_notes(
+    query: String,
+    document_id: Option<String>,
+    max_results: Option<i64>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let doc_id = document_id
+            .map(|id| uuid::Uuid::parse_str(&id))
+            .transpose()
+            .map_err(|e| format!("Invalid document UUID: {}", e))?;
+
+        let filter = NoteFilter {
+            document_id: doc_id,
+            query: Some(query),
+            limit: Some(max_results.unwrap_or(20)),
+            ..Default::default()
+        };
+
+        match database.list_notes(&filter).await {
+            Ok(notes) => Ok(serde_json::to_value(notes).unwrap()),
+            Err(e) => Err(format!("Failed to search notes: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Export the notes matching a filter as `markdown` (default) or `json`
+#[tauri::command]
+pub async fn export_notes(
+    filter: Option<NoteFilter>,
+    format: Option<String>,
+    db: tauri::State<'_, DbState>,
+) -> Result<String, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let mut filter = filter.unwrap_or_default();
+        // Exports cover everything that matches, not just the first page of results
+        filter.limit = filter.limit.or(Some(i64::MAX));
+
+        let mut notes = database
+            .list_notes(&filter)
+            .await
+            .map_err(|e| format!("Failed to export notes: {}", e))?;
+
+        match format.as_deref().unwrap_or("markdown") {
+            "markdown" => {
+                notes.sort_by(|a, b| {
+                    (&a.document_title, a.page_number, a.created_at)
+                        .cmp(&(&b.document_title, b.page_number, b.created_at))
+                });
+                Ok(notes_to_markdown(&notes))
+            }
+            "json" => serde_json::to_string_pretty(&notes).map_err(|e| format!("Failed to export notes: {}", e)),
+            other => Err(format!("Unsupported export format '{}'", other)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Render notes as Markdown, grouped by document; `notes` must be sorted by document and page
+fn notes_to_markdown(notes: &[UserNote]) -> String {
+    let mut out = String::from("# Notes\n");
+    let mut current_document = None;
+
+    for note in notes {
+        if current_document != Some(note.document_id) {
+            current_document = Some(note.document_id);
+            let _ = write!(out, "\n## {}\n", note.document_title);
+        }
+
+        let _ = write!(out, "\n### Page {} · {}\n\n", note.page_number, note.note_type);
+        for line in note.note_text.lines() {
+            let _ = writeln!(out, "{}", line);
+        }
+        if !note.tags.is_empty() {
+            let tags: Vec<String> = note.tags.iter().map(|t| format!("#{}", t)).collect();
+            let _ = write!(out, "\n{}\n", tags.join(" "));
+        }
+        let _ = write!(out, "\n_{}_\n", note.created_at.format("%Y-%m-%d %H:%M"));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn note(document_id: uuid::Uuid, title: &str, page: i32, text: &str, tags: &[&str]) -> UserNote {
+        UserNote {
+            id: uuid::Uuid::new_v4(),
+            document_id,
+            document_title: title.to_string(),
+            selection_id: None,
+            page_number: page,
+            note_text: text.to_string(),
+            note_type: "summary".to_string(),
+            position: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            is_private: true,
+            created_at: Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_notes_to_markdown_groups_by_document() {
+        let book = uuid::Uuid::new_v4();
+        let paper = uuid::Uuid::new_v4();
+        let notes = vec![
+            note(book, "Deep Learning", 3, "Gradients flow backwards", &["backprop"]),
+            note(book, "Deep Learning", 7, "Chain rule", &[]),
+            note(paper, "Attention Is All You Need", 1, "Self-attention", &["transformers", "nlp"]),
+        ];
+
+        let markdown = notes_to_markdown(&notes);
+        assert_eq!(markdown.matches("## Deep Learning").count(), 1);
+        assert!(markdown.contains("### Page 3 · summary\n\nGradients flow backwards\n\n#backprop\n"));
+        assert!(markdown.contains("## Attention Is All You Need"));
+        assert!(markdown.contains("#transformers #nlp"));
+        assert!(markdown.contains("_2024-03-01 09:30_"));
+    }
+}
@@ -0,0 +1,68 @@
+// Library search commands
+use crate::state::DbState;
+use serde_json;
+
+#[tauri::command]
+pub async fn search_library(
+    query: String,
+    document_id: Option<String>,
+    max_results: Option<i64>,
+    offset: Option<i64>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let doc_id = document_id
+            .map(|id| uuid::Uuid::parse_str(&id))
+            .transpose()
+            .map_err(|e| format!("Invalid document UUID: {}", e))?;
+        
+        let limit = max_results.unwrap_or(20);
+        let offset = offset.unwrap_or(0);
+        
+        match database.search_library(&query, doc_id, limit, offset).await {
+            Ok(results) => Ok(serde_json::to_value(results).unwrap()),
+            Err(e) => Err(format!("Failed to search library: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn unified_search(
+    query: String,
+    entity_types: Option<Vec<String>>,
+    max_results: Option<i64>,
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        let limit = max_results.unwrap_or(20);
+        
+        match database.unified_search(&query, entity_types.as_deref(), limit).await {
+            Ok(results) => Ok(serde_json::to_value(results).unwrap()),
+            Err(e) => Err(format!("Failed to run unified search: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn rebuild_search_index(
+    db: tauri::State<'_, DbState>,
+) -> Result<serde_json::Value, String> {
+    let db_guard = db.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        match database.rebuild_search_index().await {
+            Ok(indexed) => Ok(serde_json::json!({
+                "success": true,
+                "entriesIndexed": indexed
+            })),
+            Err(e) => Err(format!("Failed to rebuild search index: {}", e)),
+        }
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
@@ -0,0 +1,448 @@
+// Concept analysis job queue database operations
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+use crate::database::{AnalysisJob, Database};
+
+/// Stages of an analysis run, recorded in `langraph_processing.processing_stages`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisStage {
+    /// Reading the chat session and settings
+    Loading,
+    /// Extracting concepts with the language model
+    Extracting,
+    /// Embedding the extracted concepts
+    Embedding,
+    /// Matching against existing concepts and saving
+    Storing,
+}
+
+impl AnalysisStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnalysisStage::Loading => "loading",
+            AnalysisStage::Extracting => "extracting",
+            AnalysisStage::Embedding => "embedding",
+            AnalysisStage::Storing => "storing",
+        }
+    }
+}
+
+/// What startup does with analysis runs that were interrupted when the app quit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterruptedAnalysisAction {
+    /// Mark them failed so they can be retried by hand
+    Fail,
+    /// Put them back in the queue to run again
+    Requeue,
+}
+
+impl InterruptedAnalysisAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fail => "fail",
+            Self::Requeue => "requeue",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "fail" => Ok(Self::Fail),
+            "requeue" => Ok(Self::Requeue),
+            other => Err(anyhow!("Unknown interrupted analysis action '{}'", other)),
+        }
+    }
+}
+
+impl Database {
+    /// Queue an analysis of a chat session. A session that is already queued or being
+    /// analyzed keeps its job, which is returned instead. `None` if the session doesn't exist.
+    pub async fn create_analysis_job(&self, chat_session_id: Uuid) -> Result<Option<AnalysisJob>> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let created = sqlx::query_as!(
+            AnalysisJob,
+            r#"
+            INSERT INTO langraph_processing (chat_session_id, status)
+            SELECT id, 'pending' FROM chat_sessions WHERE id = $1
+            ON CONFLICT (chat_session_id) WHERE status IN ('pending', 'processing') DO NOTHING
+            RETURNING id, chat_session_id, status, processing_stages, extracted_concept_count, error_message,
+                      result, created_at, started_at, completed_at, processing_time_ms
+            "#,
+            chat_session_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to queue analysis")?;
+
+        let job = match created {
+            Some(job) => {
+                sqlx::query!(
+                    "UPDATE chat_sessions SET analysis_status = 'pending', updated_at = NOW() WHERE id = $1",
+                    chat_session_id
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to update chat analysis status")?;
+                Some(job)
+            }
+            None => sqlx::query_as!(
+                AnalysisJob,
+                r#"
+                SELECT id, chat_session_id, status, processing_stages, extracted_concept_count, error_message,
+                       result, created_at, started_at, completed_at, processing_time_ms
+                FROM langraph_processing
+                WHERE chat_session_id = $1 AND status IN ('pending', 'processing')
+                "#,
+                chat_session_id
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Failed to get active analysis")?,
+        };
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(job)
+    }
+
+    pub async fn get_analysis_job(&self, job_id: Uuid) -> Result<Option<AnalysisJob>> {
+        let job = sqlx::query_as!(
+            AnalysisJob,
+            r#"
+            SELECT id, chat_session_id, status, processing_stages, extracted_concept_count, error_message,
+                   result, created_at, started_at, completed_at, processing_time_ms
+            FROM langraph_processing
+            WHERE id = $1
+            "#,
+            job_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to get analysis job")?;
+
+        Ok(job)
+    }
+
+    /// Most recent jobs first, optionally only those of one chat session
+    pub async fn list_analysis_jobs(&self, chat_session_id: Option<Uuid>, limit: i64) -> Result<Vec<AnalysisJob>> {
+        let jobs = sqlx::query_as!(
+            AnalysisJob,
+            r#"
+            SELECT id, chat_session_id, status, processing_stages, extracted_concept_count, error_message,
+                   result, created_at, started_at, completed_at, processing_time_ms
+            FROM langraph_processing
+            WHERE $1::UUID IS NULL OR chat_session_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            chat_session_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list analysis jobs")?;
+
+        Ok(jobs)
+    }
+
+    /// Take the oldest pending job off the queue and mark it as processing
+    pub async fn claim_next_analysis_job(&self) -> Result<Option<AnalysisJob>> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let job = sqlx::query_as!(
+            AnalysisJob,
+            r#"
+            UPDATE langraph_processing
+            SET status = 'processing', started_at = NOW()
+            WHERE id = (
+                SELECT id FROM langraph_processing
+                WHERE status = 'pending'
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, chat_session_id, status, processing_stages, extracted_concept_count, error_message,
+                      result, created_at, started_at, completed_at, processing_time_ms
+            "#
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to claim analysis job")?;
+
+        if let Some(job) = &job {
+            sqlx::query!(
+                "UPDATE chat_sessions SET analysis_status = 'processing', updated_at = NOW() WHERE id = $1",
+                job.chat_session_id
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to update chat analysis status")?;
+        }
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(job)
+    }
+
+    /// Record that a processing job reached a stage.
+    /// Returns `None` when the job is no longer processing, e.g. because it was cancelled.
+    pub async fn record_analysis_stage(&self, job_id: Uuid, stage: AnalysisStage) -> Result<Option<AnalysisJob>> {
+        let job = sqlx::query_as!(
+            AnalysisJob,
+            r#"
+            UPDATE langraph_processing
+            SET processing_stages = processing_stages
+                || jsonb_build_array(jsonb_build_object('stage', $2::TEXT, 'startedAt', NOW()))
+            WHERE id = $1 AND status = 'processing'
+            RETURNING id, chat_session_id, status, processing_stages, extracted_concept_count, error_message,
+                      result, created_at, started_at, completed_at, processing_time_ms
+            "#,
+            job_id,
+            stage.as_str()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to record analysis stage")?;
+
+        Ok(job)
+    }
+
+    /// Finish a processing job with the number of concepts it extracted and a summary of what it stored
+    pub async fn complete_analysis_job(
+        &self,
+        job_id: Uuid,
+        extracted_concept_count: i32,
+        result: &Value,
+    ) -> Result<Option<AnalysisJob>> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let job = sqlx::query_as!(
+            AnalysisJob,
+            r#"
+            UPDATE langraph_processing
+            SET status = 'complete', extracted_concept_count = $2, result = $3, completed_at = NOW(),
+                processing_time_ms = (EXTRACT(EPOCH FROM NOW() - started_at) * 1000)::INTEGER
+            WHERE id = $1 AND status = 'processing'
+            RETURNING id, chat_session_id, status, processing_stages, extracted_concept_count, error_message,
+                      result, created_at, started_at, completed_at, processing_time_ms
+            "#,
+            job_id,
+            extracted_concept_count,
+            result
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to complete analysis job")?;
+
+        if let Some(job) = &job {
+            sqlx::query!(
+                "UPDATE chat_sessions SET analysis_status = 'complete', updated_at = NOW() WHERE id = $1",
+                job.chat_session_id
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to update chat analysis status")?;
+        }
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(job)
+    }
+
+    /// Mark a pending or processing job as failed
+    pub async fn fail_analysis_job(&self, job_id: Uuid, error_message: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let chat_session_id = sqlx::query_scalar!(
+            r#"
+            UPDATE langraph_processing
+            SET status = 'failed', error_message = $2, completed_at = NOW(),
+                processing_time_ms = (EXTRACT(EPOCH FROM NOW() - started_at) * 1000)::INTEGER
+            WHERE id = $1 AND status IN ('pending', 'processing')
+            RETURNING chat_session_id
+            "#,
+            job_id,
+            error_message
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to mark analysis job as failed")?;
+
+        if let Some(chat_session_id) = chat_session_id {
+            sqlx::query!(
+                "UPDATE chat_sessions SET analysis_status = 'failed', updated_at = NOW() WHERE id = $1",
+                chat_session_id
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to update chat analysis status")?;
+        }
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(())
+    }
+
+    /// Cancel a job that is queued or hasn't started storing concepts yet; a run that is
+    /// already saving finishes so concepts aren't left half stored. Returns whether it was cancelled.
+    pub async fn cancel_analysis_job(&self, job_id: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let chat_session_id = sqlx::query_scalar!(
+            r#"
+            UPDATE langraph_processing
+            SET status = 'cancelled', completed_at = NOW(),
+                processing_time_ms = (EXTRACT(EPOCH FROM NOW() - started_at) * 1000)::INTEGER
+            WHERE id = $1
+              AND status IN ('pending', 'processing')
+              AND NOT processing_stages @> jsonb_build_array(jsonb_build_object('stage', $2::TEXT))
+            RETURNING chat_session_id
+            "#,
+            job_id,
+            AnalysisStage::Storing.as_str()
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to cancel analysis job")?;
+
+        if let Some(chat_session_id) = chat_session_id {
+            sqlx::query!(
+                "UPDATE chat_sessions SET analysis_status = 'none', updated_at = NOW() WHERE id = $1",
+                chat_session_id
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to update chat analysis status")?;
+        }
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(chat_session_id.is_some())
+    }
+
+    /// Reconcile analyses left unfinished by a previous run of the app. Only call this at
+    /// startup, before the worker runs: every processing job is then an interrupted one.
+    /// Chat sessions stuck at pending or processing without a queued job are handled the
+    /// same way. Returns how many runs were recovered.
+    pub async fn recover_interrupted_analyses(&self, action: InterruptedAnalysisAction) -> Result<u64> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let recovered = match action {
+            InterruptedAnalysisAction::Fail => {
+                sqlx::query!(
+                    r#"
+                    UPDATE langraph_processing
+                    SET status = 'failed', error_message = 'Interrupted when the app quit', completed_at = NOW(),
+                        processing_time_ms = (EXTRACT(EPOCH FROM NOW() - started_at) * 1000)::INTEGER
+                    WHERE status = 'processing'
+                    "#
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to mark interrupted analysis jobs as failed")?;
+
+                let sessions = sqlx::query!(
+                    r#"
+                    UPDATE chat_sessions cs
+                    SET analysis_status = 'failed', updated_at = NOW()
+                    WHERE cs.analysis_status IN ('pending', 'processing')
+                      AND NOT EXISTS (
+                          SELECT 1 FROM langraph_processing lp
+                          WHERE lp.chat_session_id = cs.id AND lp.status IN ('pending', 'processing')
+                      )
+                    "#
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to update chat analysis status")?;
+
+                // Covers the sessions of the failed jobs too
+                sessions.rows_affected()
+            }
+            InterruptedAnalysisAction::Requeue => {
+                let jobs = sqlx::query!(
+                    r#"
+                    UPDATE langraph_processing
+                    SET status = 'pending', started_at = NULL, processing_stages = '[]'
+                    WHERE status = 'processing'
+                    "#
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to requeue interrupted analysis jobs")?;
+
+                let sessions = sqlx::query!(
+                    r#"
+                    INSERT INTO langraph_processing (chat_session_id, status)
+                    SELECT cs.id, 'pending' FROM chat_sessions cs
+                    WHERE cs.analysis_status IN ('pending', 'processing')
+                      AND NOT EXISTS (
+                          SELECT 1 FROM langraph_processing lp
+                          WHERE lp.chat_session_id = cs.id AND lp.status IN ('pending', 'processing')
+                      )
+                    ON CONFLICT (chat_session_id) WHERE status IN ('pending', 'processing') DO NOTHING
+                    "#
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to requeue interrupted chat sessions")?;
+
+                sqlx::query!(
+                    r#"
+                    UPDATE chat_sessions
+                    SET analysis_status = 'pending', updated_at = NOW()
+                    WHERE analysis_status <> 'pending'
+                      AND id IN (SELECT chat_session_id FROM langraph_processing WHERE status = 'pending')
+                    "#
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to update chat analysis status")?;
+
+                jobs.rows_affected() + sessions.rows_affected()
+            }
+        };
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(recovered)
+    }
+
+    /// Queue every chat session whose analysis failed or never got a job. Sessions that
+    /// are already queued are left alone. Returns the new jobs.
+    pub async fn queue_pending_analyses(&self) -> Result<Vec<AnalysisJob>> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let jobs = sqlx::query_as!(
+            AnalysisJob,
+            r#"
+            INSERT INTO langraph_processing (chat_session_id, status)
+            SELECT id, 'pending' FROM chat_sessions
+            WHERE analysis_status IN ('pending', 'processing', 'failed')
+            ORDER BY created_at
+            ON CONFLICT (chat_session_id) WHERE status IN ('pending', 'processing') DO NOTHING
+            RETURNING id, chat_session_id, status, processing_stages, extracted_concept_count, error_message,
+                      result, created_at, started_at, completed_at, processing_time_ms
+            "#
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to queue pending analyses")?;
+
+        let chat_session_ids: Vec<Uuid> = jobs.iter().map(|job| job.chat_session_id).collect();
+        sqlx::query!(
+            "UPDATE chat_sessions SET analysis_status = 'pending', updated_at = NOW() WHERE id = ANY($1)",
+            &chat_session_ids
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to update chat analysis status")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(jobs)
+    }
+}
@@ -2,6 +2,7 @@
 use anyhow::{Context, Result};
 use serde_json::Value;
 use uuid::Uuid;
+use crate::database::search_index::{SearchEntityType, SearchIndexEntry};
 use crate::database::{Database, ChatSession, ChatSessionForAnalysis, ChatMessage, HighlightedContext};
 
 impl Database {
@@ -123,6 +124,7 @@ impl Database {
                             'pageNumber', page_number,
                             'selectedText', selected_text,
                             'textCoordinates', text_coordinates,
+                            'sectionTitle', section_title,
                             'createdAt', created_at
                         ) ORDER BY created_at
                     ) as highlighted_contexts
@@ -240,6 +242,21 @@ impl Database {
         .await
         .context("Failed to update chat session timestamp")?;
 
+        let indexed = self.index_entity(SearchIndexEntry {
+            entity_type: SearchEntityType::ChatMessage,
+            entity_id: id,
+            title: None,
+            searchable_text: content,
+            keywords: &[],
+            document_id: None,
+            chat_session_id: Some(chat_session_id),
+            page_number: None,
+        })
+        .await;
+        if let Err(e) = indexed {
+            tracing::warn!("Failed to index chat message {}: {}", id, e);
+        }
+
         Ok(id)
     }
 
@@ -254,14 +271,15 @@ impl Database {
         text_coordinates: Value,
     ) -> Result<Uuid> {
         let id = Uuid::new_v4();
+        let section_title = self.get_section_title(document_id, page_number).await?;
 
         sqlx::query!(
             r#"
             INSERT INTO highlighted_contexts (
                 id, chat_session_id, document_id, document_title, 
-                page_number, selected_text, text_coordinates
+                page_number, selected_text, text_coordinates, section_title
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             "#,
             id,
             chat_session_id,
@@ -269,7 +287,8 @@ impl Database {
             document_title,
             page_number,
             selected_text,
-            text_coordinates
+            text_coordinates,
+            section_title
         )
         .execute(&self.pool)
         .await
@@ -292,6 +311,21 @@ impl Database {
         .await
         .context("Failed to update source document count")?;
 
+        let indexed = self.index_entity(SearchIndexEntry {
+            entity_type: SearchEntityType::HighlightedContext,
+            entity_id: id,
+            title: Some(document_title),
+            searchable_text: selected_text,
+            keywords: &[],
+            document_id: Some(document_id),
+            chat_session_id: Some(chat_session_id),
+            page_number: Some(page_number),
+        })
+        .await;
+        if let Err(e) = indexed {
+            tracing::warn!("Failed to index highlighted context {}: {}", id, e);
+        }
+
         Ok(id)
     }
 
@@ -319,6 +353,8 @@ impl Database {
             .await
             .context("Failed to delete highlighted contexts")?;
 
+        self.remove_chat_session_from_index(chat_session_id).await?;
+
         // Reset source document count and update timestamp
         sqlx::query!(
             r#"
@@ -366,28 +402,6 @@ impl Database {
         Ok(())
     }
 
-    /// Update chat session analysis status
-    pub async fn update_chat_analysis_status(
-        &self,
-        chat_session_id: Uuid,
-        status: &str,
-    ) -> Result<()> {
-        sqlx::query!(
-            r#"
-            UPDATE chat_sessions 
-            SET analysis_status = $1, updated_at = NOW()
-            WHERE id = $2
-            "#,
-            status,
-            chat_session_id
-        )
-        .execute(&self.pool)
-        .await
-        .context("Failed to update chat analysis status")?;
-
-        Ok(())
-    }
-
     /// Get chat session data for analysis (messages and contexts)
     pub async fn get_chat_session_for_analysis(
         &self,
@@ -436,7 +450,8 @@ impl Database {
             // Get highlighted contexts
             let context_rows = sqlx::query!(
                 r#"
-                SELECT id, document_id, document_title, page_number, selected_text, text_coordinates, created_at
+                SELECT id, document_id, document_title, page_number, selected_text, text_coordinates,
+                       section_title, created_at
                 FROM highlighted_contexts
                 WHERE chat_session_id = $1
                 ORDER BY created_at ASC
@@ -457,6 +472,7 @@ impl Database {
                     page_number: row.page_number,
                     selected_text: row.selected_text,
                     text_coordinates: row.text_coordinates,
+                    section_title: row.section_title,
                     created_at: row.created_at,
                 })
                 .collect();
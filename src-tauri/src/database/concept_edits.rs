@@ -0,0 +1,619 @@
+// Concept editing, merging, splitting and deletion
+use anyhow::{anyhow, Context, Result};
+use pgvector::Vector;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+use crate::database::search_index::{SearchEntityType, SearchIndexEntry};
+use crate::database::{Database, ExtractedConcept};
+
+type Transaction<'a> = sqlx::Transaction<'a, sqlx::Postgres>;
+
+/// Changes to a concept; `None` leaves a field unchanged
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConceptUpdate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+impl ConceptUpdate {
+    /// Name and description of a concept once the changes are applied
+    pub fn text_of<'a>(&'a self, concept: &'a ExtractedConcept) -> (&'a str, &'a str) {
+        (
+            self.name.as_deref().map(str::trim).unwrap_or(&concept.name),
+            self.description.as_deref().map(str::trim).unwrap_or(&concept.description),
+        )
+    }
+}
+
+/// One of the concepts a concept is split into
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConceptSplitPart {
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Chats of the original concept this part came from; `None` keeps all of them
+    pub chat_session_ids: Option<Vec<Uuid>>,
+}
+
+/// A freshly computed embedding of a concept's name and description
+pub struct ConceptEmbedding {
+    pub model: String,
+    pub vector: Vec<f32>,
+}
+
+/// Tags of both lists in order, dropping case-insensitive duplicates and blanks
+pub(crate) fn merge_tags(first: &[String], second: &[String]) -> Vec<String> {
+    let mut merged: Vec<String> = Vec::new();
+    for tag in first.iter().chain(second) {
+        let tag = tag.trim();
+        if !tag.is_empty() && !merged.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            merged.push(tag.to_string());
+        }
+    }
+    merged
+}
+
+fn validate_text(name: &str, description: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(anyhow!("Concept name cannot be empty"));
+    }
+    if description.trim().is_empty() {
+        return Err(anyhow!("Concept description cannot be empty"));
+    }
+    Ok(())
+}
+
+/// Check the parts of a split against the chats linked to the original concept
+fn validate_split(parts: &[ConceptSplitPart], linked_chats: &HashMap<Uuid, f64>) -> Result<()> {
+    if parts.len() < 2 {
+        return Err(anyhow!("A concept must be split into at least two concepts"));
+    }
+    for part in parts {
+        validate_text(&part.name, &part.description)?;
+        for chat_session_id in part.chat_session_ids.iter().flatten() {
+            if !linked_chats.contains_key(chat_session_id) {
+                return Err(anyhow!("Chat session {} is not linked to the concept", chat_session_id));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Database {
+    /// Get a concept as stored
+    pub async fn get_extracted_concept(&self, concept_id: Uuid) -> Result<Option<ExtractedConcept>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, name, description, tags, confidence_score, source_chat_count, created_at, updated_at
+            FROM concepts
+            WHERE id = $1
+            "#,
+            concept_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to get concept")?;
+
+        Ok(row.map(|row| ExtractedConcept {
+            id: row.id,
+            name: row.name,
+            description: row.description,
+            tags: row.tags.and_then(|t| serde_json::from_value(t).ok()).unwrap_or_default(),
+            confidence_score: row.confidence_score,
+            source_chat_count: row.source_chat_count,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }))
+    }
+
+    /// Read a concept inside a transaction, locking it until the transaction ends
+    pub(crate) async fn lock_concept(tx: &mut Transaction<'_>, concept_id: Uuid) -> Result<Option<ExtractedConcept>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, name, description, tags, confidence_score, source_chat_count, created_at, updated_at
+            FROM concepts
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            concept_id
+        )
+        .fetch_optional(&mut **tx)
+        .await
+        .context("Failed to get concept")?;
+
+        Ok(row.map(|row| ExtractedConcept {
+            id: row.id,
+            name: row.name,
+            description: row.description,
+            tags: row.tags.and_then(|t| serde_json::from_value(t).ok()).unwrap_or_default(),
+            confidence_score: row.confidence_score,
+            source_chat_count: row.source_chat_count,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }))
+    }
+
+    /// Replace a concept's embedding, or clear it when there is no new one.
+    /// A running re-embedding job drops what it staged for the concept so it embeds the new text.
+    /// The embedding is written as a pgvector value, or as a plain array when the column is
+    /// `REAL[]` because pgvector isn't installed.
+    pub(crate) async fn set_concept_embedding(
+        &self,
+        tx: &mut Transaction<'_>,
+        concept_id: Uuid,
+        embedding: Option<&ConceptEmbedding>,
+    ) -> Result<()> {
+        let model = embedding.map(|e| e.model.as_str());
+        let dimension = embedding.map(|e| e.vector.len() as i32);
+
+        if self.vector_index.read().await.is_some() {
+            sqlx::query!(
+                r#"
+                UPDATE concepts
+                SET embedding = $2::REAL[], embedding_model = $3, embedding_dimension = $4
+                WHERE id = $1
+                "#,
+                concept_id,
+                embedding.map(|e| e.vector.as_slice()),
+                model,
+                dimension
+            )
+            .execute(&mut **tx)
+            .await
+            .context("Failed to update concept embedding")?;
+        } else {
+            sqlx::query!(
+                r#"
+                UPDATE concepts
+                SET embedding = $2, embedding_model = $3, embedding_dimension = $4
+                WHERE id = $1
+                "#,
+                concept_id,
+                embedding.map(|e| Vector::from(e.vector.clone())) as _,
+                model,
+                dimension
+            )
+            .execute(&mut **tx)
+            .await
+            .context("Failed to update concept embedding")?;
+        }
+
+        sqlx::query!("DELETE FROM concept_embedding_staging WHERE concept_id = $1", concept_id)
+            .execute(&mut **tx)
+            .await
+            .context("Failed to discard staged concept embedding")?;
+
+        Ok(())
+    }
+
+    /// Copy every relationship of `from` to `to`, skipping ones `to` already has
+    async fn copy_concept_relationships(tx: &mut Transaction<'_>, from: Uuid, to: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO concept_relationships (source_concept_id, target_concept_id, relationship_type, similarity_score)
+            SELECT CASE WHEN source_concept_id = $1 THEN $2 ELSE source_concept_id END,
+                   CASE WHEN target_concept_id = $1 THEN $2 ELSE target_concept_id END,
+                   relationship_type, similarity_score
+            FROM concept_relationships
+            WHERE (source_concept_id = $1 OR target_concept_id = $1)
+              AND source_concept_id <> $2 AND target_concept_id <> $2
+            ON CONFLICT (source_concept_id, target_concept_id) DO NOTHING
+            "#,
+            from,
+            to
+        )
+        .execute(&mut **tx)
+        .await
+        .context("Failed to copy concept relationships")?;
+
+        Ok(())
+    }
+
+    /// Bring the search index and the in-memory similarity index up to date with changed
+    /// concepts. The changes are already committed, so a failure is only logged.
+    pub(crate) async fn reindex_concepts(&self, concepts: &[ExtractedConcept]) {
+        for concept in concepts {
+            let indexed = self.index_entity(SearchIndexEntry {
+                entity_type: SearchEntityType::Concept,
+                entity_id: concept.id,
+                title: Some(&concept.name),
+                searchable_text: &format!("{}\n{}", concept.name, concept.description),
+                keywords: &concept.tags,
+                document_id: None,
+                chat_session_id: None,
+                page_number: None,
+            })
+            .await;
+            if let Err(e) = indexed {
+                tracing::warn!("Failed to index concept {}: {}", concept.id, e);
+            }
+        }
+        if let Err(e) = self.sync_vector_index().await {
+            tracing::warn!("Failed to update the concept similarity index: {}", e);
+        }
+    }
+
+    /// Edit a concept's name, description and tags. When the name or description
+    /// changes the embedding is replaced by `embedding`, or cleared without one.
+    /// Returns the updated concept if it exists.
+    pub async fn update_concept(
+        &self,
+        concept_id: Uuid,
+        changes: &ConceptUpdate,
+        embedding: Option<&ConceptEmbedding>,
+    ) -> Result<Option<ExtractedConcept>> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let Some(current) = Self::lock_concept(&mut tx, concept_id).await? else {
+            return Ok(None);
+        };
+        let (name, description) = changes.text_of(&current);
+        validate_text(name, description)?;
+        let tags = merge_tags(changes.tags.as_deref().unwrap_or(&current.tags), &[]);
+        let text_changed = name != current.name || description != current.description;
+
+        sqlx::query!(
+            "UPDATE concepts SET name = $2, description = $3, tags = $4 WHERE id = $1",
+            concept_id,
+            name,
+            description,
+            serde_json::to_value(&tags).unwrap()
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to update concept")?;
+
+        if text_changed {
+            self.set_concept_embedding(&mut tx, concept_id, embedding).await?;
+        }
+
+        let updated = Self::lock_concept(&mut tx, concept_id).await?;
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        if let Some(concept) = &updated {
+            self.reindex_concepts(std::slice::from_ref(concept)).await;
+        }
+        Ok(updated)
+    }
+
+    /// Merge `source_concept_id` into `target_concept_id`: the target takes over the
+    /// source's chat links, relationships and pending proposals, adds its chat count
+    /// and tags, keeps the higher confidence and gets `embedding`. `changes` apply to
+    /// the merged concept. The source is deleted. Returns `None` if either concept
+    /// doesn't exist.
+    pub async fn merge_concepts(
+        &self,
+        target_concept_id: Uuid,
+        source_concept_id: Uuid,
+        changes: &ConceptUpdate,
+        embedding: Option<&ConceptEmbedding>,
+    ) -> Result<Option<ExtractedConcept>> {
+        if target_concept_id == source_concept_id {
+            return Err(anyhow!("A concept cannot be merged into itself"));
+        }
+
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        // Locking both rows in id order keeps two opposite merges from deadlocking
+        sqlx::query!(
+            "SELECT id FROM concepts WHERE id = ANY($1) ORDER BY id FOR UPDATE",
+            &[target_concept_id, source_concept_id][..]
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to lock concepts")?;
+
+        let (Some(target), Some(source)) = (
+            Self::lock_concept(&mut tx, target_concept_id).await?,
+            Self::lock_concept(&mut tx, source_concept_id).await?,
+        ) else {
+            return Ok(None);
+        };
+        let (name, description) = changes.text_of(&target);
+        validate_text(name, description)?;
+        let tags = match &changes.tags {
+            Some(tags) => merge_tags(tags, &[]),
+            None => merge_tags(&target.tags, &source.tags),
+        };
+        let text_changed = name != target.name || description != target.description;
+
+        // Chats both concepts came from count once
+        let shared_chats = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*)::INTEGER AS "count!"
+            FROM concept_chat_links s
+            JOIN concept_chat_links t ON t.chat_session_id = s.chat_session_id AND t.concept_id = $1
+            WHERE s.concept_id = $2
+            "#,
+            target_concept_id,
+            source_concept_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to count shared chat sessions")?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO concept_chat_links (concept_id, chat_session_id, relevance_score, created_at)
+            SELECT $1, chat_session_id, relevance_score, created_at
+            FROM concept_chat_links
+            WHERE concept_id = $2
+            ON CONFLICT (concept_id, chat_session_id) DO UPDATE
+            SET relevance_score = GREATEST(concept_chat_links.relevance_score, EXCLUDED.relevance_score)
+            "#,
+            target_concept_id,
+            source_concept_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to move concept chat links")?;
+
+        Self::copy_concept_relationships(&mut tx, source_concept_id, target_concept_id).await?;
+
+        // Pending proposals to merge into the source now merge into the concept it became part of
+        sqlx::query!(
+            r#"
+            UPDATE concept_proposals
+            SET target_concept_id = $1, similarity_score = NULL
+            WHERE target_concept_id = $2 AND status = 'pending'
+            "#,
+            target_concept_id,
+            source_concept_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to move concept proposals")?;
+
+        sqlx::query!(
+            r#"
+            UPDATE concepts
+            SET name = $2, description = $3, tags = $4,
+                confidence_score = GREATEST(confidence_score, $5),
+                source_chat_count = GREATEST(source_chat_count + $6 - $7, 0)
+            WHERE id = $1
+            "#,
+            target_concept_id,
+            name,
+            description,
+            serde_json::to_value(&tags).unwrap(),
+            source.confidence_score,
+            source.source_chat_count,
+            shared_chats
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to update merged concept")?;
+
+        if embedding.is_some() || text_changed {
+            self.set_concept_embedding(&mut tx, target_concept_id, embedding).await?;
+        }
+
+        // Links, relationships and staged embeddings of the source go with it
+        sqlx::query!("DELETE FROM concepts WHERE id = $1", source_concept_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete merged concept")?;
+        sqlx::query!(
+            "DELETE FROM search_index WHERE entity_type = $1 AND entity_id = $2",
+            SearchEntityType::Concept.as_str(),
+            source_concept_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to remove merged concept from search index")?;
+
+        let merged = Self::lock_concept(&mut tx, target_concept_id).await?;
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        if let Some(concept) = &merged {
+            self.reindex_concepts(std::slice::from_ref(concept)).await;
+        }
+        Ok(merged)
+    }
+
+    /// Split a concept into `parts`. The concept keeps its id and becomes the first
+    /// part; every other part is a new concept with a copy of its relationships and a
+    /// `related` relationship to it. Each part is linked to the chats it lists, or to
+    /// all of the concept's chats, and gets the embedding at the same position in
+    /// `embeddings`. Returns the parts in order, or `None` if the concept doesn't exist.
+    pub async fn split_concept(
+        &self,
+        concept_id: Uuid,
+        parts: &[ConceptSplitPart],
+        embeddings: &[Option<ConceptEmbedding>],
+    ) -> Result<Option<Vec<ExtractedConcept>>> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let Some(original) = Self::lock_concept(&mut tx, concept_id).await? else {
+            return Ok(None);
+        };
+
+        let linked_chats: HashMap<Uuid, f64> = sqlx::query!(
+            "SELECT chat_session_id, relevance_score FROM concept_chat_links WHERE concept_id = $1",
+            concept_id
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to get concept chat links")?
+        .into_iter()
+        .map(|row| (row.chat_session_id, row.relevance_score))
+        .collect();
+        validate_split(parts, &linked_chats)?;
+
+        let mut part_ids = Vec::with_capacity(parts.len());
+        for (index, part) in parts.iter().enumerate() {
+            let embedding = embeddings.get(index).and_then(Option::as_ref);
+            let name = part.name.trim();
+            let description = part.description.trim();
+            let tags = serde_json::to_value(merge_tags(&part.tags, &[])).unwrap();
+            let source_chat_count = match &part.chat_session_ids {
+                Some(chat_session_ids) => chat_session_ids.iter().collect::<HashSet<_>>().len() as i32,
+                None => original.source_chat_count,
+            };
+
+            if index == 0 {
+                sqlx::query!(
+                    r#"
+                    UPDATE concepts
+                    SET name = $2, description = $3, tags = $4, source_chat_count = $5
+                    WHERE id = $1
+                    "#,
+                    concept_id,
+                    name,
+                    description,
+                    tags,
+                    source_chat_count
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to update split concept")?;
+
+                if let Some(chat_session_ids) = &part.chat_session_ids {
+                    sqlx::query!(
+                        "DELETE FROM concept_chat_links WHERE concept_id = $1 AND chat_session_id <> ALL($2)",
+                        concept_id,
+                        chat_session_ids
+                    )
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to unlink chat sessions from split concept")?;
+                }
+
+                if embedding.is_some() || name != original.name || description != original.description {
+                    self.set_concept_embedding(&mut tx, concept_id, embedding).await?;
+                }
+                part_ids.push(concept_id);
+                continue;
+            }
+
+            let part_id = Uuid::new_v4();
+            sqlx::query!(
+                r#"
+                INSERT INTO concepts (id, name, description, tags, confidence_score, source_chat_count)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+                part_id,
+                name,
+                description,
+                tags,
+                original.confidence_score,
+                source_chat_count
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to create split concept")?;
+
+            self.set_concept_embedding(&mut tx, part_id, embedding).await?;
+
+            let chats: Vec<Uuid> = match &part.chat_session_ids {
+                Some(chat_session_ids) => chat_session_ids.clone(),
+                None => linked_chats.keys().copied().collect(),
+            };
+            for chat_session_id in chats {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO concept_chat_links (concept_id, chat_session_id, relevance_score)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT (concept_id, chat_session_id) DO NOTHING
+                    "#,
+                    part_id,
+                    chat_session_id,
+                    linked_chats[&chat_session_id]
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to link split concept to chat session")?;
+            }
+
+            Self::copy_concept_relationships(&mut tx, concept_id, part_id).await?;
+            part_ids.push(part_id);
+        }
+
+        // Added after the copies so new parts don't inherit each other's edge to the original
+        for &part_id in &part_ids[1..] {
+            sqlx::query!(
+                r#"
+                INSERT INTO concept_relationships (source_concept_id, target_concept_id, relationship_type)
+                VALUES ($1, $2, 'related')
+                ON CONFLICT (source_concept_id, target_concept_id) DO NOTHING
+                "#,
+                concept_id,
+                part_id
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to relate split concepts")?;
+        }
+
+        let mut split = Vec::with_capacity(part_ids.len());
+        for part_id in part_ids {
+            split.extend(Self::lock_concept(&mut tx, part_id).await?);
+        }
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        self.reindex_concepts(&split).await;
+        Ok(Some(split))
+    }
+
+    /// Delete a concept with its chat links and relationships, returning whether it existed
+    pub async fn delete_concept(&self, concept_id: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let deleted = sqlx::query!("DELETE FROM concepts WHERE id = $1", concept_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete concept")?;
+
+        sqlx::query!(
+            "DELETE FROM search_index WHERE entity_type = $1 AND entity_id = $2",
+            SearchEntityType::Concept.as_str(),
+            concept_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to remove concept from search index")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        if let Err(e) = self.sync_vector_index().await {
+            tracing::warn!("Failed to update the concept similarity index: {}", e);
+        }
+        Ok(deleted.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(name: &str, chat_session_ids: Option<Vec<Uuid>>) -> ConceptSplitPart {
+        ConceptSplitPart {
+            name: name.to_string(),
+            description: format!("About {}", name),
+            tags: vec![],
+            chat_session_ids,
+        }
+    }
+
+    #[test]
+    fn test_merge_tags_drops_duplicates() {
+        let first = vec!["Physics".to_string(), " ".to_string()];
+        let second = vec!["physics".to_string(), "heat ".to_string()];
+        assert_eq!(merge_tags(&first, &second), vec!["Physics", "heat"]);
+    }
+
+    #[test]
+    fn test_validate_split() {
+        let chat = Uuid::new_v4();
+        let linked = HashMap::from([(chat, 0.5)]);
+
+        assert!(validate_split(&[part("Heat", None)], &linked).is_err());
+        assert!(validate_split(&[part("Heat", None), part(" ", None)], &linked).is_err());
+        assert!(validate_split(&[part("Heat", Some(vec![chat])), part("Work", None)], &linked).is_ok());
+        assert!(validate_split(&[part("Heat", Some(vec![Uuid::new_v4()])), part("Work", None)], &linked).is_err());
+    }
+}
@@ -0,0 +1,420 @@
+// Concept relationship graph database operations
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+use crate::database::{
+    ChatDocumentLink, ConceptChatLink, ConceptGraph, ConceptGraphEdge, ConceptGraphNode, ConceptNeighbor, Database,
+    KnowledgeGraph, KnowledgeGraphChat, KnowledgeGraphDocument, RelationshipDirection,
+};
+
+/// Relationship types allowed by `concept_relationships_type_valid`
+pub const RELATIONSHIP_TYPES: [&str; 5] = ["related", "prerequisite", "builds_on", "similar", "opposite"];
+
+/// The end of an edge that isn't `concept_id`
+fn other_end(edge: &ConceptGraphEdge, concept_id: Uuid) -> Uuid {
+    if edge.source_concept_id == concept_id {
+        edge.target_concept_id
+    } else {
+        edge.source_concept_id
+    }
+}
+
+/// Follow the edges recorded by a breadth-first search back from `to` to `from`
+fn trace_path(
+    parents: &HashMap<Uuid, (Uuid, ConceptGraphEdge)>,
+    from: Uuid,
+    to: Uuid,
+) -> Option<(Vec<Uuid>, Vec<ConceptGraphEdge>)> {
+    let mut concepts = vec![to];
+    let mut edges = Vec::new();
+    let mut current = to;
+    while current != from {
+        let (parent, edge) = parents.get(&current)?;
+        edges.push(edge.clone());
+        concepts.push(*parent);
+        current = *parent;
+    }
+    concepts.reverse();
+    edges.reverse();
+    Some((concepts, edges))
+}
+
+impl Database {
+    /// Store `related` relationships from a concept to the concepts named in `related_names`.
+    /// Names are looked up in `known` (lower-cased names of concepts from the same analysis)
+    /// and then among all concept names; names that match nothing are skipped.
+    /// Returns the number of relationships added.
+    pub async fn link_related_concepts(
+        &self,
+        concept_id: Uuid,
+        related_names: &[String],
+        known: &HashMap<String, Uuid>,
+    ) -> Result<usize> {
+        let mut targets = Vec::new();
+        let mut unknown = Vec::new();
+        for name in related_names {
+            let key = name.trim().to_lowercase();
+            match known.get(&key) {
+                Some(id) => targets.push(*id),
+                None if !key.is_empty() => unknown.push(key),
+                None => {}
+            }
+        }
+
+        if !unknown.is_empty() {
+            // The most discussed concept wins when several share a name
+            let found = sqlx::query_scalar!(
+                r#"
+                SELECT DISTINCT ON (LOWER(name)) id
+                FROM concepts
+                WHERE LOWER(name) = ANY($1)
+                ORDER BY LOWER(name), source_chat_count DESC, created_at
+                "#,
+                &unknown
+            )
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to resolve related concept names")?;
+            targets.extend(found);
+        }
+
+        let mut added = 0;
+        for target_id in targets.into_iter().collect::<HashSet<_>>() {
+            if target_id == concept_id {
+                continue;
+            }
+            // `related` is symmetric, so an existing edge the other way counts too
+            let result = sqlx::query!(
+                r#"
+                INSERT INTO concept_relationships (source_concept_id, target_concept_id, relationship_type)
+                SELECT $1, $2, 'related'
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM concept_relationships
+                    WHERE source_concept_id = $2 AND target_concept_id = $1
+                )
+                ON CONFLICT (source_concept_id, target_concept_id) DO NOTHING
+                "#,
+                concept_id,
+                target_id
+            )
+            .execute(&self.pool)
+            .await
+            .context("Failed to store concept relationship")?;
+            added += result.rows_affected() as usize;
+        }
+
+        Ok(added)
+    }
+
+    /// Relationships touching any of `concept_ids`, optionally limited to some types
+    async fn get_relationships_touching(
+        &self,
+        concept_ids: &[Uuid],
+        relationship_types: Option<&[String]>,
+    ) -> Result<Vec<ConceptGraphEdge>> {
+        let edges = sqlx::query_as!(
+            ConceptGraphEdge,
+            r#"
+            SELECT id, source_concept_id, target_concept_id, relationship_type, similarity_score
+            FROM concept_relationships
+            WHERE (source_concept_id = ANY($1) OR target_concept_id = ANY($1))
+              AND ($2::TEXT[] IS NULL OR relationship_type = ANY($2))
+            ORDER BY similarity_score DESC, created_at
+            "#,
+            concept_ids,
+            relationship_types
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get concept relationships")?;
+
+        Ok(edges)
+    }
+
+    /// Relationships with both ends among `concept_ids`
+    async fn get_relationships_within(
+        &self,
+        concept_ids: &[Uuid],
+        relationship_types: Option<&[String]>,
+    ) -> Result<Vec<ConceptGraphEdge>> {
+        let edges = sqlx::query_as!(
+            ConceptGraphEdge,
+            r#"
+            SELECT id, source_concept_id, target_concept_id, relationship_type, similarity_score
+            FROM concept_relationships
+            WHERE source_concept_id = ANY($1) AND target_concept_id = ANY($1)
+              AND ($2::TEXT[] IS NULL OR relationship_type = ANY($2))
+            ORDER BY similarity_score DESC, created_at
+            "#,
+            concept_ids,
+            relationship_types
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get concept relationships")?;
+
+        Ok(edges)
+    }
+
+    /// Graph nodes for concepts, in the order of `concept_ids`
+    async fn get_graph_nodes(&self, concept_ids: &[Uuid]) -> Result<Vec<ConceptGraphNode>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, name, description, tags, confidence_score, source_chat_count
+            FROM concepts
+            WHERE id = ANY($1)
+            "#,
+            concept_ids
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get concepts")?;
+
+        let mut nodes: HashMap<Uuid, ConceptGraphNode> = rows
+            .into_iter()
+            .map(|row| {
+                let node = ConceptGraphNode {
+                    id: row.id,
+                    name: row.name,
+                    description: row.description,
+                    tags: row.tags
+                        .and_then(|t| serde_json::from_value(t).ok())
+                        .unwrap_or_default(),
+                    confidence_score: row.confidence_score,
+                    source_chat_count: row.source_chat_count,
+                };
+                (node.id, node)
+            })
+            .collect();
+
+        Ok(concept_ids.iter().filter_map(|id| nodes.remove(id)).collect())
+    }
+
+    /// Concepts directly related to a concept, in either direction
+    pub async fn get_concept_neighbors(
+        &self,
+        concept_id: Uuid,
+        relationship_types: Option<&[String]>,
+    ) -> Result<Vec<ConceptNeighbor>> {
+        let edges = self.get_relationships_touching(&[concept_id], relationship_types).await?;
+        let neighbor_ids: Vec<Uuid> = edges.iter().map(|edge| other_end(edge, concept_id)).collect();
+        let nodes: HashMap<Uuid, ConceptGraphNode> = self
+            .get_graph_nodes(&neighbor_ids)
+            .await?
+            .into_iter()
+            .map(|node| (node.id, node))
+            .collect();
+
+        Ok(edges
+            .into_iter()
+            .filter_map(|edge| {
+                let concept = nodes.get(&other_end(&edge, concept_id))?.clone();
+                let direction = if edge.source_concept_id == concept_id {
+                    RelationshipDirection::Outgoing
+                } else {
+                    RelationshipDirection::Incoming
+                };
+                Some(ConceptNeighbor { concept, relationship: edge, direction })
+            })
+            .collect())
+    }
+
+    /// Every concept within `depth` relationships of a concept, at most `max_nodes`
+    /// of them (nearest first), with the relationships among them
+    pub async fn get_concept_subgraph(
+        &self,
+        concept_id: Uuid,
+        depth: u32,
+        max_nodes: usize,
+        relationship_types: Option<&[String]>,
+    ) -> Result<ConceptGraph> {
+        let mut included = vec![concept_id];
+        let mut seen: HashSet<Uuid> = HashSet::from([concept_id]);
+        let mut frontier = vec![concept_id];
+
+        for _ in 0..depth {
+            if frontier.is_empty() || included.len() >= max_nodes {
+                break;
+            }
+            let mut next = Vec::new();
+            for edge in self.get_relationships_touching(&frontier, relationship_types).await? {
+                for end in [edge.source_concept_id, edge.target_concept_id] {
+                    if included.len() < max_nodes && seen.insert(end) {
+                        included.push(end);
+                        next.push(end);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        Ok(ConceptGraph {
+            nodes: self.get_graph_nodes(&included).await?,
+            edges: self.get_relationships_within(&included, relationship_types).await?,
+        })
+    }
+
+    /// Shortest chain of relationships from one concept to another, following
+    /// relationships in either direction. `None` if the concepts aren't connected
+    /// within `max_depth` steps.
+    pub async fn find_concept_path(
+        &self,
+        from: Uuid,
+        to: Uuid,
+        max_depth: u32,
+        relationship_types: Option<&[String]>,
+    ) -> Result<Option<ConceptGraph>> {
+        let mut parents: HashMap<Uuid, (Uuid, ConceptGraphEdge)> = HashMap::new();
+        let mut seen: HashSet<Uuid> = HashSet::from([from]);
+        let mut frontier = vec![from];
+
+        for _ in 0..max_depth {
+            if frontier.is_empty() || seen.contains(&to) {
+                break;
+            }
+            let current: HashSet<Uuid> = frontier.iter().copied().collect();
+            let mut next = Vec::new();
+            for edge in self.get_relationships_touching(&frontier, relationship_types).await? {
+                // An edge between two frontier concepts leads nowhere new
+                for (near, far) in [
+                    (edge.source_concept_id, edge.target_concept_id),
+                    (edge.target_concept_id, edge.source_concept_id),
+                ] {
+                    if current.contains(&near) && seen.insert(far) {
+                        parents.insert(far, (near, edge.clone()));
+                        next.push(far);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        let Some((concept_ids, edges)) = trace_path(&parents, from, to) else {
+            return Ok(None);
+        };
+        let nodes = self.get_graph_nodes(&concept_ids).await?;
+        Ok(Some(ConceptGraph { nodes, edges }))
+    }
+
+    /// The whole knowledge graph: every concept, the chats linked to concepts, the
+    /// documents those chats highlighted and all concept relationships
+    pub async fn get_knowledge_graph(&self) -> Result<KnowledgeGraph> {
+        let concept_ids = sqlx::query_scalar!("SELECT id FROM concepts ORDER BY name, id")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list concepts")?;
+        let concepts = self.get_graph_nodes(&concept_ids).await?;
+
+        let concept_chat_links = sqlx::query_as!(
+            ConceptChatLink,
+            r#"
+            SELECT concept_id, chat_session_id, relevance_score
+            FROM concept_chat_links
+            ORDER BY concept_id, chat_session_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list concept chat links")?;
+
+        let chats = sqlx::query_as!(
+            KnowledgeGraphChat,
+            r#"
+            SELECT id, title, created_at
+            FROM chat_sessions
+            WHERE id IN (SELECT chat_session_id FROM concept_chat_links)
+            ORDER BY created_at
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list chat sessions")?;
+
+        let chat_document_links = sqlx::query_as!(
+            ChatDocumentLink,
+            r#"
+            SELECT chat_session_id, document_id, COUNT(*) AS "highlight_count!"
+            FROM highlighted_contexts
+            WHERE chat_session_id IN (SELECT chat_session_id FROM concept_chat_links)
+            GROUP BY chat_session_id, document_id
+            ORDER BY chat_session_id, document_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list chat documents")?;
+
+        let documents = sqlx::query_as!(
+            KnowledgeGraphDocument,
+            r#"
+            SELECT id, title, author
+            FROM documents
+            WHERE id IN (
+                SELECT hc.document_id
+                FROM highlighted_contexts hc
+                JOIN concept_chat_links ccl ON ccl.chat_session_id = hc.chat_session_id
+            )
+            ORDER BY title
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list documents")?;
+
+        let relationships = sqlx::query_as!(
+            ConceptGraphEdge,
+            r#"
+            SELECT id, source_concept_id, target_concept_id, relationship_type, similarity_score
+            FROM concept_relationships
+            ORDER BY created_at, id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list concept relationships")?;
+
+        Ok(KnowledgeGraph {
+            concepts,
+            chats,
+            documents,
+            concept_chat_links,
+            chat_document_links,
+            relationships,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(source: Uuid, target: Uuid) -> ConceptGraphEdge {
+        ConceptGraphEdge {
+            id: Uuid::new_v4(),
+            source_concept_id: source,
+            target_concept_id: target,
+            relationship_type: "related".to_string(),
+            similarity_score: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_trace_path_follows_parents_back_to_start() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let ab = edge(a, b);
+        // Walked against its direction
+        let bc = edge(c, b);
+        let parents = HashMap::from([(b, (a, ab.clone())), (c, (b, bc.clone()))]);
+
+        let (concepts, edges) = trace_path(&parents, a, c).unwrap();
+        assert_eq!(concepts, vec![a, b, c]);
+        assert_eq!(edges.iter().map(|e| e.id).collect::<Vec<_>>(), vec![ab.id, bc.id]);
+
+        let (concepts, edges) = trace_path(&parents, a, a).unwrap();
+        assert_eq!(concepts, vec![a]);
+        assert!(edges.is_empty());
+
+        assert!(trace_path(&parents, a, Uuid::new_v4()).is_none());
+        assert_eq!(other_end(&bc, b), c);
+    }
+}
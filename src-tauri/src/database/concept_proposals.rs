@@ -0,0 +1,465 @@
+// Review queue of concepts proposed by analyses
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::database::concept_edits::{merge_tags, ConceptEmbedding, ConceptUpdate};
+use crate::database::concept_reconcile::{extracted_embedding, ReextractedConcept};
+use crate::database::{ConceptProposal, Database};
+
+type Transaction<'a> = sqlx::Transaction<'a, sqlx::Postgres>;
+
+/// A concept extracted by an analysis, and the existing concept it would be merged into
+pub struct NewConceptProposal {
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub confidence_score: f64,
+    pub related_concepts: Vec<String>,
+    pub embedding: Option<ConceptEmbedding>,
+    /// The existing concept and how similar it is; `None` proposes a new concept
+    pub target: Option<(Uuid, Option<f64>)>,
+}
+
+impl NewConceptProposal {
+    /// Read from an extracted concept in the form sent to the concept processor
+    pub fn from_extracted(concept: &Value, target: Option<(Uuid, Option<f64>)>) -> Self {
+        let text = |key: &str| concept.get(key).and_then(|v| v.as_str()).unwrap_or_default().trim().to_string();
+        let list = |key: &str| {
+            concept
+                .get(key)
+                .and_then(|v| serde_json::from_value::<Vec<String>>(v.clone()).ok())
+                .unwrap_or_default()
+        };
+
+        Self {
+            name: text("name"),
+            description: text("description"),
+            tags: list("tags"),
+            confidence_score: concept.get("confidence_score").and_then(|v| v.as_f64()).unwrap_or(0.5),
+            related_concepts: list("related_concepts"),
+            embedding: extracted_embedding(concept),
+            target,
+        }
+    }
+}
+
+/// A pending proposal as locked for a change
+struct PendingProposal {
+    chat_session_id: Uuid,
+    analysis_job_id: Uuid,
+    name: String,
+    description: String,
+    tags: Vec<String>,
+    confidence_score: f64,
+    embedding: Option<ConceptEmbedding>,
+    /// Whether the proposal merges into a concept; its target is `None` if that was deleted
+    merge: bool,
+    target_concept_id: Option<Uuid>,
+}
+
+impl Database {
+    /// Store the concepts an analysis extracted for review in place of the chat session's
+    /// pending proposals, which are rejected. Returns how many were stored.
+    pub async fn create_concept_proposals(
+        &self,
+        analysis_job_id: Uuid,
+        chat_session_id: Uuid,
+        proposals: &[NewConceptProposal],
+    ) -> Result<usize> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        // A new analysis of the chat supersedes the earlier ones
+        sqlx::query!(
+            r#"
+            UPDATE concept_proposals
+            SET status = 'rejected', reviewed_at = NOW()
+            WHERE chat_session_id = $1 AND status = 'pending'
+            "#,
+            chat_session_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to reject superseded concept proposals")?;
+
+        for proposal in proposals {
+            sqlx::query!(
+                r#"
+                INSERT INTO concept_proposals (
+                    analysis_job_id, chat_session_id, name, description, tags, confidence_score,
+                    related_concepts, embedding, embedding_model, decision, target_concept_id,
+                    similarity_score
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8::REAL[], $9, $10, $11, $12)
+                "#,
+                analysis_job_id,
+                chat_session_id,
+                proposal.name,
+                proposal.description,
+                serde_json::to_value(merge_tags(&proposal.tags, &[])).unwrap(),
+                proposal.confidence_score.clamp(0.0, 1.0),
+                serde_json::to_value(&proposal.related_concepts).unwrap(),
+                proposal.embedding.as_ref().map(|e| e.vector.as_slice()),
+                proposal.embedding.as_ref().map(|e| e.model.as_str()),
+                if proposal.target.is_some() { "merge" } else { "new" },
+                proposal.target.map(|(id, _)| id),
+                proposal.target.and_then(|(_, similarity)| similarity)
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to store concept proposal")?;
+        }
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(proposals.len())
+    }
+
+    /// Proposals matching every filter that is given, oldest first
+    async fn query_concept_proposals(
+        &self,
+        proposal_id: Option<Uuid>,
+        status: Option<&str>,
+        chat_session_id: Option<Uuid>,
+    ) -> Result<Vec<ConceptProposal>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT p.id, p.analysis_job_id, p.chat_session_id, p.name, p.description, p.tags,
+                   p.confidence_score, p.related_concepts,
+                   p.decision,
+                   p.target_concept_id, c.name AS "target_concept_name?", p.similarity_score,
+                   p.status, p.concept_id, p.created_at, p.reviewed_at
+            FROM concept_proposals p
+            LEFT JOIN concepts c ON c.id = p.target_concept_id
+            WHERE ($1::UUID IS NULL OR p.id = $1)
+              AND ($2::TEXT IS NULL OR p.status = $2)
+              AND ($3::UUID IS NULL OR p.chat_session_id = $3)
+            ORDER BY p.created_at, p.name
+            "#,
+            proposal_id,
+            status,
+            chat_session_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get concept proposals")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ConceptProposal {
+                id: row.id,
+                analysis_job_id: row.analysis_job_id,
+                chat_session_id: row.chat_session_id,
+                name: row.name,
+                description: row.description,
+                tags: serde_json::from_value(row.tags).unwrap_or_default(),
+                confidence_score: row.confidence_score,
+                related_concepts: serde_json::from_value(row.related_concepts).unwrap_or_default(),
+                decision: row.decision,
+                target_concept_id: row.target_concept_id,
+                target_concept_name: row.target_concept_name,
+                similarity_score: row.similarity_score,
+                status: row.status,
+                concept_id: row.concept_id,
+                created_at: row.created_at,
+                reviewed_at: row.reviewed_at,
+            })
+            .collect())
+    }
+
+    pub async fn get_concept_proposal(&self, proposal_id: Uuid) -> Result<Option<ConceptProposal>> {
+        Ok(self.query_concept_proposals(Some(proposal_id), None, None).await?.pop())
+    }
+
+    /// Proposals oldest first, optionally only those with a status or from one chat session
+    pub async fn list_concept_proposals(
+        &self,
+        status: Option<&str>,
+        chat_session_id: Option<Uuid>,
+    ) -> Result<Vec<ConceptProposal>> {
+        self.query_concept_proposals(None, status, chat_session_id).await
+    }
+
+    /// Read a proposal inside a transaction and lock it until the transaction ends.
+    /// `None` if it doesn't exist; an error if it was already reviewed.
+    async fn lock_pending_proposal(tx: &mut Transaction<'_>, proposal_id: Uuid) -> Result<Option<PendingProposal>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT chat_session_id, analysis_job_id, name, description, tags, confidence_score,
+                   embedding AS "embedding: Vec<f32>", embedding_model, decision, target_concept_id, status
+            FROM concept_proposals
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            proposal_id
+        )
+        .fetch_optional(&mut **tx)
+        .await
+        .context("Failed to get concept proposal")?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        if row.status != "pending" {
+            return Err(anyhow!("The proposal was already {}", row.status));
+        }
+
+        Ok(Some(PendingProposal {
+            chat_session_id: row.chat_session_id,
+            analysis_job_id: row.analysis_job_id,
+            name: row.name,
+            description: row.description,
+            tags: serde_json::from_value(row.tags).unwrap_or_default(),
+            confidence_score: row.confidence_score,
+            embedding: row
+                .embedding
+                .zip(row.embedding_model)
+                .map(|(vector, model)| ConceptEmbedding { model, vector }),
+            merge: row.decision == "merge",
+            target_concept_id: row.target_concept_id,
+        }))
+    }
+
+    /// Edit a pending proposal's name, description and tags. When the name or description
+    /// changes the embedding is replaced by `embedding`, or cleared without one.
+    pub async fn update_concept_proposal(
+        &self,
+        proposal_id: Uuid,
+        changes: &ConceptUpdate,
+        embedding: Option<&ConceptEmbedding>,
+    ) -> Result<Option<ConceptProposal>> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let Some(current) = Self::lock_pending_proposal(&mut tx, proposal_id).await? else {
+            return Ok(None);
+        };
+        let name = changes.name.as_deref().map(str::trim).unwrap_or(&current.name);
+        let description = changes.description.as_deref().map(str::trim).unwrap_or(&current.description);
+        if name.is_empty() {
+            return Err(anyhow!("Concept name cannot be empty"));
+        }
+        if description.is_empty() {
+            return Err(anyhow!("Concept description cannot be empty"));
+        }
+        let tags = merge_tags(changes.tags.as_deref().unwrap_or(&current.tags), &[]);
+        let embedding = if name != current.name || description != current.description {
+            embedding
+        } else {
+            current.embedding.as_ref()
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE concept_proposals
+            SET name = $2, description = $3, tags = $4, embedding = $5::REAL[], embedding_model = $6
+            WHERE id = $1
+            "#,
+            proposal_id,
+            name,
+            description,
+            serde_json::to_value(&tags).unwrap(),
+            embedding.map(|e| e.vector.as_slice()),
+            embedding.map(|e| e.model.as_str())
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to update concept proposal")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        self.get_concept_proposal(proposal_id).await
+    }
+
+    /// Change what a pending proposal is committed as: merged into `target_concept_id`,
+    /// or a new concept when that is `None`
+    pub async fn retarget_concept_proposal(
+        &self,
+        proposal_id: Uuid,
+        target_concept_id: Option<Uuid>,
+    ) -> Result<Option<ConceptProposal>> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        if Self::lock_pending_proposal(&mut tx, proposal_id).await?.is_none() {
+            return Ok(None);
+        }
+        if let Some(target_concept_id) = target_concept_id {
+            if Self::lock_concept(&mut tx, target_concept_id).await?.is_none() {
+                return Err(anyhow!("Concept {} not found", target_concept_id));
+            }
+        }
+
+        // A target the user picked has no similarity score
+        sqlx::query!(
+            r#"
+            UPDATE concept_proposals
+            SET target_concept_id = $2, similarity_score = NULL,
+                decision = CASE WHEN $2::UUID IS NULL THEN 'new' ELSE 'merge' END
+            WHERE id = $1
+            "#,
+            proposal_id,
+            target_concept_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to retarget concept proposal")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        self.get_concept_proposal(proposal_id).await
+    }
+
+    /// Discard a pending proposal
+    pub async fn reject_concept_proposal(&self, proposal_id: Uuid) -> Result<Option<ConceptProposal>> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        if Self::lock_pending_proposal(&mut tx, proposal_id).await?.is_none() {
+            return Ok(None);
+        }
+        sqlx::query!(
+            "UPDATE concept_proposals SET status = 'rejected', reviewed_at = NOW() WHERE id = $1",
+            proposal_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to reject concept proposal")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        self.get_concept_proposal(proposal_id).await
+    }
+
+    /// Commit a pending proposal to the knowledge base: merge it into its target concept,
+    /// linking that to the proposal's chat session, or store it as a new concept. Its
+    /// related concepts are linked once they exist, counting the other proposals of the
+    /// same analysis accepted so far. A merge whose target concept was deleted is refused.
+    pub async fn accept_concept_proposal(&self, proposal_id: Uuid) -> Result<Option<ConceptProposal>> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let Some(proposal) = Self::lock_pending_proposal(&mut tx, proposal_id).await? else {
+            return Ok(None);
+        };
+        if proposal.merge && proposal.target_concept_id.is_none() {
+            return Err(anyhow!("The concept this proposal would be merged into no longer exists"));
+        }
+        let relevance = proposal.confidence_score.clamp(0.0, 1.0);
+
+        let concept_id = match proposal.target_concept_id {
+            Some(target_concept_id) => {
+                let linked = sqlx::query!(
+                    r#"
+                    INSERT INTO concept_chat_links (concept_id, chat_session_id, relevance_score)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT (concept_id, chat_session_id) DO NOTHING
+                    "#,
+                    target_concept_id,
+                    proposal.chat_session_id,
+                    relevance
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to link concept to chat session")?;
+
+                if linked.rows_affected() > 0 {
+                    // Another chat's concept keeps its description and gains the new tags
+                    let target = Self::lock_concept(&mut tx, target_concept_id)
+                        .await?
+                        .ok_or_else(|| anyhow!("Concept {} not found", target_concept_id))?;
+                    sqlx::query!(
+                        "UPDATE concepts SET tags = $2, source_chat_count = source_chat_count + 1 WHERE id = $1",
+                        target_concept_id,
+                        serde_json::to_value(merge_tags(&target.tags, &proposal.tags)).unwrap()
+                    )
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to update concept")?;
+                } else {
+                    // Already this chat's concept, so the proposal is a re-analysis of it
+                    let reextracted = ReextractedConcept {
+                        concept_id: target_concept_id,
+                        description: proposal.description.clone(),
+                        tags: proposal.tags.clone(),
+                        confidence_score: proposal.confidence_score,
+                        embedding: proposal.embedding,
+                    };
+                    self.refresh_reextracted_concept(&mut tx, proposal.chat_session_id, &reextracted).await?;
+                }
+                target_concept_id
+            }
+            None => {
+                let concept_id = Uuid::new_v4();
+                sqlx::query!(
+                    r#"
+                    INSERT INTO concepts (id, name, description, tags, confidence_score, source_chat_count)
+                    VALUES ($1, $2, $3, $4, $5, 1)
+                    "#,
+                    concept_id,
+                    proposal.name,
+                    proposal.description,
+                    serde_json::to_value(&proposal.tags).unwrap(),
+                    relevance
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to create concept")?;
+
+                self.set_concept_embedding(&mut tx, concept_id, proposal.embedding.as_ref()).await?;
+
+                sqlx::query!(
+                    r#"
+                    INSERT INTO concept_chat_links (concept_id, chat_session_id, relevance_score)
+                    VALUES ($1, $2, $3)
+                    "#,
+                    concept_id,
+                    proposal.chat_session_id,
+                    relevance
+                )
+                .execute(&mut *tx)
+                .await
+                .context("Failed to link concept to chat session")?;
+                concept_id
+            }
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE concept_proposals
+            SET status = 'accepted', concept_id = $2, reviewed_at = NOW()
+            WHERE id = $1
+            "#,
+            proposal_id,
+            concept_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to accept concept proposal")?;
+
+        let concept = Self::lock_concept(&mut tx, concept_id).await?;
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        self.reindex_concepts(concept.as_slice()).await;
+
+        // Relationships between the accepted proposals of the analysis, including
+        // ones that named this concept before it existed
+        let accepted = sqlx::query!(
+            r#"
+            SELECT name, related_concepts, concept_id AS "concept_id!"
+            FROM concept_proposals
+            WHERE analysis_job_id = $1 AND status = 'accepted' AND concept_id IS NOT NULL
+            "#,
+            proposal.analysis_job_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get accepted concept proposals")?;
+        let known: HashMap<String, Uuid> = accepted
+            .iter()
+            .map(|row| (row.name.trim().to_lowercase(), row.concept_id))
+            .collect();
+        for row in &accepted {
+            let related: Vec<String> = serde_json::from_value(row.related_concepts.clone()).unwrap_or_default();
+            self.link_related_concepts(row.concept_id, &related, &known).await?;
+        }
+
+        self.get_concept_proposal(proposal_id).await
+    }
+}
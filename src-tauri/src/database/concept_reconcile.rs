@@ -0,0 +1,289 @@
+// Reconciling a re-analysis of a chat session with the concepts already linked to it
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::HashSet;
+use uuid::Uuid;
+use crate::database::concept_edits::{merge_tags, ConceptEmbedding};
+use crate::database::concepts::ConceptForMatching;
+use crate::database::{AnalysisConceptChange, Database, ExtractedConcept};
+
+type Transaction<'a> = sqlx::Transaction<'a, sqlx::Postgres>;
+
+/// Similarity from which an extracted concept is the same as a linked one;
+/// the Python processor's `STRONG_MATCH_THRESHOLD`
+const SAME_CONCEPT_THRESHOLD: f32 = 0.70;
+
+/// A concept linked to a chat session
+#[derive(Debug, Clone)]
+pub struct LinkedConcept {
+    pub id: Uuid,
+    pub name: String,
+}
+
+/// What a re-analysis extracted again for a concept already linked to the chat session
+pub struct ReextractedConcept {
+    pub concept_id: Uuid,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub confidence_score: f64,
+    pub embedding: Option<ConceptEmbedding>,
+}
+
+impl ReextractedConcept {
+    /// Read from an extracted concept in the form sent to the concept processor
+    pub fn from_extracted(concept_id: Uuid, concept: &Value) -> Self {
+        Self {
+            concept_id,
+            description: concept.get("description").and_then(|v| v.as_str()).unwrap_or_default().trim().to_string(),
+            tags: concept
+                .get("tags")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default(),
+            confidence_score: concept.get("confidence_score").and_then(|v| v.as_f64()).unwrap_or(0.5),
+            embedding: extracted_embedding(concept),
+        }
+    }
+}
+
+/// The embedding attached to an extracted concept before it goes to the concept processor
+pub fn extracted_embedding(concept: &Value) -> Option<ConceptEmbedding> {
+    let vector = concept
+        .get("embedding")
+        .and_then(|v| serde_json::from_value::<Vec<f32>>(v.clone()).ok())?;
+    let model = concept.get("embedding_model").and_then(|v| v.as_str())?;
+    Some(ConceptEmbedding { model: model.to_string(), vector })
+}
+
+fn normalized_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// The candidate most similar to `embedding`, with its similarity, if it is similar
+/// enough to be the same concept
+pub fn same_concept<'a>(
+    embedding: &[f32],
+    candidates: impl IntoIterator<Item = &'a ConceptForMatching>,
+) -> Option<(Uuid, f32)> {
+    candidates
+        .into_iter()
+        .filter(|c| c.embedding.len() == embedding.len())
+        .map(|c| (c.id, cosine_similarity(embedding, &c.embedding)))
+        .filter(|(_, similarity)| *similarity >= SAME_CONCEPT_THRESHOLD)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// For each extracted concept, the linked concept it is the same as: the one with the
+/// same name, or else the linked concept whose embedding (from `embeddings`) is most
+/// similar, if similar enough. Each linked concept is matched at most once.
+pub fn match_linked_concepts(
+    extracted: &[Value],
+    linked: &[LinkedConcept],
+    embeddings: &[ConceptForMatching],
+) -> Vec<Option<Uuid>> {
+    let mut matched = vec![None; extracted.len()];
+    let mut taken = HashSet::new();
+
+    // Names first, so a similar concept can't take the place of an exact match
+    for (slot, concept) in matched.iter_mut().zip(extracted) {
+        let name = normalized_name(concept.get("name").and_then(|v| v.as_str()).unwrap_or_default());
+        if let Some(found) = linked.iter().find(|l| !taken.contains(&l.id) && normalized_name(&l.name) == name) {
+            taken.insert(found.id);
+            *slot = Some(found.id);
+        }
+    }
+
+    let linked_ids: HashSet<Uuid> = linked.iter().map(|l| l.id).collect();
+    let candidates: Vec<&ConceptForMatching> = embeddings.iter().filter(|c| linked_ids.contains(&c.id)).collect();
+    for (slot, concept) in matched.iter_mut().zip(extracted) {
+        if slot.is_some() {
+            continue;
+        }
+        let Some(embedding) = concept
+            .get("embedding")
+            .and_then(|v| serde_json::from_value::<Vec<f32>>(v.clone()).ok())
+        else {
+            continue;
+        };
+        let best = same_concept(&embedding, candidates.iter().copied().filter(|c| !taken.contains(&c.id)));
+        if let Some((id, _)) = best {
+            taken.insert(id);
+            *slot = Some(id);
+        }
+    }
+
+    matched
+}
+
+impl Database {
+    /// Concepts linked to a chat session
+    pub async fn get_linked_concepts(&self, chat_session_id: Uuid) -> Result<Vec<LinkedConcept>> {
+        let concepts = sqlx::query_as!(
+            LinkedConcept,
+            r#"
+            SELECT c.id, c.name
+            FROM concepts c
+            JOIN concept_chat_links ccl ON ccl.concept_id = c.id
+            WHERE ccl.chat_session_id = $1
+            ORDER BY c.name
+            "#,
+            chat_session_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get linked concepts")?;
+
+        Ok(concepts)
+    }
+
+    /// Give a concept linked to a chat session what the chat's latest analysis extracted
+    /// for it: the new description, the new tags added and the new relevance. Returns the
+    /// concept if its description or tags changed.
+    pub(crate) async fn refresh_reextracted_concept(
+        &self,
+        tx: &mut Transaction<'_>,
+        chat_session_id: Uuid,
+        concept: &ReextractedConcept,
+    ) -> Result<Option<ExtractedConcept>> {
+        let Some(current) = Self::lock_concept(tx, concept.concept_id).await? else {
+            return Ok(None);
+        };
+        let description = if concept.description.is_empty() {
+            current.description.as_str()
+        } else {
+            concept.description.as_str()
+        };
+        let tags = merge_tags(&current.tags, &concept.tags);
+
+        let mut changed = None;
+        if description != current.description || tags != current.tags {
+            sqlx::query!(
+                "UPDATE concepts SET description = $2, tags = $3 WHERE id = $1",
+                concept.concept_id,
+                description,
+                serde_json::to_value(&tags).unwrap()
+            )
+            .execute(&mut **tx)
+            .await
+            .context("Failed to update concept")?;
+
+            if description != current.description {
+                self.set_concept_embedding(tx, concept.concept_id, concept.embedding.as_ref()).await?;
+            }
+            changed = Self::lock_concept(tx, concept.concept_id).await?;
+        }
+
+        sqlx::query!(
+            "UPDATE concept_chat_links SET relevance_score = $3 WHERE concept_id = $1 AND chat_session_id = $2",
+            concept.concept_id,
+            chat_session_id,
+            concept.confidence_score.clamp(0.0, 1.0)
+        )
+        .execute(&mut **tx)
+        .await
+        .context("Failed to update concept relevance")?;
+
+        Ok(changed)
+    }
+
+    /// Apply a re-analysis to the concepts linked to a chat session: concepts extracted
+    /// again are refreshed and the links in `unlinked` are removed. Returns the concepts
+    /// whose description or tags changed, and the unlinked ones.
+    pub async fn reconcile_chat_concepts(
+        &self,
+        chat_session_id: Uuid,
+        reextracted: &[ReextractedConcept],
+        unlinked: &[Uuid],
+    ) -> Result<(Vec<AnalysisConceptChange>, Vec<AnalysisConceptChange>)> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let mut changed = Vec::new();
+        for concept in reextracted {
+            changed.extend(self.refresh_reextracted_concept(&mut tx, chat_session_id, concept).await?);
+        }
+
+        let removed = sqlx::query!(
+            r#"
+            WITH removed AS (
+                DELETE FROM concept_chat_links
+                WHERE chat_session_id = $1 AND concept_id = ANY($2)
+                RETURNING concept_id
+            )
+            UPDATE concepts c
+            SET source_chat_count = GREATEST(c.source_chat_count - 1, 0)
+            FROM removed
+            WHERE c.id = removed.concept_id
+            RETURNING c.id, c.name
+            "#,
+            chat_session_id,
+            unlinked
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to unlink concepts")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        self.reindex_concepts(&changed).await;
+
+        let updated = changed
+            .into_iter()
+            .map(|concept| AnalysisConceptChange { concept_id: concept.id, name: concept.name })
+            .collect();
+        let unlinked = removed
+            .into_iter()
+            .map(|row| AnalysisConceptChange { concept_id: row.id, name: row.name })
+            .collect();
+        Ok((updated, unlinked))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn linked(name: &str) -> LinkedConcept {
+        LinkedConcept { id: Uuid::new_v4(), name: name.to_string() }
+    }
+
+    #[test]
+    fn test_match_by_name_before_embedding() {
+        let recursion = linked("Recursion");
+        let induction = linked("Induction");
+        let embeddings = vec![
+            ConceptForMatching { id: recursion.id, name: recursion.name.clone(), embedding: vec![1.0, 0.0] },
+            ConceptForMatching { id: induction.id, name: induction.name.clone(), embedding: vec![0.0, 1.0] },
+        ];
+        let extracted = vec![
+            // Closest to Recursion, which the exact name below claims first
+            json!({ "name": "Recursive functions", "embedding": [0.9, 0.1] }),
+            json!({ "name": " recursion ", "embedding": [0.0, 1.0] }),
+            json!({ "name": "Mathematical induction", "embedding": [0.2, 0.9] }),
+            json!({ "name": "Unrelated", "embedding": [-1.0, 0.0] }),
+        ];
+
+        let matched = match_linked_concepts(&extracted, &[recursion.clone(), induction.clone()], &embeddings);
+
+        assert_eq!(matched, vec![None, Some(recursion.id), Some(induction.id), None]);
+    }
+
+    #[test]
+    fn test_match_without_embeddings_uses_names_only() {
+        let concept = linked("Big O notation");
+        let extracted = vec![json!({ "name": "Big O Notation" }), json!({ "name": "Big O" })];
+
+        let matched = match_linked_concepts(&extracted, std::slice::from_ref(&concept), &[]);
+
+        assert_eq!(matched, vec![Some(concept.id), None]);
+    }
+}
@@ -1,7 +1,22 @@
 // Concept extraction database operations
 use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use futures_util::stream::{self, Stream, TryStreamExt};
+use pgvector::Vector;
 use uuid::Uuid;
-use crate::database::{Database, ExtractedConcept};
+use crate::database::concept_reconcile::cosine_similarity;
+use crate::database::search_index::{SearchEntityType, SearchIndexEntry};
+use crate::database::search::build_tsquery;
+use crate::database::{ConceptMatchReason, ConceptSearchResult, Database, ExtractedConcept, SimilarConcept};
+
+/// Share of the hybrid search score that comes from embedding similarity; the rest is full-text rank
+const SEMANTIC_WEIGHT: f64 = 0.7;
+/// Bonus for concepts whose name contains the query
+const NAME_MATCH_BOOST: f64 = 0.1;
+/// Nearest neighbors of the query embedding considered by a hybrid search
+const SEMANTIC_CANDIDATES: i64 = 100;
+/// Concepts fetched per query when loading embeddings for matching
+const MATCHING_PAGE_SIZE: i64 = 500;
 
 /// Represents a concept record specifically for similarity matching.
 /// Includes the embedding vector which is otherwise not always needed.
@@ -13,6 +28,60 @@ pub struct ConceptForMatching {
     pub embedding: Vec<f32>,
 }
 
+/// The concepts kept while streaming through all of them for matching: the most similar
+/// one to each new embedding so far, and the ones always included
+struct MatchingCandidates<'a> {
+    embeddings: &'a [Vec<f32>],
+    include: &'a HashSet<Uuid>,
+    best: Vec<Option<(f32, Uuid)>>,
+    kept: HashMap<Uuid, ConceptForMatching>,
+}
+
+impl<'a> MatchingCandidates<'a> {
+    fn new(embeddings: &'a [Vec<f32>], include: &'a HashSet<Uuid>) -> Self {
+        Self { embeddings, include, best: vec![None; embeddings.len()], kept: HashMap::new() }
+    }
+
+    fn add_page(&mut self, page: Vec<ConceptForMatching>) {
+        for concept in page {
+            let mut keep = self.include.contains(&concept.id);
+            for (best, embedding) in self.best.iter_mut().zip(self.embeddings) {
+                if embedding.len() != concept.embedding.len() {
+                    continue;
+                }
+                let similarity = cosine_similarity(embedding, &concept.embedding);
+                if best.is_none_or(|(best_similarity, _)| similarity > best_similarity) {
+                    *best = Some((similarity, concept.id));
+                    keep = true;
+                }
+            }
+            if keep {
+                self.kept.insert(concept.id, concept);
+            }
+        }
+
+        // Drop the concepts that have since been outmatched
+        let best_ids: HashSet<Uuid> = self.best.iter().flatten().map(|(_, id)| *id).collect();
+        let include = self.include;
+        self.kept.retain(|id, _| include.contains(id) || best_ids.contains(id));
+    }
+
+    fn into_concepts(self) -> Vec<ConceptForMatching> {
+        let mut concepts: Vec<ConceptForMatching> = self.kept.into_values().collect();
+        concepts.sort_by_key(|concept| concept.id);
+        concepts
+    }
+}
+
+/// Blend embedding similarity and full-text rank (both 0.0-1.0) into one score.
+/// `semantic_weight` is 0.0 when the search ran without a query embedding.
+pub fn hybrid_score(semantic: Option<f64>, lexical: Option<f64>, name_match: bool, semantic_weight: f64) -> f64 {
+    let score = semantic_weight * semantic.unwrap_or(0.0)
+        + (1.0 - semantic_weight) * lexical.unwrap_or(0.0)
+        + if name_match { NAME_MATCH_BOOST } else { 0.0 };
+    score.clamp(0.0, 1.0)
+}
+
 impl Database {
     /// Store an extracted concept in the database
     pub async fn store_extracted_concept(
@@ -57,45 +126,227 @@ impl Database {
         .await
         .context("Failed to link concept to chat session")?;
 
+        self.index_entity(SearchIndexEntry {
+            entity_type: SearchEntityType::Concept,
+            entity_id: concept_id,
+            title: Some(name),
+            searchable_text: &format!("{}\n{}", name, description),
+            keywords: tags,
+            document_id: None,
+            chat_session_id: None,
+            page_number: None,
+        })
+        .await?;
+
         Ok(concept_id)
     }
 
-    /// Find similar concepts (simplified version)
-    pub async fn find_similar_concepts(&self, _concept_id: Uuid, _similarity_threshold: f64, max_results: i32) -> Result<Vec<serde_json::Value>> {
-        // Simplified implementation - just return empty for now
-        let _limit = max_results;
-        Ok(vec![])
+    /// Find the concepts closest to a concept by cosine similarity of their embeddings.
+    /// Returns nothing when the concept has no embedding yet.
+    pub async fn find_similar_concepts(&self, concept_id: Uuid, similarity_threshold: f64, max_results: i32) -> Result<Vec<SimilarConcept>> {
+        let max_results = max_results.max(0);
+
+        let neighbors = self.vector_index.read().await.as_ref().map(|index| {
+            index
+                .similar_to(concept_id, max_results as usize)
+                .into_iter()
+                .filter(|(_, similarity)| *similarity >= similarity_threshold)
+                .collect::<Vec<_>>()
+        });
+        if let Some(neighbors) = neighbors {
+            return self.get_similar_concepts_by_id(&neighbors).await;
+        }
+
+        // Only embeddings of the same model can be compared
+        let rows = sqlx::query!(
+            r#"
+            SELECT c.id, c.name, c.description, c.tags, c.confidence_score,
+                   1 - (c.embedding <=> target.embedding) AS "similarity_score!"
+            FROM concepts target
+            JOIN concepts c ON c.embedding_model = target.embedding_model AND c.id <> target.id
+            WHERE target.id = $1
+              AND target.embedding IS NOT NULL
+              AND c.embedding IS NOT NULL
+              AND 1 - (c.embedding <=> target.embedding) >= $2
+            ORDER BY c.embedding <=> target.embedding
+            LIMIT $3
+            "#,
+            concept_id,
+            similarity_threshold,
+            max_results as i64
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to find similar concepts")?;
+
+        let concepts = rows
+            .into_iter()
+            .map(|row| SimilarConcept {
+                id: row.id,
+                name: row.name,
+                description: row.description,
+                tags: row.tags
+                    .and_then(|t| serde_json::from_value(t).ok())
+                    .unwrap_or_default(),
+                confidence_score: row.confidence_score,
+                similarity_score: row.similarity_score,
+            })
+            .collect();
+
+        Ok(concepts)
+    }
+
+    /// Load the concepts found by the in-memory index, keeping its order
+    async fn get_similar_concepts_by_id(&self, neighbors: &[(Uuid, f64)]) -> Result<Vec<SimilarConcept>> {
+        let ids: Vec<Uuid> = neighbors.iter().map(|(id, _)| *id).collect();
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, name, description, tags, confidence_score
+            FROM concepts
+            WHERE id = ANY($1)
+            "#,
+            &ids
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to find similar concepts")?;
+
+        let mut rows: HashMap<Uuid, _> = rows.into_iter().map(|row| (row.id, row)).collect();
+        Ok(neighbors
+            .iter()
+            .filter_map(|(id, similarity)| {
+                let row = rows.remove(id)?;
+                Some(SimilarConcept {
+                    id: row.id,
+                    name: row.name,
+                    description: row.description,
+                    tags: row.tags
+                        .and_then(|t| serde_json::from_value(t).ok())
+                        .unwrap_or_default(),
+                    confidence_score: row.confidence_score,
+                    similarity_score: *similarity,
+                })
+            })
+            .collect())
     }
 
-    /// Search concepts by text (simplified version)
-    pub async fn search_concepts_by_text(&self, query_text: &str, _similarity_threshold: f64, max_results: i32) -> Result<Vec<serde_json::Value>> {
-        // Simple text search for now
-        let concepts = sqlx::query!(
+    /// Concepts nearest to an embedding of `embedding_model`, as (id, cosine similarity), closest first
+    pub async fn nearest_concepts(&self, embedding_model: &str, embedding: &[f32], limit: i64) -> Result<Vec<(Uuid, f64)>> {
+        if let Some(index) = self.vector_index.read().await.as_ref() {
+            return Ok(index.nearest(embedding_model, embedding, limit.max(0) as usize));
+        }
+
+        let rows = sqlx::query!(
             r#"
-            SELECT id, name, description, confidence_score
+            SELECT id, 1 - (embedding <=> $2) AS "similarity!"
             FROM concepts
-            WHERE LOWER(name) LIKE LOWER($1) OR LOWER(description) LIKE LOWER($1)
-            ORDER BY confidence_score DESC
-            LIMIT $2
+            WHERE embedding IS NOT NULL AND embedding_model = $1
+            ORDER BY embedding <=> $2
+            LIMIT $3
             "#,
-            format!("%{}%", query_text),
-            max_results as i64
+            embedding_model,
+            Vector::from(embedding.to_vec()) as _,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to find nearest concepts")?;
+
+        Ok(rows.into_iter().map(|row| (row.id, row.similarity)).collect())
+    }
+
+    /// Search concepts by meaning and by words.
+    /// With a query embedding (model name and vector), the concepts nearest to it are blended
+    /// with full-text matches; without one, only full-text and name matches are found.
+    pub async fn search_concepts_by_text(
+        &self,
+        query_text: &str,
+        query_embedding: Option<(&str, &[f32])>,
+        similarity_threshold: f64,
+        max_results: i32,
+    ) -> Result<Vec<ConceptSearchResult>> {
+        let query_text = query_text.trim();
+        if query_text.is_empty() {
+            return Ok(vec![]);
+        }
+        let tsquery = build_tsquery(query_text);
+        let semantic_weight = if query_embedding.is_some() { SEMANTIC_WEIGHT } else { 0.0 };
+        let nearest = match query_embedding {
+            Some((model, embedding)) => self.nearest_concepts(model, embedding, SEMANTIC_CANDIDATES).await?,
+            None => vec![],
+        };
+        let (semantic_ids, semantic_scores): (Vec<Uuid>, Vec<f64>) = nearest.into_iter().unzip();
+
+        let rows = sqlx::query!(
+            r#"
+            WITH semantic AS (
+                SELECT id, similarity
+                FROM UNNEST($2::UUID[], $4::FLOAT8[]) AS s(id, similarity)
+            ),
+            lexical AS (
+                SELECT c.id, ts_rank_cd(to_tsvector('english', c.name || ' ' || c.description), q, 32) AS rank
+                FROM concepts c
+                CROSS JOIN to_tsquery('english', $1) q
+                WHERE to_tsvector('english', c.name || ' ' || c.description) @@ q
+            )
+            SELECT c.id, c.name, c.description, c.tags, c.confidence_score, c.source_chat_count,
+                   c.created_at, c.updated_at,
+                   s.similarity AS "semantic_score?",
+                   l.rank::FLOAT8 AS "lexical_score?",
+                   POSITION(LOWER($5) IN LOWER(c.name)) > 0 AS "name_match!"
+            FROM concepts c
+            LEFT JOIN semantic s ON s.id = c.id AND s.similarity >= $3
+            LEFT JOIN lexical l ON l.id = c.id
+            WHERE s.id IS NOT NULL
+               OR l.id IS NOT NULL
+               OR POSITION(LOWER($5) IN LOWER(c.name)) > 0
+            "#,
+            tsquery,
+            &semantic_ids,
+            similarity_threshold,
+            &semantic_scores,
+            query_text
         )
         .fetch_all(&self.pool)
         .await
         .context("Failed to search concepts by text")?;
-        
-        let results: Vec<serde_json::Value> = concepts
+
+        let mut results: Vec<ConceptSearchResult> = rows
             .into_iter()
-            .map(|row| serde_json::json!({
-                "id": row.id,
-                "name": row.name,
-                "description": row.description,
-                "confidenceScore": row.confidence_score,
-                "similarityScore": 0.8 // Placeholder
-            }))
+            .map(|row| {
+                let mut match_reasons = Vec::new();
+                if row.semantic_score.is_some() {
+                    match_reasons.push(ConceptMatchReason::Semantic);
+                }
+                if row.lexical_score.is_some() {
+                    match_reasons.push(ConceptMatchReason::FullText);
+                }
+                if row.name_match {
+                    match_reasons.push(ConceptMatchReason::Name);
+                }
+
+                ConceptSearchResult {
+                    id: row.id,
+                    name: row.name,
+                    description: row.description,
+                    tags: row.tags
+                        .and_then(|t| serde_json::from_value(t).ok())
+                        .unwrap_or_default(),
+                    confidence_score: row.confidence_score,
+                    source_chat_count: row.source_chat_count,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                    score: hybrid_score(row.semantic_score, row.lexical_score, row.name_match, semantic_weight),
+                    semantic_score: row.semantic_score,
+                    lexical_score: row.lexical_score,
+                    match_reasons,
+                }
+            })
             .collect();
-        
+
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results.truncate(max_results.max(0) as usize);
+
         Ok(results)
     }
 
@@ -257,46 +508,137 @@ impl Database {
         Ok(chats)
     }
 
-    /// Fetches all concepts from the database with their embeddings, for the purpose of similarity matching.
-    pub async fn get_all_concepts_for_matching(&self) -> Result<Vec<ConceptForMatching>> {
+    /// Fetches the concepts that new concepts can be matched against, with their embeddings:
+    /// the concept most similar to each of `embeddings`, and the concepts in `include`.
+    /// Only embeddings made by `embedding_model` are considered, since vectors of different
+    /// models can't be compared. The concepts are streamed a page at a time, so the whole
+    /// corpus is never loaded at once.
+    pub async fn get_concepts_for_matching(
+        &self,
+        embedding_model: &str,
+        embeddings: &[Vec<f32>],
+        include: &HashSet<Uuid>,
+    ) -> Result<Vec<ConceptForMatching>> {
+        if let Some(index) = self.vector_index.read().await.as_ref() {
+            return Ok(index.candidates_for_matching(embedding_model, embeddings, include));
+        }
+
+        let mut candidates = MatchingCandidates::new(embeddings, include);
+        let mut pages = std::pin::pin!(self.stream_concepts_for_matching(embedding_model, MATCHING_PAGE_SIZE));
+        while let Some(page) = pages.try_next().await? {
+            candidates.add_page(page);
+        }
+        Ok(candidates.into_concepts())
+    }
+
+    /// One page of concepts for matching, in id order, starting after the concept `after`
+    pub async fn get_concepts_for_matching_page(
+        &self,
+        embedding_model: &str,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<ConceptForMatching>> {
         let rows = sqlx::query!(
             r#"
-            SELECT id, name, embedding::TEXT AS embedding_text
+            SELECT id, name, embedding AS "embedding!: Vector"
             FROM concepts
-            WHERE embedding IS NOT NULL
-            "#
+            WHERE embedding IS NOT NULL AND embedding_model = $1
+              AND ($2::UUID IS NULL OR id > $2)
+            ORDER BY id
+            LIMIT $3
+            "#,
+            embedding_model,
+            after,
+            limit
         )
         .fetch_all(&self.pool)
         .await
         .context("Failed to fetch concepts for matching")?;
 
-        let concepts: Vec<ConceptForMatching> = rows
+        Ok(rows
             .into_iter()
-            .filter_map(|row| {
-                // The embedding comes back from pgvector as a string like "[1.2,3.4,5.6]"
-                // We need to parse it into a Vec<f32>.
-                let parsed_embedding = row.embedding_text.as_ref().and_then(|text| {
-                    let trimmed = text.trim_matches(|c| c == '[' || c == ']');
-                    trimmed
-                        .split(',')
-                        .map(|s| s.parse::<f32>().ok())
-                        .collect::<Option<Vec<f32>>>()
-                });
-
-                if let Some(embedding) = parsed_embedding {
-                    Some(ConceptForMatching {
-                        id: row.id,
-                        name: row.name,
-                        embedding,
-                    })
-                } else {
-                    // Log a warning or handle the error for malformed embeddings
-                    eprintln!("Warning: Could not parse embedding for concept ID: {}", row.id);
-                    None
-                }
+            .map(|row| ConceptForMatching {
+                id: row.id,
+                name: row.name,
+                embedding: row.embedding.to_vec(),
             })
-            .collect();
+            .collect())
+    }
 
-        Ok(concepts)
+    /// Stream the concepts for matching page by page, so only `page_size` embeddings
+    /// are held at once. Each page is a separate query; no connection is kept in between.
+    pub fn stream_concepts_for_matching<'a>(
+        &'a self,
+        embedding_model: &'a str,
+        page_size: i64,
+    ) -> impl Stream<Item = Result<Vec<ConceptForMatching>>> + 'a {
+        stream::try_unfold(Some(None), move |cursor| async move {
+            let Some(after) = cursor else {
+                return Ok(None);
+            };
+            let page = self.get_concepts_for_matching_page(embedding_model, after, page_size).await?;
+            if page.is_empty() {
+                return Ok(None);
+            }
+            let next = if (page.len() as i64) < page_size {
+                None
+            } else {
+                page.last().map(|concept| Some(concept.id))
+            };
+            Ok(Some((page, next)))
+        })
+    }
+
+    /// Dimension declared on `concepts.embedding`, or `None` if the column accepts any length
+    pub async fn concept_embedding_column_dimension(&self) -> Result<Option<usize>> {
+        let typmod = sqlx::query_scalar!(
+            r#"
+            SELECT atttypmod AS "typmod!"
+            FROM pg_attribute
+            WHERE attrelid = 'concepts'::regclass AND attname = 'embedding'
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to read the concept embedding column type")?;
+
+        // pgvector stores the dimension as the type modifier, -1 when unconstrained
+        Ok(usize::try_from(typmod).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hybrid_score_blends_semantic_and_lexical() {
+        let both = hybrid_score(Some(0.8), Some(0.5), false, SEMANTIC_WEIGHT);
+        let semantic_only = hybrid_score(Some(0.8), None, false, SEMANTIC_WEIGHT);
+        assert!((both - 0.71).abs() < 1e-9);
+        assert!(both > semantic_only);
+
+        // Without a query embedding the full-text rank is the whole score
+        assert!((hybrid_score(None, Some(0.5), false, 0.0) - 0.5).abs() < 1e-9);
+        assert_eq!(hybrid_score(Some(1.0), Some(1.0), true, SEMANTIC_WEIGHT), 1.0);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_matching_candidates_keep_best_and_included() {
+        let concept = |embedding: Vec<f32>| ConceptForMatching { id: Uuid::new_v4(), name: String::new(), embedding };
+        let (close, closer, linked, other) =
+            (concept(vec![0.9, 0.4]), concept(vec![1.0, 0.1]), concept(vec![-1.0, 0.0]), concept(vec![0.0, 1.0]));
+        let ids = (close.id, closer.id, linked.id, other.id);
+        let embeddings = vec![vec![1.0, 0.0]];
+        let include = HashSet::from([linked.id]);
+
+        let mut candidates = MatchingCandidates::new(&embeddings, &include);
+        candidates.add_page(vec![close, linked]);
+        candidates.add_page(vec![other, closer]);
+        let kept: HashSet<Uuid> = candidates.into_concepts().iter().map(|c| c.id).collect();
+
+        // The first page's best match is dropped once the second page beats it
+        assert_eq!(kept, HashSet::from([ids.1, ids.2]));
+        assert!(!kept.contains(&ids.0) && !kept.contains(&ids.3));
+    }
+}
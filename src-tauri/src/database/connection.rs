@@ -2,10 +2,17 @@
 use anyhow::{Context, Result};
 use sqlx::{PgPool, Row};
 use crate::database::types::DatabaseStats;
+use crate::database::vector_index::ConceptVectorIndex;
 use std::env;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 
+#[derive(Clone)]
 pub struct Database {
     pub pool: PgPool,
+    /// Similarity index used instead of pgvector when the extension is missing;
+    /// `None` while pgvector handles similarity
+    pub vector_index: Arc<RwLock<Option<ConceptVectorIndex>>>,
 }
 
 impl Database {
@@ -15,7 +22,7 @@ impl Database {
             .await
             .context("Failed to connect to PostgreSQL database")?;
 
-        Ok(Database { pool })
+        Ok(Database { pool, vector_index: Arc::default() })
     }
 
     /// Create database connection with default local settings
@@ -33,7 +40,7 @@ impl Database {
             .await
             .context(format!("Failed to connect to database at {}", database_url))?;
 
-        Ok(Database { pool })
+        Ok(Database { pool, vector_index: Arc::default() })
     }
 
     /// Test database connection
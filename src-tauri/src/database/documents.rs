@@ -4,8 +4,21 @@ use serde_json::Value;
 use sqlx::postgres::PgRow;
 use sqlx::types::BigDecimal;
 use uuid::Uuid;
+use crate::database::search_index::{SearchEntityType, SearchIndexEntry};
 use crate::database::{Database, Document};
 
+/// Everything needed to store a newly imported document
+pub struct NewDocument<'a> {
+    pub title: &'a str,
+    pub author: Option<&'a str>,
+    pub file_path: &'a str,
+    pub file_name: &'a str,
+    pub file_size: i64,
+    pub total_pages: i32,
+    pub metadata: Value,
+    pub content_hash: Option<&'a str>,
+}
+
 impl Database {
     /// Get all documents
     pub async fn get_documents(&self) -> Result<Vec<Document>> {
@@ -40,23 +53,83 @@ impl Database {
         Ok(document)
     }
 
-    /// Create a new document record
-    pub async fn create_document(
+    /// Get document by content fingerprint.
+    /// If several records share the fingerprint, the most recently accessed one wins.
+    pub async fn get_document_by_content_hash(&self, content_hash: &str) -> Result<Option<Document>> {
+        let document = sqlx::query_as!(
+            Document,
+            "SELECT * FROM documents WHERE content_hash = $1 ORDER BY last_accessed DESC LIMIT 1",
+            content_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch document by content hash")?;
+
+        Ok(document)
+    }
+
+    /// Store the content fingerprint of a document imported before fingerprints existed
+    pub async fn set_document_content_hash(&self, id: Uuid, content_hash: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE documents SET content_hash = $2 WHERE id = $1",
+            id,
+            content_hash
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to update document content hash")?;
+
+        Ok(())
+    }
+
+    /// Point an existing document at the new location of its file,
+    /// keeping its chats, highlights and reading state
+    pub async fn relink_document(
         &self,
-        title: &str,
-        author: Option<&str>,
+        id: Uuid,
         file_path: &str,
         file_name: &str,
-        file_size: i64,
-        total_pages: i32,
-        metadata: Value,
-    ) -> Result<Uuid> {
+        content_hash: Option<&str>,
+    ) -> Result<Document> {
+        let document = sqlx::query_as!(
+            Document,
+            r#"
+            UPDATE documents
+            SET file_path = $2, file_name = $3, content_hash = COALESCE($4, content_hash),
+                last_accessed = NOW(), updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+            id,
+            file_path,
+            file_name,
+            content_hash
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to relink document")?;
+
+        Ok(document)
+    }
+
+    /// Create a new document record
+    pub async fn create_document(&self, document: NewDocument<'_>) -> Result<Uuid> {
         let id = Uuid::new_v4();
+        let NewDocument {
+            title,
+            author,
+            file_path,
+            file_name,
+            file_size,
+            total_pages,
+            metadata,
+            content_hash,
+        } = document;
 
         sqlx::query!(
             r#"
-            INSERT INTO documents (id, title, author, file_path, file_name, file_size, total_pages, metadata)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            INSERT INTO documents (id, title, author, file_path, file_name, file_size, total_pages, metadata, content_hash)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
             "#,
             id,
             title,
@@ -65,12 +138,40 @@ impl Database {
             file_name,
             file_size,
             total_pages,
-            metadata
+            metadata,
+            content_hash
         )
         .execute(&self.pool)
         .await
         .context("Failed to create document")?;
 
+        let keywords: Vec<String> = metadata
+            .get("keywords")
+            .and_then(|k| serde_json::from_value(k.clone()).ok())
+            .unwrap_or_default();
+        let subject = metadata.get("subject").and_then(|s| s.as_str());
+        let searchable_text = [Some(title), author, subject]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // The document is saved either way; a missing index entry only hides it from search
+        let indexed = self.index_entity(SearchIndexEntry {
+            entity_type: SearchEntityType::Document,
+            entity_id: id,
+            title: Some(title),
+            searchable_text: &searchable_text,
+            keywords: &keywords,
+            document_id: Some(id),
+            chat_session_id: None,
+            page_number: None,
+        })
+        .await;
+        if let Err(e) = indexed {
+            tracing::warn!("Failed to index document {}: {}", id, e);
+        }
+
         Ok(id)
     }
 
@@ -0,0 +1,138 @@
+// Embedding cache database operations
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use crate::database::{Database, EmbeddingCacheStats};
+
+impl Database {
+    /// Cached vectors of a model for the given text hashes, keyed by hash.
+    /// Every vector found counts as a hit on its cache entry.
+    pub async fn get_cached_embeddings(
+        &self,
+        model: &str,
+        dimension: i32,
+        text_hashes: &[String],
+    ) -> Result<HashMap<String, Vec<f32>>> {
+        let rows = sqlx::query!(
+            r#"
+            UPDATE embedding_cache
+            SET hit_count = hit_count + 1, last_used_at = NOW()
+            WHERE model = $1 AND dimension = $2 AND text_hash = ANY($3)
+            RETURNING text_hash, embedding
+            "#,
+            model,
+            dimension,
+            text_hashes
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to read embedding cache")?;
+
+        Ok(rows.into_iter().map(|row| (row.text_hash, row.embedding)).collect())
+    }
+
+    /// Store freshly computed vectors; `entries` pairs a text hash with its vector
+    pub async fn store_cached_embeddings(&self, model: &str, dimension: i32, entries: &[(String, Vec<f32>)]) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        for (text_hash, embedding) in entries {
+            sqlx::query!(
+                r#"
+                INSERT INTO embedding_cache (model, dimension, text_hash, embedding)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (model, dimension, text_hash) DO NOTHING
+                "#,
+                model,
+                dimension,
+                text_hash,
+                embedding
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to write embedding cache")?;
+        }
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(())
+    }
+
+    /// Add the outcome of a batch of cache lookups to the per-model totals
+    pub async fn record_embedding_cache_lookups(&self, model: &str, hits: i64, misses: i64) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO embedding_cache_stats (model, hits, misses)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (model) DO UPDATE
+            SET hits = embedding_cache_stats.hits + EXCLUDED.hits,
+                misses = embedding_cache_stats.misses + EXCLUDED.misses,
+                updated_at = NOW()
+            "#,
+            model,
+            hits,
+            misses
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to record embedding cache lookups")?;
+
+        Ok(())
+    }
+
+    /// Entries, hits and misses of the cache per model
+    pub async fn get_embedding_cache_stats(&self) -> Result<Vec<EmbeddingCacheStats>> {
+        let stats = sqlx::query_as!(
+            EmbeddingCacheStats,
+            r#"
+            SELECT COALESCE(c.model, s.model) AS "model!",
+                   COALESCE(c.entries, 0) AS "entries!",
+                   COALESCE(s.hits, 0) AS "hits!",
+                   COALESCE(s.misses, 0) AS "misses!",
+                   c.last_used_at
+            FROM (
+                SELECT model, COUNT(*) AS entries, MAX(last_used_at) AS last_used_at
+                FROM embedding_cache
+                GROUP BY model
+            ) c
+            FULL OUTER JOIN embedding_cache_stats s ON s.model = c.model
+            ORDER BY 1
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch embedding cache stats")?;
+
+        Ok(stats)
+    }
+
+    /// Delete cached vectors and totals of every model not in `keep_models`,
+    /// returning how many vectors were removed
+    pub async fn prune_embedding_cache(&self, keep_models: &[String]) -> Result<u64> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let deleted = sqlx::query!("DELETE FROM embedding_cache WHERE model <> ALL($1)", keep_models)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to prune embedding cache")?;
+
+        sqlx::query!("DELETE FROM embedding_cache_stats WHERE model <> ALL($1)", keep_models)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to prune embedding cache stats")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(deleted.rows_affected())
+    }
+
+    /// Models whose vectors are still stored on concepts
+    pub async fn get_concept_embedding_models(&self) -> Result<Vec<String>> {
+        let models = sqlx::query_scalar!(
+            r#"SELECT DISTINCT embedding_model AS "embedding_model!" FROM concepts WHERE embedding_model IS NOT NULL"#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch concept embedding models")?;
+
+        Ok(models)
+    }
+}
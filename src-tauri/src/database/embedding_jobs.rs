@@ -0,0 +1,350 @@
+// Re-embedding job database operations
+use anyhow::{Context, Result};
+use uuid::Uuid;
+use crate::database::{Database, EmbeddingJob};
+
+/// pgvector cannot build an HNSW index over vectors with more dimensions than this
+const HNSW_MAX_DIMENSION: usize = 2000;
+
+impl Database {
+    /// Start a new job for a model; fails while another job is running
+    pub async fn create_embedding_job(&self, model: &str, dimension: i32) -> Result<EmbeddingJob> {
+        let job = sqlx::query_as!(
+            EmbeddingJob,
+            r#"
+            INSERT INTO embedding_jobs (model, dimension)
+            VALUES ($1, $2)
+            RETURNING id, model, dimension, status, total_items, processed_items, error_message,
+                      started_at, updated_at, completed_at
+            "#,
+            model,
+            dimension
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to create embedding job")?;
+
+        Ok(job)
+    }
+
+    pub async fn get_embedding_job(&self, job_id: Uuid) -> Result<Option<EmbeddingJob>> {
+        let job = sqlx::query_as!(
+            EmbeddingJob,
+            r#"
+            SELECT id, model, dimension, status, total_items, processed_items, error_message,
+                   started_at, updated_at, completed_at
+            FROM embedding_jobs
+            WHERE id = $1
+            "#,
+            job_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to get embedding job")?;
+
+        Ok(job)
+    }
+
+    /// The most recently started job, whatever its status
+    pub async fn get_latest_embedding_job(&self) -> Result<Option<EmbeddingJob>> {
+        let job = sqlx::query_as!(
+            EmbeddingJob,
+            r#"
+            SELECT id, model, dimension, status, total_items, processed_items, error_message,
+                   started_at, updated_at, completed_at
+            FROM embedding_jobs
+            ORDER BY started_at DESC
+            LIMIT 1
+            "#
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to get latest embedding job")?;
+
+        Ok(job)
+    }
+
+    /// Put a failed job back into the running state so it continues where it stopped
+    pub async fn restart_embedding_job(&self, job_id: Uuid) -> Result<EmbeddingJob> {
+        let job = sqlx::query_as!(
+            EmbeddingJob,
+            r#"
+            UPDATE embedding_jobs
+            SET status = 'running', error_message = NULL, updated_at = NOW(), completed_at = NULL
+            WHERE id = $1 AND status = 'failed'
+            RETURNING id, model, dimension, status, total_items, processed_items, error_message,
+                      started_at, updated_at, completed_at
+            "#,
+            job_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to restart embedding job")?;
+
+        Ok(job)
+    }
+
+    /// Mark a running job as failed, keeping its staged vectors for a later resume
+    pub async fn fail_embedding_job(&self, job_id: Uuid, error_message: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE embedding_jobs
+            SET status = 'failed', error_message = $2, updated_at = NOW(), completed_at = NOW()
+            WHERE id = $1 AND status = 'running'
+            "#,
+            job_id,
+            error_message
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to mark embedding job as failed")?;
+
+        Ok(())
+    }
+
+    /// Cancel a running or failed job and discard its staged vectors.
+    /// Returns false when the job had already finished.
+    pub async fn cancel_embedding_job(&self, job_id: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE embedding_jobs
+            SET status = 'cancelled', updated_at = NOW(), completed_at = NOW()
+            WHERE id = $1 AND status IN ('running', 'failed')
+            "#,
+            job_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to cancel embedding job")?;
+
+        sqlx::query!("DELETE FROM concept_embedding_staging WHERE job_id = $1", job_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to discard staged embeddings")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The next concepts a job still has to embed as (id, name, description), in id order.
+    /// A concept edited after its vector was staged has to be embedded again.
+    pub async fn get_concepts_to_reembed(&self, job_id: Uuid, limit: i64) -> Result<Vec<(Uuid, String, String)>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT c.id, c.name, c.description
+            FROM concepts c
+            WHERE NOT EXISTS (
+                SELECT 1 FROM concept_embedding_staging s
+                WHERE s.job_id = $1 AND s.concept_id = c.id AND s.created_at >= c.updated_at
+            )
+            ORDER BY c.id
+            LIMIT $2
+            "#,
+            job_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get concepts to re-embed")?;
+
+        Ok(rows.into_iter().map(|row| (row.id, row.name, row.description)).collect())
+    }
+
+    /// Stage new vectors for a running job; concepts deleted in the meantime are skipped
+    pub async fn stage_concept_embeddings(&self, job_id: Uuid, entries: &[(Uuid, Vec<f32>)]) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        for (concept_id, embedding) in entries {
+            sqlx::query!(
+                r#"
+                INSERT INTO concept_embedding_staging (job_id, concept_id, embedding)
+                SELECT $1, id, $3 FROM concepts
+                WHERE id = $2
+                  AND EXISTS (SELECT 1 FROM embedding_jobs WHERE id = $1 AND status = 'running')
+                ON CONFLICT (job_id, concept_id) DO UPDATE SET embedding = EXCLUDED.embedding, created_at = NOW()
+                "#,
+                job_id,
+                concept_id,
+                embedding
+            )
+            .execute(&mut *tx)
+            .await
+            .context("Failed to stage concept embedding")?;
+        }
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(())
+    }
+
+    /// Recount a job's staged and total concepts
+    pub async fn refresh_embedding_job_progress(&self, job_id: Uuid) -> Result<EmbeddingJob> {
+        let job = sqlx::query_as!(
+            EmbeddingJob,
+            r#"
+            UPDATE embedding_jobs
+            SET processed_items = (
+                    SELECT COUNT(*)::INTEGER FROM concept_embedding_staging WHERE job_id = $1
+                ),
+                total_items = (SELECT COUNT(*)::INTEGER FROM concepts),
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, model, dimension, status, total_items, processed_items, error_message,
+                      started_at, updated_at, completed_at
+            "#,
+            job_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to update embedding job progress")?;
+
+        Ok(job)
+    }
+
+    /// Replace every concept embedding with the vectors staged by a job in one
+    /// transaction, changing the column dimension first when the model needs it.
+    /// Returns false without changing anything if the job is no longer running or
+    /// concepts appeared or changed that it has not embedded yet.
+    pub async fn swap_staged_embeddings(&self, job_id: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        // Readers keep working; concept writes wait until the swap commits
+        sqlx::query("LOCK TABLE concepts IN SHARE ROW EXCLUSIVE MODE")
+            .execute(&mut *tx)
+            .await
+            .context("Failed to lock concepts")?;
+
+        // Locking the job row keeps a concurrent cancel from interleaving with the swap
+        let Some(job) = sqlx::query!(
+            "SELECT model, dimension FROM embedding_jobs WHERE id = $1 AND status = 'running' FOR UPDATE",
+            job_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to get embedding job")?
+        else {
+            return Ok(false);
+        };
+
+        let pending = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM concepts c
+            WHERE NOT EXISTS (
+                SELECT 1 FROM concept_embedding_staging s
+                WHERE s.job_id = $1 AND s.concept_id = c.id AND s.created_at >= c.updated_at
+            )
+            "#,
+            job_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to count concepts without staged embeddings")?;
+
+        if pending > 0 {
+            return Ok(false);
+        }
+
+        let dimension = usize::try_from(job.dimension).context("Invalid embedding job dimension")?;
+
+        let typmod = sqlx::query_scalar!(
+            r#"
+            SELECT atttypmod AS "typmod!"
+            FROM pg_attribute
+            WHERE attrelid = 'concepts'::regclass AND attname = 'embedding'
+            "#
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to read the concept embedding column type")?;
+        // Without pgvector the column holds plain arrays of any length
+        let has_vector_extension = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'vector') AS "installed!""#
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to check for the vector extension")?;
+        let resize_column = has_vector_extension && usize::try_from(typmod).ok() != Some(dimension);
+
+        if resize_column {
+            // Old vectors cannot be cast to the new dimension; they are all replaced below
+            for statement in [
+                "DROP INDEX IF EXISTS idx_concepts_embedding_hnsw".to_string(),
+                format!("ALTER TABLE concepts ALTER COLUMN embedding TYPE VECTOR({}) USING NULL", dimension),
+            ] {
+                sqlx::query(&statement)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to change the concept embedding dimension")?;
+            }
+        }
+
+        // Re-embedding is not an edit, so updated_at keeps its value
+        sqlx::query("ALTER TABLE concepts DISABLE TRIGGER update_concepts_updated_at")
+            .execute(&mut *tx)
+            .await
+            .context("Failed to disable the concepts updated_at trigger")?;
+
+        sqlx::query!(
+            r#"
+            UPDATE concepts c
+            SET embedding = s.embedding, embedding_model = $2, embedding_dimension = $3
+            FROM concept_embedding_staging s
+            WHERE s.job_id = $1 AND s.concept_id = c.id
+            "#,
+            job_id,
+            job.model,
+            job.dimension
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to swap in staged embeddings")?;
+
+        sqlx::query("ALTER TABLE concepts ENABLE TRIGGER update_concepts_updated_at")
+            .execute(&mut *tx)
+            .await
+            .context("Failed to enable the concepts updated_at trigger")?;
+
+        if resize_column {
+            if dimension <= HNSW_MAX_DIMENSION {
+                sqlx::query("CREATE INDEX idx_concepts_embedding_hnsw ON concepts USING hnsw (embedding vector_cosine_ops)")
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to rebuild the concept embedding index")?;
+            } else {
+                tracing::warn!(
+                    "Concept embeddings have {} dimensions; similarity search runs without an HNSW index",
+                    dimension
+                );
+            }
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE embedding_jobs
+            SET status = 'complete',
+                total_items = (SELECT COUNT(*)::INTEGER FROM concepts),
+                processed_items = (SELECT COUNT(*)::INTEGER FROM concepts),
+                updated_at = NOW(),
+                completed_at = NOW()
+            WHERE id = $1
+            "#,
+            job_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to complete embedding job")?;
+
+        sqlx::query!("DELETE FROM concept_embedding_staging WHERE job_id = $1", job_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to clear staged embeddings")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,132 @@
+// Highlight (text selection) database operations
+use anyhow::{Context, Result};
+use serde_json::Value;
+use uuid::Uuid;
+use crate::database::{Database, TextSelection};
+
+/// Color used when a highlight is created without one
+pub const DEFAULT_HIGHLIGHT_COLOR: &str = "#fde047";
+
+/// Everything needed to store a new highlight
+pub struct NewHighlight<'a> {
+    pub document_id: Uuid,
+    pub page_number: i32,
+    pub selected_text: &'a str,
+    pub start_coordinate: Value,
+    pub end_coordinate: Value,
+    pub bounding_boxes: Value,
+    pub color: Option<&'a str>,
+    pub note: Option<&'a str>,
+}
+
+impl Database {
+    /// Save a highlight
+    pub async fn create_highlight(&self, highlight: NewHighlight<'_>) -> Result<TextSelection> {
+        let created = sqlx::query_as!(
+            TextSelection,
+            r#"
+            INSERT INTO text_selections (
+                document_id, page_number, selected_text,
+                start_coordinate, end_coordinate, bounding_boxes, color, note
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, NULLIF(TRIM($8), ''))
+            RETURNING id, document_id, page_number, selected_text, start_coordinate,
+                      end_coordinate, bounding_boxes, color, note, created_at, updated_at
+            "#,
+            highlight.document_id,
+            highlight.page_number,
+            highlight.selected_text,
+            highlight.start_coordinate,
+            highlight.end_coordinate,
+            highlight.bounding_boxes,
+            highlight.color.unwrap_or(DEFAULT_HIGHLIGHT_COLOR),
+            highlight.note
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to create highlight")?;
+
+        Ok(created)
+    }
+
+    /// Get every highlight on one page of a document, in creation order
+    pub async fn get_page_highlights(&self, document_id: Uuid, page_number: i32) -> Result<Vec<TextSelection>> {
+        let highlights = sqlx::query_as!(
+            TextSelection,
+            r#"
+            SELECT id, document_id, page_number, selected_text, start_coordinate,
+                   end_coordinate, bounding_boxes, color, note, created_at, updated_at
+            FROM text_selections
+            WHERE document_id = $1 AND page_number = $2
+            ORDER BY created_at
+            "#,
+            document_id,
+            page_number
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch page highlights")?;
+
+        Ok(highlights)
+    }
+
+    /// Get every highlight of a document, ordered by page
+    pub async fn get_document_highlights(&self, document_id: Uuid) -> Result<Vec<TextSelection>> {
+        let highlights = sqlx::query_as!(
+            TextSelection,
+            r#"
+            SELECT id, document_id, page_number, selected_text, start_coordinate,
+                   end_coordinate, bounding_boxes, color, note, created_at, updated_at
+            FROM text_selections
+            WHERE document_id = $1
+            ORDER BY page_number, created_at
+            "#,
+            document_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch document highlights")?;
+
+        Ok(highlights)
+    }
+
+    /// Change the color and/or note of a highlight.
+    /// `None` leaves a field unchanged; an empty note removes the note.
+    pub async fn update_highlight(
+        &self,
+        id: Uuid,
+        color: Option<&str>,
+        note: Option<&str>,
+    ) -> Result<Option<TextSelection>> {
+        let updated = sqlx::query_as!(
+            TextSelection,
+            r#"
+            UPDATE text_selections
+            SET color = COALESCE($2, color),
+                note = CASE WHEN $3::TEXT IS NULL THEN note ELSE NULLIF(TRIM($3), '') END,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, document_id, page_number, selected_text, start_coordinate,
+                      end_coordinate, bounding_boxes, color, note, created_at, updated_at
+            "#,
+            id,
+            color,
+            note
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to update highlight")?;
+
+        Ok(updated)
+    }
+
+    /// Delete a highlight, returning whether it existed
+    pub async fn delete_highlight(&self, id: Uuid) -> Result<bool> {
+        let deleted = sqlx::query!("DELETE FROM text_selections WHERE id = $1", id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete highlight")?;
+
+        Ok(deleted.rows_affected() > 0)
+    }
+}
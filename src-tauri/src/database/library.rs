@@ -0,0 +1,80 @@
+// Library root database operations
+use anyhow::{Context, Result};
+use uuid::Uuid;
+use crate::database::{Database, LibraryRoot};
+
+impl Database {
+    /// Add a library root, or return the existing one for the same path
+    pub async fn add_library_root(&self, path: &str) -> Result<LibraryRoot> {
+        let root = sqlx::query_as!(
+            LibraryRoot,
+            r#"
+            INSERT INTO library_roots (path)
+            VALUES ($1)
+            ON CONFLICT (path) DO UPDATE SET path = EXCLUDED.path
+            RETURNING id, path, created_at, last_scanned_at
+            "#,
+            path
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to add library root")?;
+
+        Ok(root)
+    }
+
+    /// Get all configured library roots
+    pub async fn get_library_roots(&self) -> Result<Vec<LibraryRoot>> {
+        let roots = sqlx::query_as!(
+            LibraryRoot,
+            "SELECT id, path, created_at, last_scanned_at FROM library_roots ORDER BY path"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch library roots")?;
+
+        Ok(roots)
+    }
+
+    /// Remove a library root, returning its path if it existed.
+    /// Documents imported from the root are kept.
+    pub async fn remove_library_root(&self, id: Uuid) -> Result<Option<String>> {
+        let path = sqlx::query_scalar!("DELETE FROM library_roots WHERE id = $1 RETURNING path", id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to remove library root")?;
+
+        Ok(path)
+    }
+
+    /// Record that a full scan of a library root finished
+    pub async fn mark_library_root_scanned(&self, id: Uuid) -> Result<()> {
+        sqlx::query!("UPDATE library_roots SET last_scanned_at = NOW() WHERE id = $1", id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to update library root scan time")?;
+
+        Ok(())
+    }
+
+    /// Delete a document whose file was removed from the library, unless the user has
+    /// attached highlights, selections or notes to it. Those documents are kept so
+    /// they can be relinked if the file comes back. Returns whether it was deleted.
+    pub async fn delete_document_if_unused(&self, id: Uuid) -> Result<bool> {
+        let deleted = sqlx::query!(
+            r#"
+            DELETE FROM documents d
+            WHERE d.id = $1
+              AND NOT EXISTS (SELECT 1 FROM highlighted_contexts hc WHERE hc.document_id = d.id)
+              AND NOT EXISTS (SELECT 1 FROM text_selections ts WHERE ts.document_id = d.id)
+              AND NOT EXISTS (SELECT 1 FROM user_notes un WHERE un.document_id = d.id)
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete document")?;
+
+        Ok(deleted.rows_affected() > 0)
+    }
+}
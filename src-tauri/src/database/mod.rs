@@ -1,11 +1,26 @@
 // Database module - organizes all database operations
 pub mod connection;
 pub mod documents;
+pub mod pages;
+pub mod outline;
+pub mod library;
 pub mod chat;
+pub mod highlights;
 pub mod concepts;
+pub mod concept_graph;
+pub mod concept_edits;
+pub mod concept_reconcile;
+pub mod concept_proposals;
+pub mod analysis_jobs;
+pub mod embedding_cache;
+pub mod embedding_jobs;
 pub mod navigation;
+pub mod notes;
 pub mod preferences;
+pub mod search;
+pub mod search_index;
 pub mod types;
+pub mod vector_index;
 
 // Re-export the main Database struct and commonly used types
 pub use connection::Database;
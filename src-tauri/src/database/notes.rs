@@ -0,0 +1,271 @@
+// User notes database operations
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+use crate::database::search::build_tsquery;
+use crate::database::search_index::{SearchEntityType, SearchIndexEntry};
+use crate::database::{Database, UserNote};
+
+/// Values allowed in `user_notes.note_type`
+pub const NOTE_TYPES: [&str; 4] = ["personal-insight", "summary", "question", "highlight"];
+
+/// Everything needed to store a new note.
+/// Without a page number, the page of the linked selection is used.
+pub struct NewNote<'a> {
+    pub document_id: Uuid,
+    pub selection_id: Option<Uuid>,
+    pub page_number: Option<i32>,
+    pub note_text: &'a str,
+    pub note_type: Option<&'a str>,
+    pub position: Option<Value>,
+    pub tags: &'a [String],
+}
+
+/// Changes to a note; `None` leaves a field unchanged
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteUpdate {
+    pub note_text: Option<String>,
+    pub note_type: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub position: Option<Value>,
+}
+
+/// Criteria for listing notes; every field is optional and they combine with AND.
+/// With a `query`, notes are ranked by relevance instead of by date.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteFilter {
+    pub document_id: Option<Uuid>,
+    pub page_number: Option<i32>,
+    pub note_type: Option<String>,
+    pub tag: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub query: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Lowercase, trim and de-duplicate tags, keeping their order
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+fn validate_note_type(note_type: &str) -> Result<()> {
+    if NOTE_TYPES.contains(&note_type) {
+        Ok(())
+    } else {
+        Err(anyhow!("Invalid note type '{}', expected one of {}", note_type, NOTE_TYPES.join(", ")))
+    }
+}
+
+impl Database {
+    /// Create a note on a document page or selection
+    pub async fn create_note(&self, note: NewNote<'_>) -> Result<UserNote> {
+        if let Some(note_type) = note.note_type {
+            validate_note_type(note_type)?;
+        }
+        if note.page_number.is_none() && note.selection_id.is_none() {
+            return Err(anyhow!("A note needs a page number or a selection"));
+        }
+        let tags = normalize_tags(note.tags);
+
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO user_notes (document_id, selection_id, page_number, note_text, note_type, position, tags)
+            SELECT $1, $2,
+                   COALESCE($3, (SELECT page_number FROM text_selections WHERE id = $2 AND document_id = $1)),
+                   TRIM($4), COALESCE($5, 'personal-insight'), $6, $7
+            RETURNING id
+            "#,
+            note.document_id,
+            note.selection_id,
+            note.page_number,
+            note.note_text,
+            note.note_type,
+            note.position,
+            &tags
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to create note")?;
+
+        let created = self.get_note(id).await?.context("Created note not found")?;
+        self.index_note(&created).await;
+
+        Ok(created)
+    }
+
+    /// Get a note by ID
+    pub async fn get_note(&self, id: Uuid) -> Result<Option<UserNote>> {
+        let note = sqlx::query_as!(
+            UserNote,
+            r#"
+            SELECT n.id, n.document_id, d.title AS document_title, n.selection_id, n.page_number,
+                   n.note_text, n.note_type, n.position, COALESCE(n.tags, '{}') AS "tags!",
+                   n.is_private, n.created_at, n.updated_at
+            FROM user_notes n
+            JOIN documents d ON d.id = n.document_id
+            WHERE n.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch note")?;
+
+        Ok(note)
+    }
+
+    /// Apply changes to a note, returning the updated note if it exists
+    pub async fn update_note(&self, id: Uuid, update: &NoteUpdate) -> Result<Option<UserNote>> {
+        if let Some(note_type) = &update.note_type {
+            validate_note_type(note_type)?;
+        }
+        let tags = update.tags.as_deref().map(normalize_tags);
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE user_notes
+            SET note_text = COALESCE(TRIM($2), note_text),
+                note_type = COALESCE($3, note_type),
+                tags = COALESCE($4, tags),
+                position = COALESCE($5, position)
+            WHERE id = $1
+            "#,
+            id,
+            update.note_text.as_deref(),
+            update.note_type.as_deref(),
+            tags.as_deref(),
+            update.position
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to update note")?;
+
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let note = self.get_note(id).await?;
+        if let Some(note) = &note {
+            self.index_note(note).await;
+        }
+
+        Ok(note)
+    }
+
+    /// Delete a note, returning whether it existed
+    pub async fn delete_note(&self, id: Uuid) -> Result<bool> {
+        let deleted = sqlx::query!("DELETE FROM user_notes WHERE id = $1", id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete note")?;
+
+        self.remove_from_index(SearchEntityType::UserNote, id).await?;
+
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    /// List notes matching a filter.
+    /// Notes are ordered by relevance when searching, otherwise newest first.
+    pub async fn list_notes(&self, filter: &NoteFilter) -> Result<Vec<UserNote>> {
+        let tsquery = match filter.query.as_deref() {
+            Some(query) => match build_tsquery(query) {
+                Some(tsquery) => Some(tsquery),
+                // Nothing searchable in the query, so nothing can match
+                None => return Ok(vec![]),
+            },
+            None => None,
+        };
+        let tag = filter.tag.as_ref().and_then(|t| normalize_tags(std::slice::from_ref(t)).pop());
+
+        let notes = sqlx::query_as!(
+            UserNote,
+            r#"
+            SELECT n.id, n.document_id, d.title AS document_title, n.selection_id, n.page_number,
+                   n.note_text, n.note_type, n.position, COALESCE(n.tags, '{}') AS "tags!",
+                   n.is_private, n.created_at, n.updated_at
+            FROM user_notes n
+            JOIN documents d ON d.id = n.document_id
+            CROSS JOIN to_tsquery('english', $1) q
+            WHERE ($1::TEXT IS NULL OR to_tsvector('english', n.note_text) @@ q)
+              AND ($2::UUID IS NULL OR n.document_id = $2)
+              AND ($3::INTEGER IS NULL OR n.page_number = $3)
+              AND ($4::TEXT IS NULL OR n.note_type = $4)
+              AND ($5::TEXT IS NULL OR n.tags @> ARRAY[$5])
+              AND ($6::TIMESTAMPTZ IS NULL OR n.created_at >= $6)
+              AND ($7::TIMESTAMPTZ IS NULL OR n.created_at < $7)
+            ORDER BY
+                CASE WHEN $1::TEXT IS NULL THEN 0
+                     ELSE ts_rank(to_tsvector('english', n.note_text), q) END DESC,
+                n.created_at DESC
+            LIMIT $8 OFFSET $9
+            "#,
+            tsquery,
+            filter.document_id,
+            filter.page_number,
+            filter.note_type,
+            tag,
+            filter.created_after,
+            filter.created_before,
+            filter.limit.unwrap_or(100),
+            filter.offset.unwrap_or(0)
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list notes")?;
+
+        Ok(notes)
+    }
+
+    /// Keep the unified search index entry of a note current. The note is already
+    /// saved, so a failure is only logged.
+    async fn index_note(&self, note: &UserNote) {
+        let indexed = self.index_entity(SearchIndexEntry {
+            entity_type: SearchEntityType::UserNote,
+            entity_id: note.id,
+            title: Some(&note.document_title),
+            searchable_text: &note.note_text,
+            keywords: &note.tags,
+            document_id: Some(note.document_id),
+            chat_session_id: None,
+            page_number: Some(note.page_number),
+        })
+        .await;
+        if let Err(e) = indexed {
+            tracing::warn!("Failed to index note {}: {}", note.id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = vec![
+            " Optimization ".to_string(),
+            "#optimization".to_string(),
+            "".to_string(),
+            "Calculus".to_string(),
+        ];
+        assert_eq!(normalize_tags(&tags), vec!["optimization", "calculus"]);
+    }
+
+    #[test]
+    fn test_validate_note_type() {
+        assert!(validate_note_type("summary").is_ok());
+        assert!(validate_note_type("todo").is_err());
+    }
+}
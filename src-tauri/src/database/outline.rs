@@ -0,0 +1,190 @@
+// Document outline database operations
+use anyhow::{Context, Result};
+use uuid::Uuid;
+use crate::database::{Database, DocumentOutlineEntry, OutlineNode};
+use crate::pdf_handler::OutlineItem;
+
+/// Separator between the titles of a section path
+const SECTION_SEPARATOR: &str = " > ";
+
+/// Nest a flat outline, given in reading order, into a tree
+pub fn build_outline_tree(entries: &[DocumentOutlineEntry]) -> Vec<OutlineNode> {
+    fn attach(stack: &mut [OutlineNode], roots: &mut Vec<OutlineNode>, node: OutlineNode) {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<OutlineNode> = Vec::new();
+    for entry in entries {
+        // Close every open entry at the same or a deeper level
+        while stack.last().is_some_and(|node| node.level >= entry.level) {
+            if let Some(node) = stack.pop() {
+                attach(&mut stack, &mut roots, node);
+            }
+        }
+        stack.push(OutlineNode {
+            title: entry.title.clone(),
+            level: entry.level,
+            page_number: entry.page_number,
+            children: Vec::new(),
+        });
+    }
+    while let Some(node) = stack.pop() {
+        attach(&mut stack, &mut roots, node);
+    }
+
+    roots
+}
+
+/// Titles from the top-level entry down to the innermost section containing `page_number`
+pub fn section_path(entries: &[DocumentOutlineEntry], page_number: i32) -> Vec<&str> {
+    // The section starting closest before the page wins; on ties the deepest (latest) entry
+    let Some(index) = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.page_number.is_some_and(|p| p <= page_number))
+        .max_by_key(|(i, e)| (e.page_number, *i))
+        .map(|(i, _)| i)
+    else {
+        return vec![];
+    };
+
+    let mut path = vec![entries[index].title.as_str()];
+    let mut level = entries[index].level;
+    for entry in entries[..index].iter().rev() {
+        if entry.level < level {
+            path.push(&entry.title);
+            level = entry.level;
+        }
+    }
+    path.reverse();
+    path
+}
+
+impl Database {
+    /// Replace the stored outline of a document and record that it was extracted
+    pub async fn store_document_outline(&self, document_id: Uuid, items: &[OutlineItem]) -> Result<()> {
+        let positions: Vec<i32> = (1..=items.len() as i32).collect();
+        let levels: Vec<i32> = items.iter().map(|i| i.level as i32).collect();
+        let titles: Vec<String> = items.iter().map(|i| i.title.clone()).collect();
+        let page_numbers: Vec<Option<i32>> = items.iter().map(|i| i.page_number.map(|p| p as i32)).collect();
+
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        sqlx::query!("DELETE FROM document_outline WHERE document_id = $1", document_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to clear existing document outline")?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO document_outline (document_id, position, level, title, page_number)
+            SELECT $1, position, level, title, page_number
+            FROM UNNEST($2::INTEGER[], $3::INTEGER[], $4::TEXT[], $5::INTEGER[])
+                AS o(position, level, title, page_number)
+            "#,
+            document_id,
+            &positions,
+            &levels,
+            &titles,
+            &page_numbers as &[Option<i32>]
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to insert document outline")?;
+
+        sqlx::query!("UPDATE documents SET outline_extracted_at = NOW() WHERE id = $1", document_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to record outline extraction")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(())
+    }
+
+    /// Get the outline of a document as a flat list in reading order
+    pub async fn get_document_outline(&self, document_id: Uuid) -> Result<Vec<DocumentOutlineEntry>> {
+        let entries = sqlx::query_as!(
+            DocumentOutlineEntry,
+            r#"
+            SELECT id, document_id, position, level, title, page_number
+            FROM document_outline
+            WHERE document_id = $1
+            ORDER BY position
+            "#,
+            document_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch document outline")?;
+
+        Ok(entries)
+    }
+
+    /// Outline path of the section a page belongs to, e.g. "Chapter 2 > Gradient Descent"
+    pub async fn get_section_title(&self, document_id: Uuid, page_number: i32) -> Result<Option<String>> {
+        let entries = self.get_document_outline(document_id).await?;
+        let path = section_path(&entries, page_number);
+
+        Ok((!path.is_empty()).then(|| path.join(SECTION_SEPARATOR)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(items: &[(&str, i32, Option<i32>)]) -> Vec<DocumentOutlineEntry> {
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, (title, level, page_number))| DocumentOutlineEntry {
+                id: Uuid::new_v4(),
+                document_id: Uuid::nil(),
+                position: i as i32 + 1,
+                level: *level,
+                title: title.to_string(),
+                page_number: *page_number,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_build_outline_tree() {
+        let entries = outline(&[
+            ("Chapter 1", 1, Some(1)),
+            ("1.1 Basics", 2, Some(2)),
+            ("1.1.1 Notation", 3, Some(3)),
+            ("1.2 Methods", 2, Some(5)),
+            ("Chapter 2", 1, Some(9)),
+        ]);
+
+        let tree = build_outline_tree(&entries);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].children.len(), 2);
+        assert_eq!(tree[0].children[0].children[0].title, "1.1.1 Notation");
+        assert_eq!(tree[0].children[1].title, "1.2 Methods");
+        assert!(tree[1].children.is_empty());
+    }
+
+    #[test]
+    fn test_section_path() {
+        let entries = outline(&[
+            ("Preface", 1, None),
+            ("Chapter 1", 1, Some(1)),
+            ("1.1 Basics", 2, Some(2)),
+            ("1.2 Methods", 2, Some(5)),
+            ("Chapter 2", 1, Some(9)),
+            ("2.1 Intro", 2, Some(9)),
+        ]);
+
+        assert_eq!(section_path(&entries, 1), vec!["Chapter 1"]);
+        assert_eq!(section_path(&entries, 6), vec!["Chapter 1", "1.2 Methods"]);
+        assert_eq!(section_path(&entries, 9), vec!["Chapter 2", "2.1 Intro"]);
+        assert!(section_path(&outline(&[("Appendix", 1, Some(20))]), 3).is_empty());
+    }
+}
@@ -0,0 +1,75 @@
+// Document page text database operations
+use anyhow::{Context, Result};
+use uuid::Uuid;
+use crate::database::{Database, DocumentPage};
+
+impl Database {
+    /// Replace the stored page text of a document.
+    /// `pages` holds the text of every page in order, starting at page 1.
+    pub async fn store_document_pages(&self, document_id: Uuid, pages: &[String]) -> Result<()> {
+        let page_numbers: Vec<i32> = (1..=pages.len() as i32).collect();
+        let char_counts: Vec<i32> = pages.iter().map(|p| p.chars().count() as i32).collect();
+
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        sqlx::query!("DELETE FROM document_pages WHERE document_id = $1", document_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to clear existing document pages")?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO document_pages (document_id, page_number, content, char_count)
+            SELECT $1, page_number, content, char_count
+            FROM UNNEST($2::INTEGER[], $3::TEXT[], $4::INTEGER[]) AS p(page_number, content, char_count)
+            "#,
+            document_id,
+            &page_numbers,
+            pages,
+            &char_counts
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to insert document pages")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(())
+    }
+
+    /// Get the extracted text of a single page
+    pub async fn get_document_page(
+        &self,
+        document_id: Uuid,
+        page_number: i32,
+    ) -> Result<Option<DocumentPage>> {
+        let page = sqlx::query_as!(
+            DocumentPage,
+            r#"
+            SELECT id, document_id, page_number, content, char_count, extracted_at
+            FROM document_pages
+            WHERE document_id = $1 AND page_number = $2
+            "#,
+            document_id,
+            page_number
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch document page")?;
+
+        Ok(page)
+    }
+
+    /// Check whether text has already been extracted for a document
+    pub async fn has_document_pages(&self, document_id: Uuid) -> Result<bool> {
+        let exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM document_pages WHERE document_id = $1) as "exists!""#,
+            document_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to check document pages")?;
+
+        Ok(exists)
+    }
+}
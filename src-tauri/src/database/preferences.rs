@@ -2,7 +2,9 @@
 use anyhow::{Context, Result};
 use serde_json::Value;
 use uuid::Uuid;
+use crate::database::analysis_jobs::InterruptedAnalysisAction;
 use crate::database::Database;
+use crate::embeddings::{EmbeddingBackend, EmbeddingSettings};
 
 impl Database {
     /// Save user preferences
@@ -63,4 +65,112 @@ impl Database {
             Ok(None)
         }
     }
-} 
\ No newline at end of file
+
+    /// Get the embedding provider settings, falling back to the defaults
+    pub async fn get_embedding_settings(&self) -> Result<EmbeddingSettings> {
+        let row = sqlx::query!(
+            r#"
+            SELECT embedding_provider, embedding_base_url, embedding_model, embedding_dimension
+            FROM user_preferences
+            LIMIT 1
+            "#
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch embedding settings")?;
+
+        match row {
+            Some(row) => Ok(EmbeddingSettings {
+                provider: EmbeddingBackend::parse(&row.embedding_provider)?,
+                base_url: row.embedding_base_url,
+                model: row.embedding_model,
+                dimension: row.embedding_dimension,
+            }),
+            None => Ok(EmbeddingSettings::default()),
+        }
+    }
+
+    /// Save the embedding provider settings
+    pub async fn save_embedding_settings(&self, settings: &EmbeddingSettings) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO user_preferences (embedding_provider, embedding_base_url, embedding_model, embedding_dimension)
+            VALUES ($1, NULLIF(TRIM($2), ''), NULLIF(TRIM($3), ''), $4)
+            ON CONFLICT (singleton_key) DO UPDATE
+            SET embedding_provider = EXCLUDED.embedding_provider,
+                embedding_base_url = EXCLUDED.embedding_base_url,
+                embedding_model = EXCLUDED.embedding_model,
+                embedding_dimension = EXCLUDED.embedding_dimension,
+                updated_at = NOW()
+            "#,
+            settings.provider.as_str(),
+            settings.base_url.as_deref(),
+            settings.model.as_deref(),
+            settings.dimension
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to save embedding settings")?;
+
+        Ok(())
+    }
+
+    /// What to do at startup with analyses the app was running when it quit
+    pub async fn get_interrupted_analysis_action(&self) -> Result<InterruptedAnalysisAction> {
+        let action = sqlx::query_scalar!("SELECT interrupted_analysis_action FROM user_preferences LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch interrupted analysis action")?;
+
+        match action {
+            Some(action) => InterruptedAnalysisAction::parse(&action),
+            None => Ok(InterruptedAnalysisAction::Fail),
+        }
+    }
+
+    pub async fn save_interrupted_analysis_action(&self, action: InterruptedAnalysisAction) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO user_preferences (interrupted_analysis_action)
+            VALUES ($1)
+            ON CONFLICT (singleton_key) DO UPDATE
+            SET interrupted_analysis_action = EXCLUDED.interrupted_analysis_action,
+                updated_at = NOW()
+            "#,
+            action.as_str()
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to save interrupted analysis action")?;
+
+        Ok(())
+    }
+
+    /// Whether analyses store extracted concepts as proposals for review instead of saving them
+    pub async fn get_review_extracted_concepts(&self) -> Result<bool> {
+        let enabled = sqlx::query_scalar!("SELECT review_extracted_concepts FROM user_preferences LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch concept review setting")?;
+
+        Ok(enabled.unwrap_or(false))
+    }
+
+    pub async fn save_review_extracted_concepts(&self, enabled: bool) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO user_preferences (review_extracted_concepts)
+            VALUES ($1)
+            ON CONFLICT (singleton_key) DO UPDATE
+            SET review_extracted_concepts = EXCLUDED.review_extracted_concepts,
+                updated_at = NOW()
+            "#,
+            enabled
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to save concept review setting")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+// Full-text search database operations
+use anyhow::{Context, Result};
+use uuid::Uuid;
+use crate::database::{Database, LibrarySearchResult};
+
+/// Convert a user search string into a PostgreSQL `to_tsquery` expression.
+///
+/// Supported syntax:
+/// - `word other` matches pages containing both words
+/// - `"exact phrase"` matches the words next to each other
+/// - `optim*` matches any word starting with the prefix
+/// - `-word` excludes pages containing the word
+///
+/// Returns `None` when nothing searchable is left after sanitizing.
+pub fn build_tsquery(query: &str) -> Option<String> {
+    let mut clauses = Vec::new();
+    let mut rest = query.trim();
+
+    while !rest.is_empty() {
+        let negated = rest.starts_with('-');
+        if negated {
+            rest = &rest[1..];
+        }
+
+        let (term, is_phrase, remaining) = if let Some(phrase) = rest.strip_prefix('"') {
+            match phrase.find('"') {
+                Some(end) => (&phrase[..end], true, &phrase[end + 1..]),
+                None => (phrase, true, ""),
+            }
+        } else {
+            match rest.find(char::is_whitespace) {
+                Some(end) => (&rest[..end], false, &rest[end..]),
+                None => (rest, false, ""),
+            }
+        };
+        rest = remaining.trim_start();
+
+        let is_prefix = !is_phrase && term.ends_with('*');
+        let mut lexemes: Vec<String> = term
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_lowercase())
+            .collect();
+        if lexemes.is_empty() {
+            continue;
+        }
+        if is_prefix {
+            if let Some(last) = lexemes.last_mut() {
+                last.push_str(":*");
+            }
+        }
+
+        // Hyphenated words and phrases both become adjacency matches
+        let clause = if lexemes.len() == 1 {
+            lexemes.remove(0)
+        } else {
+            format!("({})", lexemes.join(" <-> "))
+        };
+        clauses.push(if negated { format!("!{}", clause) } else { clause });
+    }
+
+    // A query made only of exclusions would match nothing useful
+    if clauses.iter().all(|c| c.starts_with('!')) {
+        return None;
+    }
+
+    Some(clauses.join(" & "))
+}
+
+impl Database {
+    /// Ranked full-text search over the extracted text of every page in the library
+    pub async fn search_library(
+        &self,
+        query: &str,
+        document_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<LibrarySearchResult>> {
+        let Some(tsquery) = build_tsquery(query) else {
+            return Ok(vec![]);
+        };
+
+        let results = sqlx::query_as!(
+            LibrarySearchResult,
+            r#"
+            SELECT
+                dp.document_id,
+                d.title AS document_title,
+                dp.page_number,
+                ts_headline(
+                    'english', dp.content, q,
+                    'StartSel=<mark>, StopSel=</mark>, MaxWords=35, MinWords=15, MaxFragments=2, FragmentDelimiter=" … "'
+                ) AS "snippet!",
+                ts_rank_cd(dp.search_vector, q, 32) AS "score!"
+            FROM document_pages dp
+            JOIN documents d ON d.id = dp.document_id
+            CROSS JOIN to_tsquery('english', $1) q
+            WHERE dp.search_vector @@ q
+              AND ($2::UUID IS NULL OR dp.document_id = $2)
+            ORDER BY 5 DESC, d.title, dp.page_number
+            LIMIT $3 OFFSET $4
+            "#,
+            tsquery,
+            document_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to search library")?;
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_words_are_combined_with_and() {
+        assert_eq!(build_tsquery("gradient descent").as_deref(), Some("gradient & descent"));
+    }
+
+    #[test]
+    fn test_phrase_and_prefix_queries() {
+        assert_eq!(
+            build_tsquery("\"stochastic gradient descent\" optim*").as_deref(),
+            Some("(stochastic <-> gradient <-> descent) & optim:*")
+        );
+    }
+
+    #[test]
+    fn test_exclusions_and_sanitizing() {
+        assert_eq!(
+            build_tsquery("back-propagation -keras 'nets' & |").as_deref(),
+            Some("(back <-> propagation) & !keras & nets")
+        );
+        assert_eq!(build_tsquery("-keras"), None);
+        assert_eq!(build_tsquery("  &&  "), None);
+    }
+}
@@ -0,0 +1,317 @@
+// Unified search index maintenance and queries
+use anyhow::{Context, Result};
+use uuid::Uuid;
+use crate::database::search::build_tsquery;
+use crate::database::{Database, UnifiedSearchResult};
+
+/// Kinds of entities stored in `search_index`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchEntityType {
+    Document,
+    ChatMessage,
+    HighlightedContext,
+    Concept,
+    UserNote,
+}
+
+impl SearchEntityType {
+    /// Value stored in `search_index.entity_type`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchEntityType::Document => "document",
+            SearchEntityType::ChatMessage => "chat-message",
+            SearchEntityType::HighlightedContext => "highlighted-context",
+            SearchEntityType::Concept => "concept",
+            SearchEntityType::UserNote => "user-note",
+        }
+    }
+
+    /// Relevance multiplier applied to the text rank of this entity type.
+    /// Curated knowledge ranks above raw conversation.
+    pub fn weight(&self) -> f64 {
+        match self {
+            SearchEntityType::Document => 1.0,
+            SearchEntityType::Concept => 0.9,
+            SearchEntityType::UserNote => 0.9,
+            SearchEntityType::HighlightedContext => 0.8,
+            SearchEntityType::ChatMessage => 0.6,
+        }
+    }
+}
+
+/// Everything needed to (re)index a single entity
+pub struct SearchIndexEntry<'a> {
+    pub entity_type: SearchEntityType,
+    pub entity_id: Uuid,
+    pub title: Option<&'a str>,
+    pub searchable_text: &'a str,
+    pub keywords: &'a [String],
+    pub document_id: Option<Uuid>,
+    pub chat_session_id: Option<Uuid>,
+    pub page_number: Option<i32>,
+}
+
+impl Database {
+    /// Insert or refresh the search index entry for an entity
+    pub async fn index_entity(&self, entry: SearchIndexEntry<'_>) -> Result<()> {
+        // The index rejects empty text, and an entity with no text can't be found anyway
+        if entry.searchable_text.trim().is_empty() {
+            return self.remove_from_index(entry.entity_type, entry.entity_id).await;
+        }
+
+        // Keywords are folded into the text so the existing tsvector index covers them
+        let searchable_text = if entry.keywords.is_empty() {
+            entry.searchable_text.to_string()
+        } else {
+            format!("{}\n{}", entry.searchable_text, entry.keywords.join(" "))
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO search_index (
+                entity_type, entity_id, title, searchable_text, keywords,
+                document_id, chat_session_id, page_number, weight
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9::FLOAT8::NUMERIC(3,2))
+            ON CONFLICT (entity_type, entity_id) DO UPDATE SET
+                title = EXCLUDED.title,
+                searchable_text = EXCLUDED.searchable_text,
+                keywords = EXCLUDED.keywords,
+                document_id = EXCLUDED.document_id,
+                chat_session_id = EXCLUDED.chat_session_id,
+                page_number = EXCLUDED.page_number,
+                weight = EXCLUDED.weight
+            "#,
+            entry.entity_type.as_str(),
+            entry.entity_id,
+            entry.title,
+            searchable_text,
+            entry.keywords,
+            entry.document_id,
+            entry.chat_session_id,
+            entry.page_number,
+            entry.entity_type.weight()
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to update search index")?;
+
+        Ok(())
+    }
+
+    /// Remove an entity from the search index
+    pub async fn remove_from_index(&self, entity_type: SearchEntityType, entity_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM search_index WHERE entity_type = $1 AND entity_id = $2",
+            entity_type.as_str(),
+            entity_id
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to remove entity from search index")?;
+
+        Ok(())
+    }
+
+    /// Remove the messages and highlighted contexts of a chat session from the index
+    pub async fn remove_chat_session_from_index(&self, chat_session_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM search_index
+            WHERE chat_session_id = $1 AND entity_type IN ('chat-message', 'highlighted-context')
+            "#,
+            chat_session_id
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to remove chat session from search index")?;
+
+        Ok(())
+    }
+
+    /// Index (or re-index) every concept linked to a chat session.
+    /// Concepts written by the Python processor bypass `index_entity`, so this runs after analysis.
+    pub async fn index_concepts_for_chat_session(&self, chat_session_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO search_index (entity_type, entity_id, title, searchable_text, keywords, weight)
+            SELECT 'concept', c.id, c.name,
+                   concat_ws(E'\n', c.name, c.description,
+                             (SELECT string_agg(tag, ' ') FROM jsonb_array_elements_text(COALESCE(c.tags, '[]'::jsonb)) tag)),
+                   ARRAY(SELECT jsonb_array_elements_text(COALESCE(c.tags, '[]'::jsonb))),
+                   $2::FLOAT8::NUMERIC(3,2)
+            FROM concepts c
+            JOIN concept_chat_links ccl ON ccl.concept_id = c.id
+            WHERE ccl.chat_session_id = $1
+            ON CONFLICT (entity_type, entity_id) DO UPDATE SET
+                title = EXCLUDED.title,
+                searchable_text = EXCLUDED.searchable_text,
+                keywords = EXCLUDED.keywords
+            "#,
+            chat_session_id,
+            SearchEntityType::Concept.weight()
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to index concepts for chat session")?;
+
+        Ok(())
+    }
+
+    /// Rebuild the whole search index from the source tables
+    pub async fn rebuild_search_index(&self) -> Result<u64> {
+        let mut tx = self.pool.begin().await.context("Failed to begin transaction")?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM search_index
+            WHERE entity_type IN ('document', 'chat-message', 'highlighted-context', 'concept', 'user-note')
+            "#
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to clear search index")?;
+
+        let documents = sqlx::query!(
+            r#"
+            INSERT INTO search_index (entity_type, entity_id, title, searchable_text, keywords, document_id, weight)
+            SELECT 'document', d.id, d.title,
+                   concat_ws(E'\n', d.title, d.author, d.metadata->>'subject', array_to_string(k.keywords, ' ')),
+                   k.keywords,
+                   d.id, $1::FLOAT8::NUMERIC(3,2)
+            FROM documents d
+            CROSS JOIN LATERAL (
+                SELECT ARRAY(SELECT jsonb_array_elements_text(
+                    CASE WHEN jsonb_typeof(d.metadata->'keywords') = 'array'
+                         THEN d.metadata->'keywords' ELSE '[]'::jsonb END
+                )) AS keywords
+            ) k
+            "#,
+            SearchEntityType::Document.weight()
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to index documents")?;
+
+        let messages = sqlx::query!(
+            r#"
+            INSERT INTO search_index (entity_type, entity_id, searchable_text, chat_session_id, weight)
+            SELECT 'chat-message', cm.id, cm.content, cm.chat_session_id, $1::FLOAT8::NUMERIC(3,2)
+            FROM chat_messages cm
+            "#,
+            SearchEntityType::ChatMessage.weight()
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to index chat messages")?;
+
+        let contexts = sqlx::query!(
+            r#"
+            INSERT INTO search_index (
+                entity_type, entity_id, title, searchable_text,
+                document_id, chat_session_id, page_number, weight
+            )
+            SELECT 'highlighted-context', hc.id, hc.document_title, hc.selected_text,
+                   hc.document_id, hc.chat_session_id, hc.page_number, $1::FLOAT8::NUMERIC(3,2)
+            FROM highlighted_contexts hc
+            "#,
+            SearchEntityType::HighlightedContext.weight()
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to index highlighted contexts")?;
+
+        let concepts = sqlx::query!(
+            r#"
+            INSERT INTO search_index (entity_type, entity_id, title, searchable_text, keywords, weight)
+            SELECT 'concept', c.id, c.name,
+                   concat_ws(E'\n', c.name, c.description,
+                             (SELECT string_agg(tag, ' ') FROM jsonb_array_elements_text(COALESCE(c.tags, '[]'::jsonb)) tag)),
+                   ARRAY(SELECT jsonb_array_elements_text(COALESCE(c.tags, '[]'::jsonb))),
+                   $1::FLOAT8::NUMERIC(3,2)
+            FROM concepts c
+            "#,
+            SearchEntityType::Concept.weight()
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to index concepts")?;
+
+        let notes = sqlx::query!(
+            r#"
+            INSERT INTO search_index (
+                entity_type, entity_id, title, searchable_text, keywords,
+                document_id, page_number, weight
+            )
+            SELECT 'user-note', n.id, d.title,
+                   concat_ws(E'\n', n.note_text, array_to_string(n.tags, ' ')),
+                   COALESCE(n.tags, '{}'),
+                   n.document_id, n.page_number, $1::FLOAT8::NUMERIC(3,2)
+            FROM user_notes n
+            JOIN documents d ON d.id = n.document_id
+            "#,
+            SearchEntityType::UserNote.weight()
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to index user notes")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(documents.rows_affected()
+            + messages.rows_affected()
+            + contexts.rows_affected()
+            + concepts.rows_affected()
+            + notes.rows_affected())
+    }
+
+    /// Ranked search across every indexed entity type.
+    /// `entity_types` restricts the result to the given `search_index.entity_type` values.
+    pub async fn unified_search(
+        &self,
+        query: &str,
+        entity_types: Option<&[String]>,
+        limit: i64,
+    ) -> Result<Vec<UnifiedSearchResult>> {
+        let Some(tsquery) = build_tsquery(query) else {
+            return Ok(vec![]);
+        };
+
+        let results = sqlx::query_as!(
+            UnifiedSearchResult,
+            r#"
+            SELECT
+                si.entity_type,
+                si.entity_id,
+                si.title,
+                ts_headline(
+                    'english', si.searchable_text, q,
+                    'StartSel=<mark>, StopSel=</mark>, MaxWords=35, MinWords=15'
+                ) AS "snippet!",
+                si.document_id,
+                d.title AS "document_title?",
+                si.page_number,
+                si.chat_session_id,
+                cs.title AS "chat_title?",
+                si.weight::FLOAT8 AS "weight!",
+                (ts_rank(to_tsvector('english', si.searchable_text), q) * si.weight)::FLOAT8 AS "score!"
+            FROM search_index si
+            CROSS JOIN to_tsquery('english', $1) q
+            LEFT JOIN documents d ON d.id = si.document_id
+            LEFT JOIN chat_sessions cs ON cs.id = si.chat_session_id
+            WHERE to_tsvector('english', si.searchable_text) @@ q
+              AND ($2::TEXT[] IS NULL OR si.entity_type = ANY($2))
+            ORDER BY 11 DESC
+            LIMIT $3
+            "#,
+            tsquery,
+            entity_types,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to run unified search")?;
+
+        Ok(results)
+    }
+}
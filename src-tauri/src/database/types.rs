@@ -29,9 +29,67 @@ pub struct Document {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub metadata: Value,
+    pub content_hash: Option<String>,
+    pub outline_extracted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct DocumentOutlineEntry {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub position: i32,
+    pub level: i32,
+    pub title: String,
+    pub page_number: Option<i32>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineNode {
+    pub title: String,
+    pub level: i32,
+    pub page_number: Option<i32>,
+    pub children: Vec<OutlineNode>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct DocumentPage {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub page_number: i32,
+    pub content: String,
+    pub char_count: i32,
+    pub extracted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibrarySearchResult {
+    pub document_id: Uuid,
+    pub document_title: String,
+    pub page_number: i32,
+    pub snippet: String,
+    pub score: f32,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnifiedSearchResult {
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub title: Option<String>,
+    pub snippet: String,
+    pub document_id: Option<Uuid>,
+    pub document_title: Option<String>,
+    pub page_number: Option<i32>,
+    pub chat_session_id: Option<Uuid>,
+    pub chat_title: Option<String>,
+    pub weight: f64,
+    pub score: f64,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TextSelection {
     pub id: Uuid,
     pub document_id: Uuid,
@@ -40,7 +98,10 @@ pub struct TextSelection {
     pub start_coordinate: Value,
     pub end_coordinate: Value,
     pub bounding_boxes: Value,
+    pub color: String,
+    pub note: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -106,6 +167,7 @@ pub struct HighlightedContext {
     pub page_number: i32,
     pub selected_text: String,
     pub text_coordinates: Value,
+    pub section_title: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -151,4 +213,285 @@ pub struct ExtractedConcept {
     pub source_chat_count: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
-} 
\ No newline at end of file
+}
+
+/// A concept found near another one in embedding space
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarConcept {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub confidence_score: f64,
+    /// Cosine similarity to the source concept, 1.0 being identical
+    pub similarity_score: f64,
+}
+
+/// Embedding cache usage of one model
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingCacheStats {
+    pub model: String,
+    pub entries: i64,
+    pub hits: i64,
+    pub misses: i64,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Why a concept matched a text search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConceptMatchReason {
+    /// The query embedding is close to the concept embedding
+    Semantic,
+    /// The query words appear in the name or description
+    FullText,
+    /// The concept name contains the query
+    Name,
+}
+
+/// A concept returned by a hybrid text search
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConceptSearchResult {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub confidence_score: f64,
+    pub source_chat_count: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Blended relevance between 0.0 and 1.0
+    pub score: f64,
+    pub semantic_score: Option<f64>,
+    pub lexical_score: Option<f64>,
+    pub match_reasons: Vec<ConceptMatchReason>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelocatedDocument {
+    pub document_id: Uuid,
+    pub title: String,
+    pub old_path: String,
+    pub new_path: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingDocument {
+    pub document_id: Uuid,
+    pub title: String,
+    pub file_path: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelocationReport {
+    pub relocated: Vec<RelocatedDocument>,
+    pub still_missing: Vec<MissingDocument>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryRoot {
+    pub id: Uuid,
+    pub path: String,
+    pub created_at: DateTime<Utc>,
+    pub last_scanned_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserNote {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub document_title: String,
+    pub selection_id: Option<Uuid>,
+    pub page_number: i32,
+    pub note_text: String,
+    pub note_type: String,
+    pub position: Option<Value>,
+    pub tags: Vec<String>,
+    pub is_private: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A background job re-embedding every concept with one model
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingJob {
+    pub id: Uuid,
+    pub model: String,
+    pub dimension: i32,
+    /// `running`, `complete`, `failed` or `cancelled`
+    pub status: String,
+    pub total_items: i32,
+    pub processed_items: i32,
+    pub error_message: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// A background concept extraction run, recorded in `langraph_processing`
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisJob {
+    pub id: Uuid,
+    pub chat_session_id: Uuid,
+    /// `pending`, `processing`, `complete`, `failed` or `cancelled`
+    pub status: String,
+    /// Stages the run has reached, in order, as `{ "stage", "startedAt" }`
+    pub processing_stages: Value,
+    pub extracted_concept_count: i32,
+    pub error_message: Option<String>,
+    /// Concepts created and linked and relationships added by a completed run
+    pub result: Option<Value>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub processing_time_ms: Option<i32>,
+}
+
+/// A concept as a node of the relationship graph
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConceptGraphNode {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub confidence_score: f64,
+    pub source_chat_count: i32,
+}
+
+/// A row of `concept_relationships`
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConceptGraphEdge {
+    pub id: Uuid,
+    pub source_concept_id: Uuid,
+    pub target_concept_id: Uuid,
+    /// `related`, `prerequisite`, `builds_on`, `similar` or `opposite`
+    pub relationship_type: String,
+    pub similarity_score: f64,
+}
+
+/// Concepts and the relationships between them
+#[derive(Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConceptGraph {
+    pub nodes: Vec<ConceptGraphNode>,
+    pub edges: Vec<ConceptGraphEdge>,
+}
+
+/// Whether a relationship points away from or towards the concept asked about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelationshipDirection {
+    Outgoing,
+    Incoming,
+}
+
+/// A concept directly related to another one
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConceptNeighbor {
+    pub concept: ConceptGraphNode,
+    pub relationship: ConceptGraphEdge,
+    pub direction: RelationshipDirection,
+}
+
+/// A chat session in the knowledge graph export
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnowledgeGraphChat {
+    pub id: Uuid,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A document in the knowledge graph export
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnowledgeGraphDocument {
+    pub id: Uuid,
+    pub title: String,
+    pub author: Option<String>,
+}
+
+/// A row of `concept_chat_links`
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConceptChatLink {
+    pub concept_id: Uuid,
+    pub chat_session_id: Uuid,
+    pub relevance_score: f64,
+}
+
+/// A document a chat session highlighted text from
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatDocumentLink {
+    pub chat_session_id: Uuid,
+    pub document_id: Uuid,
+    pub highlight_count: i64,
+}
+
+/// Every concept with the chats it came from, the documents those chats were
+/// about and the relationships between concepts
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnowledgeGraph {
+    pub concepts: Vec<ConceptGraphNode>,
+    pub chats: Vec<KnowledgeGraphChat>,
+    pub documents: Vec<KnowledgeGraphDocument>,
+    pub concept_chat_links: Vec<ConceptChatLink>,
+    pub chat_document_links: Vec<ChatDocumentLink>,
+    pub relationships: Vec<ConceptGraphEdge>,
+}
+
+/// A concept named in an analysis report
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisConceptChange {
+    pub concept_id: Uuid,
+    pub name: String,
+}
+
+/// How an analysis changed the concepts linked to its chat session: concepts newly
+/// linked, linked concepts whose details changed, and links the chat no longer supports
+#[derive(Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisDiff {
+    pub added: Vec<AnalysisConceptChange>,
+    pub updated: Vec<AnalysisConceptChange>,
+    pub unlinked: Vec<AnalysisConceptChange>,
+}
+
+/// A concept an analysis extracted, waiting for review before it joins the knowledge base
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConceptProposal {
+    pub id: Uuid,
+    pub analysis_job_id: Uuid,
+    pub chat_session_id: Uuid,
+    pub name: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub confidence_score: f64,
+    pub related_concepts: Vec<String>,
+    /// 'new', or 'merge' into the target concept
+    pub decision: String,
+    pub target_concept_id: Option<Uuid>,
+    pub target_concept_name: Option<String>,
+    pub similarity_score: Option<f64>,
+    pub status: String,
+    pub concept_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
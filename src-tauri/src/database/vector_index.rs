@@ -0,0 +1,264 @@
+// In-memory concept embedding index. Without the pgvector extension the database
+// can store embeddings but not compare them, so similarity queries use this instead.
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use uuid::Uuid;
+use crate::database::concepts::ConceptForMatching;
+use crate::database::Database;
+use crate::hnsw::Hnsw;
+
+/// Concepts whose embeddings are loaded per query
+const LOAD_BATCH_SIZE: usize = 500;
+
+struct IndexedConcept {
+    name: String,
+    model: String,
+    dimension: usize,
+    updated_at: DateTime<Utc>,
+}
+
+/// Concept embeddings with one HNSW graph per embedding model and dimension,
+/// since vectors of different models can't be compared
+#[derive(Default)]
+pub struct ConceptVectorIndex {
+    graphs: HashMap<(String, usize), Hnsw>,
+    concepts: HashMap<Uuid, IndexedConcept>,
+}
+
+impl ConceptVectorIndex {
+    pub fn len(&self) -> usize {
+        self.concepts.len()
+    }
+
+    fn is_current(&self, id: Uuid, model: &str, dimension: i32, updated_at: DateTime<Utc>) -> bool {
+        self.concepts.get(&id).is_some_and(|concept| {
+            concept.model == model
+                && usize::try_from(dimension).ok() == Some(concept.dimension)
+                && concept.updated_at == updated_at
+        })
+    }
+
+    fn upsert(&mut self, id: Uuid, name: String, model: String, updated_at: DateTime<Utc>, embedding: &[f32]) {
+        self.remove(id);
+        let dimension = embedding.len();
+        self.graphs
+            .entry((model.clone(), dimension))
+            .or_insert_with(|| Hnsw::new(dimension))
+            .insert(id, embedding);
+        self.concepts.insert(id, IndexedConcept { name, model, dimension, updated_at });
+    }
+
+    fn remove(&mut self, id: Uuid) {
+        let Some(concept) = self.concepts.remove(&id) else {
+            return;
+        };
+        let key = (concept.model, concept.dimension);
+        if let Some(graph) = self.graphs.get_mut(&key) {
+            graph.remove(id);
+            if graph.is_empty() {
+                self.graphs.remove(&key);
+            }
+        }
+    }
+
+    /// Concepts nearest to an embedding of `model`, as (id, cosine similarity), closest first
+    pub fn nearest(&self, model: &str, embedding: &[f32], limit: usize) -> Vec<(Uuid, f64)> {
+        self.graphs
+            .get(&(model.to_string(), embedding.len()))
+            .map(|graph| {
+                graph
+                    .search(embedding, limit)
+                    .into_iter()
+                    .map(|(id, similarity)| (id, f64::from(similarity)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Concepts nearest to a concept, excluding the concept itself
+    pub fn similar_to(&self, concept_id: Uuid, limit: usize) -> Vec<(Uuid, f64)> {
+        let Some(concept) = self.concepts.get(&concept_id) else {
+            return vec![];
+        };
+        let Some(embedding) = self
+            .graphs
+            .get(&(concept.model.clone(), concept.dimension))
+            .and_then(|graph| graph.get(concept_id))
+        else {
+            return vec![];
+        };
+
+        self.nearest(&concept.model, embedding, limit + 1)
+            .into_iter()
+            .filter(|(id, _)| *id != concept_id)
+            .take(limit)
+            .collect()
+    }
+
+    /// The concept of `model` nearest to each embedding, and the concepts of `model` in `include`
+    pub fn candidates_for_matching(
+        &self,
+        model: &str,
+        embeddings: &[Vec<f32>],
+        include: &HashSet<Uuid>,
+    ) -> Vec<ConceptForMatching> {
+        let nearest = embeddings
+            .iter()
+            .filter_map(|embedding| self.nearest(model, embedding, 1).first().map(|(id, _)| *id));
+        let ids: BTreeSet<Uuid> = include.iter().copied().chain(nearest).collect();
+
+        ids.into_iter()
+            .filter_map(|id| {
+                let concept = self.concepts.get(&id).filter(|concept| concept.model == model)?;
+                let graph = self.graphs.get(&(concept.model.clone(), concept.dimension))?;
+                Some(ConceptForMatching {
+                    id,
+                    name: concept.name.clone(),
+                    embedding: graph.get(id)?.to_vec(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Database {
+    /// Whether the pgvector extension is installed in the connected database
+    pub async fn has_vector_extension(&self) -> Result<bool> {
+        let installed = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'vector') AS "installed!""#
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to check for the vector extension")?;
+
+        Ok(installed)
+    }
+
+    /// Build the in-memory index from the stored embeddings if pgvector is missing.
+    /// Returns whether the index is in use.
+    pub async fn init_vector_index(&self) -> Result<bool> {
+        if self.has_vector_extension().await? {
+            return Ok(false);
+        }
+
+        *self.vector_index.write().await = Some(ConceptVectorIndex::default());
+        self.sync_vector_index().await?;
+
+        if let Some(index) = self.vector_index.read().await.as_ref() {
+            tracing::info!("pgvector is not installed; indexed {} concept embeddings in memory", index.len());
+        }
+        Ok(true)
+    }
+
+    /// Bring the in-memory index in line with the stored embeddings: concepts that
+    /// are new or changed since they were indexed are loaded, deleted ones dropped.
+    /// Does nothing while pgvector handles similarity.
+    pub async fn sync_vector_index(&self) -> Result<()> {
+        self.refresh_vector_index(false).await
+    }
+
+    /// Reload every embedding into the in-memory index. Needed after a re-embedding
+    /// job, which replaces vectors without touching `updated_at`.
+    pub async fn rebuild_vector_index(&self) -> Result<()> {
+        self.refresh_vector_index(true).await
+    }
+
+    async fn refresh_vector_index(&self, reload_all: bool) -> Result<()> {
+        if self.vector_index.read().await.is_none() {
+            return Ok(());
+        }
+
+        let current = sqlx::query!(
+            r#"
+            SELECT id, embedding_model AS "embedding_model!", embedding_dimension AS "embedding_dimension!", updated_at
+            FROM concepts
+            WHERE embedding IS NOT NULL
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list concept embeddings")?;
+
+        let (stale, removed) = {
+            let guard = self.vector_index.read().await;
+            let Some(index) = guard.as_ref() else {
+                return Ok(());
+            };
+            let stale: Vec<Uuid> = current
+                .iter()
+                .filter(|row| {
+                    reload_all || !index.is_current(row.id, &row.embedding_model, row.embedding_dimension, row.updated_at)
+                })
+                .map(|row| row.id)
+                .collect();
+            let present: HashSet<Uuid> = current.iter().map(|row| row.id).collect();
+            let removed: Vec<Uuid> = index.concepts.keys().filter(|id| !present.contains(id)).copied().collect();
+            (stale, removed)
+        };
+
+        let mut loaded = Vec::with_capacity(stale.len());
+        for ids in stale.chunks(LOAD_BATCH_SIZE) {
+            // A real[] cast reads pgvector columns and plain array columns alike
+            let rows = sqlx::query!(
+                r#"
+                SELECT id, name, embedding_model AS "embedding_model!", updated_at,
+                       embedding::REAL[] AS "embedding!"
+                FROM concepts
+                WHERE id = ANY($1) AND embedding IS NOT NULL
+                "#,
+                ids
+            )
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to load concept embeddings")?;
+            loaded.extend(rows);
+        }
+
+        let mut guard = self.vector_index.write().await;
+        if let Some(index) = guard.as_mut() {
+            for id in removed {
+                index.remove(id);
+            }
+            for row in loaded {
+                index.upsert(row.id, row.name, row.embedding_model, row.updated_at, &row.embedding);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_keeps_models_apart() {
+        let mut index = ConceptVectorIndex::default();
+        let now = Utc::now();
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        index.upsert(a, "a".into(), "small".into(), now, &[1.0, 0.0, 0.0]);
+        index.upsert(b, "b".into(), "small".into(), now, &[0.9, 0.1, 0.0]);
+        index.upsert(c, "c".into(), "large".into(), now, &[1.0, 0.0, 0.0]);
+
+        let similar = index.similar_to(a, 5);
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].0, b);
+        let all = HashSet::from([a, b, c]);
+        assert_eq!(index.candidates_for_matching("large", &[], &all).len(), 1);
+        assert!(index.is_current(a, "small", 3, now));
+        assert!(!index.is_current(a, "large", 3, now));
+
+        // Re-embedding with another model moves the concept between graphs
+        index.upsert(a, "a".into(), "large".into(), now, &[0.0, 1.0, 0.0]);
+        assert!(index.similar_to(b, 5).is_empty());
+        assert_eq!(index.candidates_for_matching("large", &[], &all).len(), 2);
+        let nearest = index.candidates_for_matching("small", &[vec![1.0, 0.1, 0.0]], &HashSet::new());
+        assert_eq!(nearest.iter().map(|concept| concept.id).collect::<Vec<_>>(), vec![b]);
+
+        index.remove(b);
+        assert_eq!(index.len(), 2);
+        assert!(index.nearest("small", &[1.0, 0.0, 0.0], 5).is_empty());
+    }
+}
@@ -0,0 +1,591 @@
+// Embedding providers: turn text into vectors for concept similarity and search.
+// Every provider reports its model name and dimension so stored vectors can be
+// traced back to the model that produced them.
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::database::Database;
+
+pub const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+pub const DEFAULT_OPENAI_MODEL: &str = "text-embedding-3-small";
+pub const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434";
+pub const DEFAULT_OLLAMA_MODEL: &str = "nomic-embed-text";
+pub const HASHING_MODEL: &str = "feature-hashing-v1";
+/// Dimension of the `concepts.embedding` column created by migration 003
+pub const DEFAULT_DIMENSION: usize = 1536;
+
+/// Texts sent to a remote provider in one request
+const BATCH_SIZE: usize = 64;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Something that can embed text
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Model name recorded next to every vector this provider produces
+    fn model(&self) -> &str;
+
+    /// Length of every vector this provider produces
+    fn dimension(&self) -> usize;
+
+    /// Embed texts, returning one vector per text in the same order
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+
+    /// Embed a single text
+    async fn embed_one(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed(&[text.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("Embedding provider returned no vector"))
+    }
+}
+
+/// Which provider to use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingBackend {
+    OpenAi,
+    Ollama,
+    Hashing,
+}
+
+impl EmbeddingBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::OpenAi => "openai",
+            Self::Ollama => "ollama",
+            Self::Hashing => "hashing",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "openai" => Ok(Self::OpenAi),
+            "ollama" => Ok(Self::Ollama),
+            "hashing" => Ok(Self::Hashing),
+            other => Err(anyhow!("Unknown embedding provider '{}'", other)),
+        }
+    }
+}
+
+/// Embedding settings stored in `user_preferences`; unset fields use the provider defaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingSettings {
+    pub provider: EmbeddingBackend,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+    pub dimension: Option<i32>,
+}
+
+impl Default for EmbeddingSettings {
+    fn default() -> Self {
+        Self {
+            provider: EmbeddingBackend::OpenAi,
+            base_url: None,
+            model: None,
+            dimension: None,
+        }
+    }
+}
+
+impl EmbeddingSettings {
+    /// Configured model, or the default model of the provider
+    pub fn model_name(&self) -> &str {
+        match (&self.model, self.provider) {
+            (Some(model), _) => model,
+            (None, EmbeddingBackend::OpenAi) => DEFAULT_OPENAI_MODEL,
+            (None, EmbeddingBackend::Ollama) => DEFAULT_OLLAMA_MODEL,
+            (None, EmbeddingBackend::Hashing) => HASHING_MODEL,
+        }
+    }
+
+    fn configured_dimension(&self) -> Result<Option<usize>> {
+        self.dimension
+            .map(|d| usize::try_from(d).ok().filter(|d| *d > 0).ok_or_else(|| anyhow!("Invalid embedding dimension {}", d)))
+            .transpose()
+    }
+
+    /// Check that the settings describe a provider whose vector length is known
+    pub fn validate(&self) -> Result<()> {
+        let configured = self.configured_dimension()?;
+        match self.provider {
+            EmbeddingBackend::Hashing => Ok(()),
+            _ => resolve_dimension(self.model_name(), configured).map(|_| ()),
+        }
+    }
+}
+
+/// Dimension of well-known embedding models, used when none is configured
+pub fn known_dimension(model: &str) -> Option<usize> {
+    let name = model.split(':').next().unwrap_or(model);
+    match name {
+        "text-embedding-3-small" | "text-embedding-ada-002" => Some(1536),
+        "text-embedding-3-large" => Some(3072),
+        "nomic-embed-text" => Some(768),
+        "mxbai-embed-large" => Some(1024),
+        "all-minilm" => Some(384),
+        _ => None,
+    }
+}
+
+/// Build the provider described by the settings.
+/// The OpenAI API key is only needed for the default OpenAI endpoint.
+pub fn build_provider(settings: &EmbeddingSettings, openai_api_key: Option<&str>) -> Result<Box<dyn EmbeddingProvider>> {
+    let dimension = settings.configured_dimension()?;
+    let model = settings.model_name();
+
+    match settings.provider {
+        EmbeddingBackend::OpenAi => {
+            let api_key = openai_api_key.filter(|k| !k.trim().is_empty());
+            if api_key.is_none() && settings.base_url.is_none() {
+                return Err(anyhow!("OpenAI API key not configured. Please set your API key in Preferences."));
+            }
+            let base_url = settings.base_url.as_deref().unwrap_or(DEFAULT_OPENAI_BASE_URL);
+            Ok(Box::new(OpenAiEmbeddings::new(base_url, api_key, model, dimension)?))
+        }
+        EmbeddingBackend::Ollama => {
+            let base_url = settings.base_url.as_deref().unwrap_or(DEFAULT_OLLAMA_BASE_URL);
+            Ok(Box::new(OllamaEmbeddings::new(base_url, model, dimension)?))
+        }
+        EmbeddingBackend::Hashing => Ok(Box::new(HashingEmbeddings::new(dimension.unwrap_or(DEFAULT_DIMENSION)))),
+    }
+}
+
+/// Build the provider configured in the user preferences, backed by the embedding cache
+pub async fn provider_from_preferences(database: &Database) -> Result<Box<dyn EmbeddingProvider>> {
+    let settings = database.get_embedding_settings().await?;
+    let openai_api_key = database
+        .get_user_preferences()
+        .await?
+        .and_then(|prefs| prefs.get("openai_api_key").and_then(|key| key.as_str().map(|s| s.to_string())));
+
+    let provider = build_provider(&settings, openai_api_key.as_deref())?;
+    // Hashing is cheaper than a cache lookup
+    if settings.provider == EmbeddingBackend::Hashing {
+        return Ok(provider);
+    }
+    Ok(Box::new(CachedEmbeddings::new(provider, database.clone())))
+}
+
+/// The configured provider, failing when its vectors don't fit the concept embedding column
+pub async fn concept_embedding_provider(database: &Database) -> Result<Box<dyn EmbeddingProvider>> {
+    let provider = provider_from_preferences(database).await?;
+    if let Some(column_dimension) = database.concept_embedding_column_dimension().await? {
+        if column_dimension != provider.dimension() {
+            return Err(anyhow!(
+                "{} produces {}-dimensional vectors but concepts store {}",
+                provider.model(),
+                provider.dimension(),
+                column_dimension
+            ));
+        }
+    }
+    Ok(provider)
+}
+
+/// The text embedded for a concept, shared by analysis and re-embedding
+pub fn concept_embedding_text(name: &str, description: &str) -> String {
+    format!("{}: {}", name, description)
+}
+
+/// Cache key of an embedded text: the hex-encoded SHA-256 of its exact content
+pub fn text_hash(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+/// A provider backed by the persistent `embedding_cache`.
+/// Only texts without a cached vector reach the wrapped provider. Cache errors are
+/// logged and never fail an embedding request.
+pub struct CachedEmbeddings {
+    inner: Box<dyn EmbeddingProvider>,
+    database: Database,
+}
+
+impl CachedEmbeddings {
+    pub fn new(inner: Box<dyn EmbeddingProvider>, database: Database) -> Self {
+        Self { inner, database }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for CachedEmbeddings {
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.dimension()
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let model = self.inner.model();
+        let dimension = self.inner.dimension() as i32;
+        let hashes: Vec<String> = texts.iter().map(|text| text_hash(text)).collect();
+
+        let mut vectors = match self.database.get_cached_embeddings(model, dimension, &hashes).await {
+            Ok(cached) => cached,
+            Err(e) => {
+                tracing::warn!("Embedding cache unavailable: {}", e);
+                HashMap::new()
+            }
+        };
+        let hits = hashes.iter().filter(|hash| vectors.contains_key(*hash)).count();
+
+        // Embed each missing text once, even if it occurs several times
+        let mut missing: Vec<(String, String)> = Vec::new();
+        for (hash, text) in hashes.iter().zip(texts) {
+            if !vectors.contains_key(hash) && !missing.iter().any(|(h, _)| h == hash) {
+                missing.push((hash.clone(), text.clone()));
+            }
+        }
+
+        if !missing.is_empty() {
+            let missing_texts: Vec<String> = missing.iter().map(|(_, text)| text.clone()).collect();
+            let computed = self.inner.embed(&missing_texts).await?;
+            let entries: Vec<(String, Vec<f32>)> = missing.into_iter().map(|(hash, _)| hash).zip(computed).collect();
+
+            if let Err(e) = self.database.store_cached_embeddings(model, dimension, &entries).await {
+                tracing::warn!("Failed to cache embeddings: {}", e);
+            }
+            vectors.extend(entries);
+        }
+
+        if let Err(e) = self
+            .database
+            .record_embedding_cache_lookups(model, hits as i64, (texts.len() - hits) as i64)
+            .await
+        {
+            tracing::warn!("Failed to record embedding cache lookups: {}", e);
+        }
+
+        hashes
+            .iter()
+            .map(|hash| vectors.get(hash).cloned().ok_or_else(|| anyhow!("Embedding provider returned too few vectors")))
+            .collect()
+    }
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .context("Failed to create HTTP client")
+}
+
+fn resolve_dimension(model: &str, configured: Option<usize>) -> Result<usize> {
+    configured
+        .or_else(|| known_dimension(model))
+        .ok_or_else(|| anyhow!("Unknown dimension for embedding model '{}', please configure it", model))
+}
+
+/// Check that a provider returned one vector of the expected length per text
+fn check_vectors(vectors: Vec<Vec<f32>>, expected_count: usize, dimension: usize) -> Result<Vec<Vec<f32>>> {
+    if vectors.len() != expected_count {
+        return Err(anyhow!("Expected {} embeddings, got {}", expected_count, vectors.len()));
+    }
+    if let Some(vector) = vectors.iter().find(|v| v.len() != dimension) {
+        return Err(anyhow!("Expected {}-dimensional embeddings, got {}", dimension, vector.len()));
+    }
+    Ok(vectors)
+}
+
+/// Vectors from an OpenAI `/embeddings` response, ordered by their `index`
+fn parse_openai_response(body: &Value) -> Result<Vec<Vec<f32>>> {
+    let data = body
+        .get("data")
+        .and_then(|d| d.as_array())
+        .ok_or_else(|| anyhow!("Embedding response has no data"))?;
+
+    let mut items: Vec<(u64, Vec<f32>)> = data
+        .iter()
+        .enumerate()
+        .map(|(position, item)| {
+            let index = item.get("index").and_then(|i| i.as_u64()).unwrap_or(position as u64);
+            let embedding = serde_json::from_value(item.get("embedding").cloned().unwrap_or(Value::Null))
+                .context("Embedding response has a malformed vector")?;
+            Ok((index, embedding))
+        })
+        .collect::<Result<_>>()?;
+    items.sort_by_key(|(index, _)| *index);
+
+    Ok(items.into_iter().map(|(_, embedding)| embedding).collect())
+}
+
+/// Any server implementing the OpenAI `/embeddings` API (OpenAI, Azure proxies, LM Studio, vLLM, ...)
+pub struct OpenAiEmbeddings {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    dimension: usize,
+    /// Ask the server to shorten vectors; only sent when configured explicitly
+    requested_dimension: Option<usize>,
+}
+
+impl OpenAiEmbeddings {
+    pub fn new(base_url: &str, api_key: Option<&str>, model: &str, dimension: Option<usize>) -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.map(|k| k.to_string()),
+            model: model.to_string(),
+            dimension: resolve_dimension(model, dimension)?,
+            requested_dimension: dimension,
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiEmbeddings {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH_SIZE) {
+            let mut body = serde_json::json!({ "model": self.model, "input": batch });
+            if let Some(dimension) = self.requested_dimension {
+                body["dimensions"] = dimension.into();
+            }
+
+            let mut request = self.client.post(format!("{}/embeddings", self.base_url)).json(&body);
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+            let response: Value = request
+                .send()
+                .await
+                .context("Failed to reach embedding server")?
+                .error_for_status()
+                .context("Embedding request failed")?
+                .json()
+                .await
+                .context("Failed to read embedding response")?;
+
+            vectors.extend(check_vectors(parse_openai_response(&response)?, batch.len(), self.dimension)?);
+        }
+        Ok(vectors)
+    }
+}
+
+/// A local Ollama server (`/api/embed`)
+pub struct OllamaEmbeddings {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    dimension: usize,
+}
+
+impl OllamaEmbeddings {
+    pub fn new(base_url: &str, model: &str, dimension: Option<usize>) -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            dimension: resolve_dimension(model, dimension)?,
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaEmbeddings {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH_SIZE) {
+            let response: Value = self
+                .client
+                .post(format!("{}/api/embed", self.base_url))
+                .json(&serde_json::json!({ "model": self.model, "input": batch }))
+                .send()
+                .await
+                .context("Failed to reach Ollama")?
+                .error_for_status()
+                .context("Ollama embedding request failed")?
+                .json()
+                .await
+                .context("Failed to read Ollama response")?;
+
+            let embeddings = serde_json::from_value(response.get("embeddings").cloned().unwrap_or(Value::Null))
+                .context("Ollama response has no embeddings")?;
+            vectors.extend(check_vectors(embeddings, batch.len(), self.dimension)?);
+        }
+        Ok(vectors)
+    }
+}
+
+/// Deterministic, offline embeddings from hashed words and character trigrams.
+/// Texts sharing vocabulary end up close together, which is enough for tests and
+/// for running without any embedding service.
+pub struct HashingEmbeddings {
+    dimension: usize,
+}
+
+impl HashingEmbeddings {
+    pub fn new(dimension: usize) -> Self {
+        Self { dimension }
+    }
+
+    /// Embed a text without going through the async trait
+    pub fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimension];
+        if self.dimension == 0 {
+            return vector;
+        }
+
+        let lowercase = text.to_lowercase();
+        for word in lowercase.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+            self.add_feature(&mut vector, word.as_bytes(), 1.0);
+
+            let padded: Vec<char> = format!(" {} ", word).chars().collect();
+            for trigram in padded.windows(3) {
+                let trigram: String = trigram.iter().collect();
+                self.add_feature(&mut vector, trigram.as_bytes(), 0.5);
+            }
+        }
+
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+        vector
+    }
+
+    fn add_feature(&self, vector: &mut [f32], feature: &[u8], weight: f32) {
+        let hash = fnv1a(feature);
+        let index = (hash % self.dimension as u64) as usize;
+        // The top bit picks the sign so unrelated features tend to cancel out
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[index] += sign * weight;
+    }
+}
+
+/// 64-bit FNV-1a, stable across platforms and Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+#[async_trait]
+impl EmbeddingProvider for HashingEmbeddings {
+    fn model(&self) -> &str {
+        HASHING_MODEL
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed_text(text)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[test]
+    fn test_hashing_embeddings_are_deterministic_and_normalized() {
+        let provider = HashingEmbeddings::new(256);
+        let first = provider.embed_text("Stochastic gradient descent");
+        let second = provider.embed_text("stochastic  GRADIENT descent");
+
+        assert_eq!(first.len(), 256);
+        assert_eq!(first, second);
+        assert!((cosine(&first, &first) - 1.0).abs() < 1e-5);
+        assert!(provider.embed_text("").iter().all(|v| *v == 0.0));
+    }
+
+    #[test]
+    fn test_hashing_embeddings_reflect_shared_vocabulary() {
+        let provider = HashingEmbeddings::new(512);
+        let query = provider.embed_text("gradient descent");
+        let related = provider.embed_text("mini-batch gradient descent optimizer");
+        let unrelated = provider.embed_text("photosynthesis in chloroplasts");
+
+        assert!(cosine(&query, &related) > cosine(&query, &unrelated));
+    }
+
+    #[test]
+    fn test_text_hash_is_exact_content_sha256() {
+        assert_eq!(
+            text_hash("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_ne!(text_hash("Gradient descent"), text_hash("gradient descent"));
+    }
+
+    #[test]
+    fn test_parse_openai_response_orders_by_index() {
+        let body = serde_json::json!({
+            "data": [
+                { "index": 1, "embedding": [0.0, 1.0] },
+                { "index": 0, "embedding": [1.0, 0.0] }
+            ]
+        });
+
+        let vectors = parse_openai_response(&body).unwrap();
+        assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert!(check_vectors(vectors.clone(), 2, 2).is_ok());
+        assert!(check_vectors(vectors, 2, 3).is_err());
+        assert!(parse_openai_response(&serde_json::json!({ "error": "bad key" })).is_err());
+    }
+
+    #[test]
+    fn test_build_provider_reports_model_and_dimension() {
+        let hashing = build_provider(
+            &EmbeddingSettings { provider: EmbeddingBackend::Hashing, dimension: Some(64), ..Default::default() },
+            None,
+        )
+        .unwrap();
+        assert_eq!((hashing.model(), hashing.dimension()), (HASHING_MODEL, 64));
+
+        let ollama = build_provider(&EmbeddingSettings { provider: EmbeddingBackend::Ollama, ..Default::default() }, None).unwrap();
+        assert_eq!((ollama.model(), ollama.dimension()), (DEFAULT_OLLAMA_MODEL, 768));
+
+        // The hosted OpenAI API needs a key, a self-hosted compatible server may not
+        assert!(build_provider(&EmbeddingSettings::default(), None).is_err());
+        let local = EmbeddingSettings {
+            base_url: Some("http://localhost:1234/v1".to_string()),
+            model: Some("custom-model".to_string()),
+            dimension: Some(1024),
+            ..Default::default()
+        };
+        assert_eq!(build_provider(&local, None).unwrap().dimension(), 1024);
+    }
+
+    #[test]
+    fn test_settings_validation() {
+        assert!(EmbeddingSettings::default().validate().is_ok());
+        assert!(EmbeddingSettings { dimension: Some(0), ..Default::default() }.validate().is_err());
+
+        let unknown_model = EmbeddingSettings {
+            provider: EmbeddingBackend::Ollama,
+            model: Some("my-embedder".to_string()),
+            ..Default::default()
+        };
+        assert!(unknown_model.validate().is_err());
+        assert!(EmbeddingSettings { dimension: Some(512), ..unknown_model }.validate().is_ok());
+    }
+}
@@ -0,0 +1,386 @@
+// Knowledge graph export: concepts, the chats they came from, the documents those
+// chats were about and concept relationships, for external tools such as Gephi.
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fmt::Write;
+use uuid::Uuid;
+
+use crate::database::KnowledgeGraph;
+
+/// Namespace of the GeniusReads terms used in JSON-LD
+const VOCABULARY: &str = "https://geniusreads.app/vocab#";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphExportFormat {
+    GraphMl,
+    Dot,
+    JsonLd,
+}
+
+impl GraphExportFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "graphml" => Ok(Self::GraphMl),
+            "dot" => Ok(Self::Dot),
+            "jsonld" | "json-ld" => Ok(Self::JsonLd),
+            other => Err(anyhow!("Unsupported graph export format '{}'", other)),
+        }
+    }
+}
+
+pub fn export_graph(graph: &KnowledgeGraph, format: GraphExportFormat) -> String {
+    match format {
+        GraphExportFormat::GraphMl => to_graphml(graph),
+        GraphExportFormat::Dot => to_dot(graph),
+        GraphExportFormat::JsonLd => serde_json::to_string_pretty(&to_jsonld(graph)).unwrap(),
+    }
+}
+
+fn concept_node(id: Uuid) -> String {
+    format!("concept:{}", id)
+}
+
+fn chat_node(id: Uuid) -> String {
+    format!("chat:{}", id)
+}
+
+fn document_node(id: Uuid) -> String {
+    format!("document:{}", id)
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters other than whitespace are not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Write a GraphML `<data>` element
+fn data(out: &mut String, key: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "      <data key=\"{}\">{}</data>", key, escape_xml(&value.to_string()));
+}
+
+/// GraphML with typed attributes, readable by Gephi, yEd and NetworkX
+fn to_graphml(graph: &KnowledgeGraph) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" ",
+        "xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" ",
+        "xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n",
+        "  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+        "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        "  <key id=\"description\" for=\"node\" attr.name=\"description\" attr.type=\"string\"/>\n",
+        "  <key id=\"tags\" for=\"node\" attr.name=\"tags\" attr.type=\"string\"/>\n",
+        "  <key id=\"confidence\" for=\"node\" attr.name=\"confidence\" attr.type=\"double\"/>\n",
+        "  <key id=\"sourceChatCount\" for=\"node\" attr.name=\"sourceChatCount\" attr.type=\"int\"/>\n",
+        "  <key id=\"author\" for=\"node\" attr.name=\"author\" attr.type=\"string\"/>\n",
+        "  <key id=\"relation\" for=\"edge\" attr.name=\"relation\" attr.type=\"string\"/>\n",
+        "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"double\"/>\n",
+        "  <graph id=\"knowledge\" edgedefault=\"directed\">\n",
+    ));
+
+    for concept in &graph.concepts {
+        let _ = writeln!(out, "    <node id=\"{}\">", concept_node(concept.id));
+        data(&mut out, "kind", "concept");
+        data(&mut out, "label", &concept.name);
+        data(&mut out, "description", &concept.description);
+        data(&mut out, "tags", concept.tags.join(", "));
+        data(&mut out, "confidence", concept.confidence_score);
+        data(&mut out, "sourceChatCount", concept.source_chat_count);
+        out.push_str("    </node>\n");
+    }
+    for chat in &graph.chats {
+        let _ = writeln!(out, "    <node id=\"{}\">", chat_node(chat.id));
+        data(&mut out, "kind", "chat");
+        data(&mut out, "label", &chat.title);
+        out.push_str("    </node>\n");
+    }
+    for document in &graph.documents {
+        let _ = writeln!(out, "    <node id=\"{}\">", document_node(document.id));
+        data(&mut out, "kind", "document");
+        data(&mut out, "label", &document.title);
+        if let Some(author) = &document.author {
+            data(&mut out, "author", author);
+        }
+        out.push_str("    </node>\n");
+    }
+
+    let edge = |out: &mut String, source: String, target: String, relation: &str, weight: f64| {
+        let _ = writeln!(out, "    <edge source=\"{}\" target=\"{}\">", source, target);
+        data(out, "relation", relation);
+        data(out, "weight", weight);
+        out.push_str("    </edge>\n");
+    };
+    for relationship in &graph.relationships {
+        edge(
+            &mut out,
+            concept_node(relationship.source_concept_id),
+            concept_node(relationship.target_concept_id),
+            &relationship.relationship_type,
+            relationship.similarity_score,
+        );
+    }
+    for link in &graph.concept_chat_links {
+        edge(&mut out, concept_node(link.concept_id), chat_node(link.chat_session_id), "discussed_in", link.relevance_score);
+    }
+    for link in &graph.chat_document_links {
+        edge(
+            &mut out,
+            chat_node(link.chat_session_id),
+            document_node(link.document_id),
+            "highlights_from",
+            link.highlight_count as f64,
+        );
+    }
+
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "")
+}
+
+/// Graphviz DOT with one shape per kind of node
+fn to_dot(graph: &KnowledgeGraph) -> String {
+    let mut out = String::from("digraph knowledge {\n  graph [overlap=false];\n  node [style=filled];\n\n");
+
+    for concept in &graph.concepts {
+        let _ = writeln!(
+            out,
+            "  \"{}\" [label=\"{}\", tooltip=\"{}\", shape=ellipse, fillcolor=\"#dbeafe\"];",
+            concept_node(concept.id),
+            escape_dot(&concept.name),
+            escape_dot(&concept.description)
+        );
+    }
+    for chat in &graph.chats {
+        let _ = writeln!(
+            out,
+            "  \"{}\" [label=\"{}\", shape=box, fillcolor=\"#fef3c7\"];",
+            chat_node(chat.id),
+            escape_dot(&chat.title)
+        );
+    }
+    for document in &graph.documents {
+        let _ = writeln!(
+            out,
+            "  \"{}\" [label=\"{}\", shape=note, fillcolor=\"#dcfce7\"];",
+            document_node(document.id),
+            escape_dot(&document.title)
+        );
+    }
+    out.push('\n');
+
+    for relationship in &graph.relationships {
+        let _ = writeln!(
+            out,
+            "  \"{}\" -> \"{}\" [label=\"{}\"];",
+            concept_node(relationship.source_concept_id),
+            concept_node(relationship.target_concept_id),
+            escape_dot(&relationship.relationship_type)
+        );
+    }
+    for link in &graph.concept_chat_links {
+        let _ = writeln!(
+            out,
+            "  \"{}\" -> \"{}\" [style=dashed];",
+            concept_node(link.concept_id),
+            chat_node(link.chat_session_id)
+        );
+    }
+    for link in &graph.chat_document_links {
+        let _ = writeln!(
+            out,
+            "  \"{}\" -> \"{}\" [style=dotted];",
+            chat_node(link.chat_session_id),
+            document_node(link.document_id)
+        );
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn iri(id: Uuid) -> String {
+    format!("urn:uuid:{}", id)
+}
+
+/// JSON-LD using SKOS for concepts and Dublin Core for chats and documents.
+/// `related` relationships become `skos:related`; the other types use GeniusReads terms.
+fn to_jsonld(graph: &KnowledgeGraph) -> Value {
+    let scheme = "urn:geniusreads:knowledge";
+    let mut concepts: HashMap<Uuid, Map<String, Value>> = graph
+        .concepts
+        .iter()
+        .map(|concept| {
+            let node = json!({
+                "@id": iri(concept.id),
+                "@type": "skos:Concept",
+                "skos:inScheme": { "@id": scheme },
+                "skos:prefLabel": concept.name,
+                "skos:definition": concept.description,
+                "dcterms:subject": concept.tags,
+                "gr:confidence": concept.confidence_score,
+            });
+            (concept.id, node.as_object().cloned().unwrap_or_default())
+        })
+        .collect();
+
+    let mut add = |concept_id: Uuid, property: &str, target: Value| {
+        if let Some(node) = concepts.get_mut(&concept_id) {
+            let values = node.entry(property).or_insert_with(|| json!([]));
+            if let Some(values) = values.as_array_mut() {
+                values.push(target);
+            }
+        }
+    };
+    for relationship in &graph.relationships {
+        let property = match relationship.relationship_type.as_str() {
+            "related" => "skos:related".to_string(),
+            "builds_on" => "gr:buildsOn".to_string(),
+            other => format!("gr:{}", other),
+        };
+        add(relationship.source_concept_id, &property, json!({ "@id": iri(relationship.target_concept_id) }));
+        // skos:related is symmetric
+        if relationship.relationship_type == "related" {
+            add(relationship.target_concept_id, "skos:related", json!({ "@id": iri(relationship.source_concept_id) }));
+        }
+    }
+    for link in &graph.concept_chat_links {
+        add(link.concept_id, "dcterms:source", json!({ "@id": iri(link.chat_session_id) }));
+    }
+
+    let mut nodes = vec![json!({
+        "@id": scheme,
+        "@type": "skos:ConceptScheme",
+        "skos:prefLabel": "GeniusReads knowledge graph",
+    })];
+    nodes.extend(graph.concepts.iter().filter_map(|concept| concepts.remove(&concept.id).map(Value::Object)));
+
+    for chat in &graph.chats {
+        let references: Vec<Value> = graph
+            .chat_document_links
+            .iter()
+            .filter(|link| link.chat_session_id == chat.id)
+            .map(|link| json!({ "@id": iri(link.document_id) }))
+            .collect();
+        nodes.push(json!({
+            "@id": iri(chat.id),
+            "@type": "gr:ChatSession",
+            "dcterms:title": chat.title,
+            "dcterms:created": chat.created_at.to_rfc3339(),
+            "dcterms:references": references,
+        }));
+    }
+    for document in &graph.documents {
+        let mut node = json!({
+            "@id": iri(document.id),
+            "@type": "dcterms:BibliographicResource",
+            "dcterms:title": document.title,
+        });
+        if let Some(author) = &document.author {
+            node["dcterms:creator"] = json!(author);
+        }
+        nodes.push(node);
+    }
+
+    json!({
+        "@context": {
+            "skos": "http://www.w3.org/2004/02/skos/core#",
+            "dcterms": "http://purl.org/dc/terms/",
+            "gr": VOCABULARY,
+        },
+        "@graph": nodes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{
+        ChatDocumentLink, ConceptChatLink, ConceptGraphEdge, ConceptGraphNode, KnowledgeGraphChat, KnowledgeGraphDocument,
+    };
+    use chrono::Utc;
+
+    fn sample_graph() -> KnowledgeGraph {
+        let (entropy, heat, chat, paper) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let concept = |id, name: &str, description: &str| ConceptGraphNode {
+            id,
+            name: name.to_string(),
+            description: description.to_string(),
+            tags: vec!["physics".to_string()],
+            confidence_score: 0.9,
+            source_chat_count: 1,
+        };
+        KnowledgeGraph {
+            concepts: vec![
+                concept(entropy, "Entropy", "Disorder of a \"system\" & its <states>"),
+                concept(heat, "Heat", "Energy in transit\nbetween bodies"),
+            ],
+            chats: vec![KnowledgeGraphChat { id: chat, title: "Thermo".to_string(), created_at: Utc::now() }],
+            documents: vec![KnowledgeGraphDocument { id: paper, title: "Physics".to_string(), author: None }],
+            concept_chat_links: vec![ConceptChatLink { concept_id: entropy, chat_session_id: chat, relevance_score: 0.8 }],
+            chat_document_links: vec![ChatDocumentLink { chat_session_id: chat, document_id: paper, highlight_count: 2 }],
+            relationships: vec![ConceptGraphEdge {
+                id: Uuid::new_v4(),
+                source_concept_id: entropy,
+                target_concept_id: heat,
+                relationship_type: "related".to_string(),
+                similarity_score: 0.0,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_graphml_escapes_text() {
+        let graph = sample_graph();
+        let xml = to_graphml(&graph);
+        assert!(xml.contains("Disorder of a &quot;system&quot; &amp; its &lt;states&gt;"));
+        assert_eq!(xml.matches("<node ").count(), 4);
+        assert_eq!(xml.matches("<edge ").count(), 3);
+        assert!(xml.contains(&format!("source=\"concept:{}\"", graph.concepts[0].id)));
+        assert_eq!(escape_xml("a\u{1}b"), "ab");
+    }
+
+    #[test]
+    fn test_dot_escapes_labels() {
+        let dot = to_dot(&sample_graph());
+        assert!(dot.starts_with("digraph knowledge {"));
+        assert!(dot.contains("Disorder of a \\\"system\\\""));
+        assert!(dot.contains("Energy in transit\\nbetween bodies"));
+        assert_eq!(dot.matches(" -> ").count(), 3);
+    }
+
+    #[test]
+    fn test_jsonld_uses_skos() {
+        let graph = sample_graph();
+        let doc = to_jsonld(&graph);
+        let nodes = doc["@graph"].as_array().unwrap();
+        assert_eq!(nodes.len(), 5);
+        assert_eq!(nodes[0]["@type"], "skos:ConceptScheme");
+
+        let entropy = &nodes[1];
+        assert_eq!(entropy["@type"], "skos:Concept");
+        assert_eq!(entropy["skos:prefLabel"], "Entropy");
+        assert_eq!(entropy["skos:related"][0]["@id"], iri(graph.concepts[1].id));
+        assert_eq!(entropy["dcterms:source"][0]["@id"], iri(graph.chats[0].id));
+        // The symmetric side of a `related` relationship
+        assert_eq!(nodes[2]["skos:related"][0]["@id"], iri(graph.concepts[0].id));
+        assert_eq!(nodes[3]["dcterms:references"][0]["@id"], iri(graph.documents[0].id));
+
+        assert!(GraphExportFormat::parse("gexf").is_err());
+        assert_eq!(GraphExportFormat::parse("json-ld").unwrap(), GraphExportFormat::JsonLd);
+    }
+}
@@ -0,0 +1,345 @@
+// Hierarchical navigable small world graph: approximate nearest neighbor search
+// over cosine similarity, used for concept embeddings when pgvector is unavailable.
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use uuid::Uuid;
+
+/// Neighbors kept per node on the upper layers
+const M: usize = 16;
+/// Neighbors kept per node on the bottom layer
+const M_MAX0: usize = 2 * M;
+/// Candidate list size while inserting
+const EF_CONSTRUCTION: usize = 100;
+/// Minimum candidate list size while searching
+const EF_SEARCH: usize = 64;
+
+/// A distance that can live in a heap
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Distance(f32);
+
+impl Eq for Distance {}
+
+impl PartialOrd for Distance {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Distance {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+struct Node {
+    id: Uuid,
+    /// Unit length, so cosine distance is one minus the dot product
+    vector: Vec<f32>,
+    /// Neighbors per layer, from layer 0 up to the node's level
+    neighbors: Vec<Vec<usize>>,
+    deleted: bool,
+}
+
+/// An HNSW index over unit vectors of one dimension
+pub struct Hnsw {
+    dimension: usize,
+    nodes: Vec<Node>,
+    slots: HashMap<Uuid, usize>,
+    entry_point: Option<usize>,
+    max_level: usize,
+    deleted: usize,
+    rng_state: u64,
+}
+
+fn normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|v| v / norm).collect()
+}
+
+fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
+    1.0 - a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>()
+}
+
+impl Hnsw {
+    pub fn new(dimension: usize) -> Self {
+        Self {
+            dimension,
+            nodes: Vec::new(),
+            slots: HashMap::new(),
+            entry_point: None,
+            max_level: 0,
+            deleted: 0,
+            rng_state: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// The stored (unit length) vector of an id
+    pub fn get(&self, id: Uuid) -> Option<&[f32]> {
+        self.slots.get(&id).map(|&slot| self.nodes[slot].vector.as_slice())
+    }
+
+    /// Add a vector, replacing any earlier vector of the same id.
+    /// Vectors of the wrong dimension are ignored.
+    pub fn insert(&mut self, id: Uuid, vector: &[f32]) {
+        if vector.len() != self.dimension {
+            return;
+        }
+        self.remove(id);
+
+        let vector = normalize(vector);
+        let level = self.random_level();
+        let slot = self.nodes.len();
+        self.nodes.push(Node {
+            id,
+            vector,
+            neighbors: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.slots.insert(id, slot);
+
+        let Some(entry_point) = self.entry_point else {
+            self.entry_point = Some(slot);
+            self.max_level = level;
+            return;
+        };
+
+        let query = self.nodes[slot].vector.clone();
+        let mut entry_points = vec![entry_point];
+        for layer in (level + 1..=self.max_level).rev() {
+            entry_points = self.closest(&query, &entry_points, 1, layer);
+        }
+
+        for layer in (0..=level.min(self.max_level)).rev() {
+            let candidates = self.closest(&query, &entry_points, EF_CONSTRUCTION, layer);
+            let neighbors: Vec<usize> = candidates.iter().copied().take(M).collect();
+            for &neighbor in &neighbors {
+                self.connect(neighbor, slot, layer);
+            }
+            self.nodes[slot].neighbors[layer] = neighbors;
+            entry_points = candidates;
+        }
+
+        if level > self.max_level {
+            self.entry_point = Some(slot);
+            self.max_level = level;
+        }
+    }
+
+    /// Remove a vector. The node stays in the graph to keep it navigable
+    /// until enough nodes are gone to rebuild.
+    pub fn remove(&mut self, id: Uuid) -> bool {
+        let Some(slot) = self.slots.remove(&id) else {
+            return false;
+        };
+        self.nodes[slot].deleted = true;
+        self.deleted += 1;
+
+        if self.slots.is_empty() {
+            *self = Self::new(self.dimension);
+        } else if self.deleted * 4 > self.nodes.len() {
+            self.rebuild();
+        }
+        true
+    }
+
+    /// The `k` ids closest to `query`, closest first, with their cosine similarity
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(Uuid, f32)> {
+        let Some(entry_point) = self.entry_point else {
+            return vec![];
+        };
+        if query.len() != self.dimension || k == 0 {
+            return vec![];
+        }
+
+        let query = normalize(query);
+        let mut entry_points = vec![entry_point];
+        for layer in (1..=self.max_level).rev() {
+            entry_points = self.closest(&query, &entry_points, 1, layer);
+        }
+
+        self.closest(&query, &entry_points, EF_SEARCH.max(k + self.deleted), 0)
+            .into_iter()
+            .filter(|&slot| !self.nodes[slot].deleted)
+            .take(k)
+            .map(|slot| {
+                let node = &self.nodes[slot];
+                (node.id, 1.0 - cosine_distance(&query, &node.vector))
+            })
+            .collect()
+    }
+
+    /// Best-first search of one layer, returning up to `ef` slots closest first
+    fn closest(&self, query: &[f32], entry_points: &[usize], ef: usize, layer: usize) -> Vec<usize> {
+        let mut visited: HashSet<usize> = entry_points.iter().copied().collect();
+        let mut candidates = BinaryHeap::new();
+        let mut found = BinaryHeap::new();
+        for &slot in entry_points {
+            let distance = Distance(cosine_distance(query, &self.nodes[slot].vector));
+            candidates.push(Reverse((distance, slot)));
+            found.push((distance, slot));
+        }
+
+        while let Some(Reverse((distance, slot))) = candidates.pop() {
+            if found.len() >= ef && found.peek().is_some_and(|&(worst, _)| distance > worst) {
+                break;
+            }
+            let Some(neighbors) = self.nodes[slot].neighbors.get(layer) else {
+                continue;
+            };
+            for &neighbor in neighbors {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let distance = Distance(cosine_distance(query, &self.nodes[neighbor].vector));
+                if found.len() < ef || found.peek().is_some_and(|&(worst, _)| distance < worst) {
+                    candidates.push(Reverse((distance, neighbor)));
+                    found.push((distance, neighbor));
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        found.into_sorted_vec().into_iter().map(|(_, slot)| slot).collect()
+    }
+
+    /// Add an edge from `from` to `to`, dropping `from`'s farthest neighbor when it has too many
+    fn connect(&mut self, from: usize, to: usize, layer: usize) {
+        let max_neighbors = if layer == 0 { M_MAX0 } else { M };
+        self.nodes[from].neighbors[layer].push(to);
+        if self.nodes[from].neighbors[layer].len() <= max_neighbors {
+            return;
+        }
+
+        let origin = &self.nodes[from].vector;
+        let mut ranked: Vec<(Distance, usize)> = self.nodes[from].neighbors[layer]
+            .iter()
+            .map(|&n| (Distance(cosine_distance(origin, &self.nodes[n].vector)), n))
+            .collect();
+        ranked.sort();
+        ranked.truncate(max_neighbors);
+        self.nodes[from].neighbors[layer] = ranked.into_iter().map(|(_, n)| n).collect();
+    }
+
+    fn rebuild(&mut self) {
+        let live: Vec<(Uuid, Vec<f32>)> = self
+            .nodes
+            .drain(..)
+            .filter(|node| !node.deleted)
+            .map(|node| (node.id, node.vector))
+            .collect();
+        let rng_state = self.rng_state;
+        *self = Self::new(self.dimension);
+        self.rng_state = rng_state;
+        for (id, vector) in live {
+            self.insert(id, &vector);
+        }
+    }
+
+    /// Exponentially distributed level, so each layer holds about 1/M of the one below
+    fn random_level(&mut self) -> usize {
+        // xorshift64*
+        self.rng_state ^= self.rng_state >> 12;
+        self.rng_state ^= self.rng_state << 25;
+        self.rng_state ^= self.rng_state >> 27;
+        let bits = self.rng_state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+        let uniform = (bits as f64 + 1.0) / (1u64 << 53) as f64;
+        (-uniform.ln() / (M as f64).ln()) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_vectors(count: usize, dimension: usize) -> Vec<(Uuid, Vec<f32>)> {
+        let mut state = 42u64;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+        };
+        (0..count)
+            .map(|_| (Uuid::new_v4(), (0..dimension).map(|_| next()).collect()))
+            .collect()
+    }
+
+    fn exact_neighbors(vectors: &[(Uuid, Vec<f32>)], query: &[f32], k: usize) -> Vec<Uuid> {
+        let query = normalize(query);
+        let mut ranked: Vec<(Distance, Uuid)> = vectors
+            .iter()
+            .map(|(id, v)| (Distance(cosine_distance(&query, &normalize(v))), *id))
+            .collect();
+        ranked.sort();
+        ranked.into_iter().take(k).map(|(_, id)| id).collect()
+    }
+
+    #[test]
+    fn test_search_finds_exact_match_first() {
+        let vectors = random_vectors(200, 16);
+        let mut index = Hnsw::new(16);
+        for (id, vector) in &vectors {
+            index.insert(*id, vector);
+        }
+
+        for (id, vector) in vectors.iter().take(20) {
+            let results = index.search(vector, 1);
+            assert_eq!(results[0].0, *id);
+            assert!((results[0].1 - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_search_recall_against_brute_force() {
+        let vectors = random_vectors(1000, 32);
+        let mut index = Hnsw::new(32);
+        for (id, vector) in &vectors {
+            index.insert(*id, vector);
+        }
+
+        let queries = random_vectors(20, 32);
+        let mut hits = 0;
+        for (_, query) in &queries {
+            let expected = exact_neighbors(&vectors, query, 10);
+            let found: Vec<Uuid> = index.search(query, 10).into_iter().map(|(id, _)| id).collect();
+            hits += expected.iter().filter(|id| found.contains(id)).count();
+        }
+        assert!(hits >= 180, "recall too low: {}/200", hits);
+    }
+
+    #[test]
+    fn test_remove_and_replace() {
+        let vectors = random_vectors(100, 8);
+        let mut index = Hnsw::new(8);
+        for (id, vector) in &vectors {
+            index.insert(*id, vector);
+        }
+
+        // Enough removals to trigger a rebuild
+        for (id, _) in vectors.iter().take(40) {
+            assert!(index.remove(*id));
+        }
+        assert!(!index.remove(vectors[0].0));
+        assert_eq!(index.slots.len(), 60);
+        for (id, vector) in vectors.iter().take(40) {
+            assert!(index.search(vector, 60).iter().all(|(found, _)| found != id));
+        }
+
+        let (id, _) = vectors[50];
+        index.insert(id, &vectors[0].1);
+        assert_eq!(index.slots.len(), 60);
+        assert_eq!(index.search(&vectors[0].1, 1)[0].0, id);
+
+        // Wrong dimensions are ignored
+        index.insert(Uuid::new_v4(), &[1.0, 2.0]);
+        assert_eq!(index.slots.len(), 60);
+        assert!(index.search(&[1.0, 2.0], 5).is_empty());
+    }
+}
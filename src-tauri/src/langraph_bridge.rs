@@ -2,11 +2,23 @@ use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyList};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 use uuid::Uuid;
 use anyhow::{Result, anyhow};
 use tracing::info;
 
 use crate::database::concepts::ConceptForMatching;
+use crate::python_worker::PythonWorker;
+
+/// Longest a concept extraction may run in the worker, LLM calls included
+const EXTRACTION_TIMEOUT: Duration = Duration::from_secs(300);
+/// Longest matching and saving concepts, or any other call, may run in the worker
+const PROCESSING_TIMEOUT: Duration = Duration::from_secs(120);
+/// Time between health checks of the worker
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
 
 /// Represents a concept extracted from chat messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +54,7 @@ pub struct HighlightedContextForExtraction {
     pub document_title: String,
     pub page_number: i32,
     pub selected_text: String,
+    pub section_title: Option<String>,
 }
 
 /// Represents the result of concept extraction
@@ -62,12 +75,44 @@ pub struct ConceptProcessingResult {
     pub success: bool,
     pub new_concepts_created: usize,
     pub concepts_linked: usize,
+    /// Extracted concept name to the id of the concept it was stored as or linked to
+    #[serde(default)]
+    pub concept_ids: HashMap<String, Uuid>,
     pub error_message: Option<String>,
 }
 
+/// Where the Python pipeline runs, chosen with `LANGGRAPH_PYTHON_MODE`
+#[derive(Clone)]
+enum PythonBackend {
+    /// In a supervised child process (`worker`, the default)
+    Worker(Arc<PythonWorker>),
+    /// Inside the app through PyO3 (`embedded`)
+    Embedded,
+}
+
 /// LangGraph bridge for concept extraction
+#[derive(Clone)]
 pub struct LangGraphBridge {
     python_module_path: String,
+    backend: PythonBackend,
+}
+
+/// The interpreter for the worker: `LANGGRAPH_PYTHON` if set, else the virtual
+/// environment next to the Python modules, else `python3` from the PATH
+fn worker_interpreter(python_module_path: &Path) -> PathBuf {
+    if let Ok(python) = std::env::var("LANGGRAPH_PYTHON") {
+        return PathBuf::from(python);
+    }
+    let venv = if cfg!(windows) {
+        python_module_path.join("venv").join("Scripts").join("python.exe")
+    } else {
+        python_module_path.join("venv").join("bin").join("python")
+    };
+    if venv.exists() {
+        venv
+    } else {
+        PathBuf::from("python3")
+    }
 }
 
 impl LangGraphBridge {
@@ -94,14 +139,69 @@ impl LangGraphBridge {
         
         // Log the path for debugging
         tracing::info!("LangGraph bridge using Python path: {}", python_path_str);
-        
+
+        dotenvy::dotenv().ok();
+        let mode = std::env::var("LANGGRAPH_PYTHON_MODE").unwrap_or_default();
+        let backend = match mode.as_str() {
+            "embedded" => PythonBackend::Embedded,
+            other => {
+                if !other.is_empty() && other != "worker" {
+                    tracing::warn!("Unknown LANGGRAPH_PYTHON_MODE '{}', using the worker", other);
+                }
+                let python = worker_interpreter(&python_path);
+                tracing::info!("LangGraph bridge running Python in a worker process with {}", python.display());
+                PythonBackend::Worker(Arc::new(PythonWorker::new(python, python_path.join("worker.py"))))
+            }
+        };
+
         Self {
             python_module_path: python_path_str,
+            backend,
+        }
+    }
+
+    /// Ping the worker every `HEALTH_CHECK_INTERVAL` while the bridge exists, starting
+    /// it again when it stopped answering. Nothing to check for embedded Python.
+    pub fn spawn_health_monitor(&self) {
+        let PythonBackend::Worker(worker) = &self.backend else {
+            return;
+        };
+        let worker: Weak<PythonWorker> = Arc::downgrade(worker);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(HEALTH_CHECK_INTERVAL);
+            let Some(worker) = worker.upgrade() else {
+                break;
+            };
+            if let Err(e) = worker.health_check() {
+                tracing::error!("Python worker is unhealthy: {}", e);
+            }
+        });
+    }
+
+    /// Status of the Python pipeline: where it runs and, for the worker, its ping
+    /// answer (`null` while it is busy with a call) and restart count
+    pub fn health(&self) -> Result<Value> {
+        match &self.backend {
+            PythonBackend::Embedded => Ok(serde_json::json!({ "mode": "embedded" })),
+            PythonBackend::Worker(worker) => {
+                let ping = worker.health_check()?;
+                Ok(serde_json::json!({
+                    "mode": "worker",
+                    "ping": ping,
+                    "restarts": worker.restarts()
+                }))
+            }
         }
     }
 
     /// Initialize the Python environment and load required modules
     pub fn initialize(&self) -> Result<()> {
+        if let PythonBackend::Worker(worker) = &self.backend {
+            let pong = worker.start()?;
+            info!("Python worker ready: {}", pong);
+            return Ok(());
+        }
+
         info!("Initializing Python environment for LangGraph");
         info!("Python module path: {}", self.python_module_path);
         
@@ -146,6 +246,30 @@ impl LangGraphBridge {
     /// Extract concepts from chat session using LangGraph workflow
     pub fn extract_concepts(&self, input: &ConceptExtractionInput, openai_api_key: &str) -> Result<serde_json::Value> {
         println!("🧠 Starting concept extraction for session: {}", input.chat_session_id);
+
+        if let PythonBackend::Worker(worker) = &self.backend {
+            // The worker expects the snake_case keys the embedded call builds
+            let params = serde_json::json!({
+                "input": {
+                    "chat_session_id": input.chat_session_id,
+                    "messages": input.messages.iter().map(|msg| serde_json::json!({
+                        "content": msg.content,
+                        "sender_type": msg.sender_type,
+                        "created_at": msg.created_at,
+                    })).collect::<Vec<_>>(),
+                    "highlighted_contexts": input.highlighted_contexts.iter().map(|ctx| serde_json::json!({
+                        "document_title": ctx.document_title,
+                        "page_number": ctx.page_number,
+                        "selected_text": ctx.selected_text,
+                        "section_title": ctx.section_title,
+                    })).collect::<Vec<_>>(),
+                },
+                "openai_api_key": openai_api_key,
+            });
+            let result = worker.call("extract_concepts", params, EXTRACTION_TIMEOUT)?;
+            println!("✅ Concept extraction completed successfully");
+            return Ok(result);
+        }
         
         Python::with_gil(|py| -> Result<serde_json::Value> {
             // Set the OpenAI API key as an environment variable for the Python process
@@ -189,6 +313,7 @@ impl LangGraphBridge {
                 let _ = py_ctx.set_item("document_title", &ctx.document_title);
                 let _ = py_ctx.set_item("page_number", ctx.page_number);
                 let _ = py_ctx.set_item("selected_text", &ctx.selected_text);
+                let _ = py_ctx.set_item("section_title", &ctx.section_title);
                 py_ctx
             }));
             py_input.set_item("highlighted_contexts", py_contexts)
@@ -227,6 +352,17 @@ impl LangGraphBridge {
             chat_session_id
         );
 
+        if let PythonBackend::Worker(worker) = &self.backend {
+            let params = serde_json::json!({
+                "chat_session_id": chat_session_id.to_string(),
+                "new_concepts": new_concepts,
+                "existing_concepts": existing_concepts,
+            });
+            let result = worker.call("process_and_store_concepts", params, PROCESSING_TIMEOUT)?;
+            return serde_json::from_value(result)
+                .map_err(|e| anyhow!("Failed to deserialize concept processing result: {}", e));
+        }
+
         Python::with_gil(|py| -> Result<ConceptProcessingResult> {
             let concept_processor = py.import_bound("concept_processor")?;
 
@@ -249,55 +385,6 @@ impl LangGraphBridge {
         })
     }
 
-    /// Generate vector embeddings for a list of concept descriptions
-    pub fn generate_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f64>>> {
-        info!("Generating vector embeddings for {} texts", texts.len());
-        
-        Python::with_gil(|py| -> Result<Vec<Vec<f64>>> {
-            // Import the vector embeddings module
-            let vector_embeddings = py.import_bound("vector_embeddings")
-                .map_err(|e| anyhow!("Failed to import vector_embeddings: {}", e))?;
-            
-            // Convert texts to Python list
-            let py_texts = PyList::new_bound(py, texts.iter().map(|s| s.as_str()));
-            
-            // Call the generate_embeddings function
-            let generate_function = vector_embeddings.getattr("generate_embeddings")
-                .map_err(|e| anyhow!("Failed to get generate_embeddings function: {}", e))?;
-            let py_result = generate_function.call1((py_texts,))
-                .map_err(|e| anyhow!("Failed to call generate_embeddings: {}", e))?;
-            
-            // Convert Python result to Rust Vec<Vec<f64>>
-            let embeddings: Vec<Vec<f64>> = py_result.extract()
-                .map_err(|e| anyhow!("Failed to extract embeddings: {}", e))?;
-            
-            info!("Generated {} embeddings", embeddings.len());
-            Ok(embeddings)
-        })
-    }
-
-    /// Calculate similarity between two concept embeddings
-    pub fn calculate_similarity(&self, embedding1: Vec<f64>, embedding2: Vec<f64>) -> Result<f64> {
-        Python::with_gil(|py| -> Result<f64> {
-            let vector_embeddings = py.import_bound("vector_embeddings")
-                .map_err(|e| anyhow!("Failed to import vector_embeddings: {}", e))?;
-            
-            // Convert embeddings to Python lists
-            let py_emb1 = PyList::new_bound(py, embedding1.iter());
-            let py_emb2 = PyList::new_bound(py, embedding2.iter());
-            
-            // Call the calculate_similarity function
-            let similarity_function = vector_embeddings.getattr("calculate_similarity")
-                .map_err(|e| anyhow!("Failed to get calculate_similarity function: {}", e))?;
-            let py_result = similarity_function.call1((py_emb1, py_emb2))
-                .map_err(|e| anyhow!("Failed to call calculate_similarity: {}", e))?;
-            
-            let similarity: f64 = py_result.extract()
-                .map_err(|e| anyhow!("Failed to extract similarity: {}", e))?;
-            Ok(similarity)
-        })
-    }
-
     /// Convert Rust input to Python dictionary
     fn input_to_python_dict<'py>(&self, py: Python<'py>, input: &ConceptExtractionInput) -> PyResult<Bound<'py, PyDict>> {
         let dict = PyDict::new_bound(py);
@@ -323,6 +410,7 @@ impl LangGraphBridge {
             ctx_dict.set_item("document_title", &context.document_title)?;
             ctx_dict.set_item("page_number", context.page_number)?;
             ctx_dict.set_item("selected_text", &context.selected_text)?;
+            ctx_dict.set_item("section_title", &context.section_title)?;
             contexts_list.append(ctx_dict)?;
         }
         dict.set_item("highlighted_contexts", contexts_list)?;
@@ -420,6 +508,7 @@ mod tests {
                     document_title: "AI Textbook".to_string(),
                     page_number: 1,
                     selected_text: "Machine learning is a subset of AI".to_string(),
+                    section_title: Some("Chapter 1 > Introduction".to_string()),
                 },
             ],
         };
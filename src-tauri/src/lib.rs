@@ -3,15 +3,23 @@ use std::sync::Arc;
 use tauri::Manager;
 use tokio::sync::Mutex;
 
+mod analysis;
 mod database;
+mod embeddings;
+mod graph_export;
+mod hnsw;
 mod pdf_handler;
+mod pdf_protocol;
+mod python_worker;
 mod langraph_bridge;
 mod commands;
+mod library;
+mod reembedding;
 mod state;
 
 use database::Database;
 use langraph_bridge::LangGraphBridge;
-use state::{DbState, LangGraphState};
+use state::{DbState, LangGraphState, LibraryWatcherState};
 
 // Re-export all commands from the commands module
 use commands::*;
@@ -22,6 +30,9 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
+        .register_asynchronous_uri_scheme_protocol(pdf_protocol::PDF_PROTOCOL, |ctx, request, responder| {
+            pdf_protocol::handle_request(ctx.app_handle(), request, responder)
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             get_app_info,
@@ -32,6 +43,13 @@ pub fn run() {
             update_document_state,
             update_document_total_pages,
             get_recent_documents,
+            get_document_page_text,
+            get_document_outline,
+            relocate_missing_documents,
+            add_library_root,
+            get_library_roots,
+            remove_library_root,
+            scan_library_roots,
             test_database_connection,
             get_database_stats,
             get_documents,
@@ -42,6 +60,18 @@ pub fn run() {
             set_active_chat_session,
             add_chat_message,
             add_highlighted_context,
+            create_highlight,
+            get_page_highlights,
+            get_document_highlights,
+            update_highlight,
+            delete_highlight,
+            create_note,
+            get_note,
+            update_note,
+            delete_note,
+            list_notes,
+            search_notes,
+            export_notes,
             delete_chat_session,
             clear_chat_session,
             end_chat_session,
@@ -52,14 +82,47 @@ pub fn run() {
             get_last_reading_position,
             save_user_preferences,
             get_user_preferences,
+            get_embedding_settings,
+            save_embedding_settings,
+            test_embedding_provider,
+            get_embedding_cache_stats,
+            prune_embedding_cache,
+            start_reembedding,
+            get_reembedding_status,
+            cancel_reembedding,
             analyze_chat_session,
+            get_analysis_job,
+            list_analysis_jobs,
+            cancel_analysis_job,
+            retry_analysis,
+            analyze_all_pending,
+            get_interrupted_analysis_action,
+            save_interrupted_analysis_action,
             get_extraction_concepts,
             get_concept_by_id,
             find_similar_concepts,
             search_concepts_by_text,
+            update_concept,
+            merge_concepts,
+            split_concept,
+            delete_concept,
+            list_concept_proposals,
+            accept_concept_proposal,
+            update_concept_proposal,
+            retarget_concept_proposal,
+            reject_concept_proposal,
+            get_concept_review_enabled,
+            save_concept_review_enabled,
+            get_concept_neighbors,
+            get_concept_subgraph,
+            find_concept_path,
+            export_knowledge_graph,
             get_concept_chat_relationship,
             get_concepts_for_chat_session,
             get_chats_for_concept,
+            search_library,
+            unified_search,
+            rebuild_search_index,
             test_langraph_bridge
         ])
         .setup(|app| {
@@ -71,12 +134,27 @@ pub fn run() {
             let langraph_state: LangGraphState = Arc::new(Mutex::new(None));
             app.manage(langraph_state.clone());
 
+            // Library watcher starts once the database is available
+            let library_watcher_state: LibraryWatcherState = Arc::new(Mutex::new(None));
+            app.manage(library_watcher_state.clone());
+            let app_handle = app.handle().clone();
+            let analysis_app_handle = app.handle().clone();
+            let analysis_db_state = db_state.clone();
+            let worker_langraph_state = langraph_state.clone();
+
             // Connect to database in async context
             tauri::async_runtime::spawn(async move {
                 match Database::new_local().await {
                     Ok(database) => {
                         tracing::info!("Database connected successfully");
+                        if let Err(e) = database.init_vector_index().await {
+                            tracing::error!("Failed to set up concept similarity: {}", e);
+                        }
+                        reembedding::resume_running_job(app_handle.clone(), database.clone()).await;
+                        analysis::recover_interrupted(&database).await;
                         *db_state.lock().await = Some(database);
+                        analysis::start_queued(app_handle.clone(), &db_state, &worker_langraph_state).await;
+                        start_library_sync(app_handle, db_state, library_watcher_state).await;
                     }
                     Err(e) => {
                         tracing::error!("Failed to connect to database: {}", e);
@@ -92,11 +170,20 @@ pub fn run() {
                 let bridge = LangGraphBridge::new();
                 println!("🔧 Created LangGraph bridge, attempting to initialize...");
                 
-                match bridge.initialize() {
+                // Starting Python blocks, so it stays off the async runtime
+                let initialized = {
+                    let bridge = bridge.clone();
+                    tauri::async_runtime::spawn_blocking(move || bridge.initialize()).await
+                        .map_err(anyhow::Error::from)
+                        .and_then(|result| result)
+                };
+                match initialized {
                     Ok(_) => {
                         println!("✅ LangGraph bridge initialized successfully!");
                         tracing::info!("LangGraph bridge initialized successfully");
+                        bridge.spawn_health_monitor();
                         *langraph_state.lock().await = Some(bridge);
+                        analysis::start_queued(analysis_app_handle, &analysis_db_state, &langraph_state).await;
                     }
                     Err(e) => {
                         println!("❌ Failed to initialize LangGraph bridge: {}", e);
@@ -0,0 +1,323 @@
+// Library folders: bulk import from configured roots and keeping `documents` in
+// sync with the files on disk while the app runs
+use anyhow::{anyhow, Result};
+use notify::event::EventKind;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::sync::Mutex;
+
+use crate::commands::spawn_text_extraction;
+use crate::database::documents::NewDocument;
+use crate::database::LibraryRoot;
+use crate::pdf_handler::PDFHandler;
+use crate::state::DbState;
+
+/// Emitted after every file of a scan with a `LibraryScanProgress` payload
+pub const SCAN_PROGRESS_EVENT: &str = "library-scan-progress";
+/// Emitted when a scan of a root finishes with its final `LibraryScanProgress`
+pub const SCAN_COMPLETE_EVENT: &str = "library-scan-complete";
+/// Emitted when the watcher changed `documents`, with a `LibrarySyncSummary` payload
+pub const LIBRARY_CHANGED_EVENT: &str = "library-changed";
+
+/// How long to wait for a burst of file system events to settle before syncing
+const WATCH_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Scans and watcher updates are serialized so the same file is never imported twice
+static SYNC_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibrarySyncSummary {
+    pub imported: u32,
+    pub relinked: u32,
+    pub removed: u32,
+    pub skipped: u32,
+    pub failed: u32,
+}
+
+impl LibrarySyncSummary {
+    fn has_changes(&self) -> bool {
+        self.imported + self.relinked + self.removed > 0
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryScanProgress {
+    pub root_id: String,
+    pub root_path: String,
+    pub processed: usize,
+    pub total: usize,
+    pub current_file: Option<String>,
+    #[serde(flatten)]
+    pub summary: LibrarySyncSummary,
+}
+
+enum ImportOutcome {
+    Imported,
+    Relinked,
+    /// Already in the library, or a duplicate of a document whose file still exists
+    Skipped,
+}
+
+/// Import one PDF found in a library root
+async fn import_file(db: &DbState, path: &str) -> Result<ImportOutcome> {
+    {
+        let db_guard = db.lock().await;
+        let database = db_guard.as_ref().ok_or_else(|| anyhow!("Database not initialized"))?;
+        if database.get_document_by_path(path).await?.is_some() {
+            return Ok(ImportOutcome::Skipped);
+        }
+    }
+
+    // Hashing and parsing happen without holding the database lock
+    let owned_path = path.to_string();
+    let content_hash =
+        tauri::async_runtime::spawn_blocking(move || PDFHandler::compute_content_hash(&owned_path)).await??;
+
+    {
+        let db_guard = db.lock().await;
+        let database = db_guard.as_ref().ok_or_else(|| anyhow!("Database not initialized"))?;
+        if let Some(known) = database.get_document_by_content_hash(&content_hash).await? {
+            // Two copies of the same paper stay one document; only a vanished file is relinked
+            if Path::new(&known.file_path).exists() {
+                return Ok(ImportOutcome::Skipped);
+            }
+            let file_name = Path::new(path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| known.file_name.clone());
+            database.relink_document(known.id, path, &file_name, None).await?;
+            tracing::info!("Relinked document {} from {} to {}", known.id, known.file_path, path);
+            return Ok(ImportOutcome::Relinked);
+        }
+    }
+
+    let owned_path = path.to_string();
+    let pdf_doc =
+        tauri::async_runtime::spawn_blocking(move || PDFHandler::create_document(&owned_path)).await??;
+
+    let document_id = {
+        let db_guard = db.lock().await;
+        let database = db_guard.as_ref().ok_or_else(|| anyhow!("Database not initialized"))?;
+        database
+            .create_document(NewDocument {
+                title: &pdf_doc.title,
+                author: pdf_doc.metadata.author.as_deref(),
+                file_path: &pdf_doc.file_path,
+                file_name: &pdf_doc.file_name,
+                file_size: pdf_doc.file_size as i64,
+                total_pages: pdf_doc.total_pages as i32,
+                metadata: serde_json::json!(pdf_doc.metadata),
+                content_hash: Some(&content_hash),
+            })
+            .await?
+    };
+    spawn_text_extraction(db.clone(), document_id, pdf_doc.file_path);
+
+    Ok(ImportOutcome::Imported)
+}
+
+fn record_import(summary: &mut LibrarySyncSummary, path: &str, result: Result<ImportOutcome>) {
+    match result {
+        Ok(ImportOutcome::Imported) => summary.imported += 1,
+        Ok(ImportOutcome::Relinked) => summary.relinked += 1,
+        Ok(ImportOutcome::Skipped) => summary.skipped += 1,
+        Err(e) => {
+            tracing::warn!("Failed to import {}: {}", path, e);
+            summary.failed += 1;
+        }
+    }
+}
+
+/// Remove the documents stored at (or below) `paths` whose files no longer exist
+async fn remove_missing_documents(db: &DbState, paths: &[PathBuf]) -> Result<u32> {
+    let db_guard = db.lock().await;
+    let database = db_guard.as_ref().ok_or_else(|| anyhow!("Database not initialized"))?;
+
+    let mut removed = 0;
+    for document in database.get_documents().await? {
+        let file_path = Path::new(&document.file_path);
+        if file_path.exists() || !paths.iter().any(|p| file_path.starts_with(p)) {
+            continue;
+        }
+        if database.delete_document_if_unused(document.id).await? {
+            tracing::info!("Removed document {} ({})", document.id, document.file_path);
+            removed += 1;
+        } else {
+            tracing::info!("Kept document {} with user data although {} is gone", document.id, document.file_path);
+        }
+    }
+
+    Ok(removed)
+}
+
+fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+}
+
+/// Scan a library root recursively, importing new PDFs and removing documents
+/// whose files disappeared. Progress is reported through `SCAN_PROGRESS_EVENT`.
+pub async fn scan_root<R: Runtime>(
+    app: &AppHandle<R>,
+    db: &DbState,
+    root: &LibraryRoot,
+) -> Result<LibrarySyncSummary> {
+    let _sync = SYNC_LOCK.lock().await;
+
+    let root_path = root.path.clone();
+    let files = tauri::async_runtime::spawn_blocking(move || PDFHandler::find_pdf_files(&root_path)).await?;
+
+    let mut progress = LibraryScanProgress {
+        root_id: root.id.to_string(),
+        root_path: root.path.clone(),
+        processed: 0,
+        total: files.len(),
+        current_file: None,
+        summary: LibrarySyncSummary::default(),
+    };
+    tracing::info!("Scanning library root {} ({} PDFs)", root.path, files.len());
+
+    for file in files {
+        let path = file.to_string_lossy().to_string();
+        let result = import_file(db, &path).await;
+        record_import(&mut progress.summary, &path, result);
+
+        progress.processed += 1;
+        progress.current_file = Some(path);
+        let _ = app.emit(SCAN_PROGRESS_EVENT, &progress);
+    }
+
+    progress.summary.removed = remove_missing_documents(db, &[PathBuf::from(&root.path)]).await?;
+    progress.current_file = None;
+
+    {
+        let db_guard = db.lock().await;
+        if let Some(database) = db_guard.as_ref() {
+            database.mark_library_root_scanned(root.id).await?;
+        }
+    }
+
+    let _ = app.emit(SCAN_COMPLETE_EVENT, &progress);
+    Ok(progress.summary)
+}
+
+/// Scan every configured library root, one after the other
+pub async fn scan_all_roots<R: Runtime>(app: &AppHandle<R>, db: &DbState) -> Result<()> {
+    let roots = {
+        let db_guard = db.lock().await;
+        let database = db_guard.as_ref().ok_or_else(|| anyhow!("Database not initialized"))?;
+        database.get_library_roots().await?
+    };
+
+    for root in roots {
+        if let Err(e) = scan_root(app, db, &root).await {
+            tracing::error!("Failed to scan library root {}: {}", root.path, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Bring `documents` in line with a set of changed paths reported by the watcher
+async fn sync_paths(db: &DbState, paths: BTreeSet<PathBuf>) -> LibrarySyncSummary {
+    let _sync = SYNC_LOCK.lock().await;
+    let mut summary = LibrarySyncSummary::default();
+
+    let mut files = BTreeSet::new();
+    let mut gone = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            // A folder moved into a root arrives as a single event
+            files.extend(PDFHandler::find_pdf_files(&path.to_string_lossy()));
+        } else if path.is_file() {
+            if is_pdf(&path) {
+                files.insert(path);
+            }
+        } else {
+            gone.push(path);
+        }
+    }
+
+    // Imports run first so a rename relinks the document before the old path counts as removed
+    for file in files {
+        let path = file.to_string_lossy().to_string();
+        let result = import_file(db, &path).await;
+        record_import(&mut summary, &path, result);
+    }
+
+    if !gone.is_empty() {
+        match remove_missing_documents(db, &gone).await {
+            Ok(removed) => summary.removed = removed,
+            Err(e) => tracing::error!("Failed to remove missing documents: {}", e),
+        }
+    }
+
+    summary
+}
+
+/// File system watcher over the library roots
+pub struct LibraryWatcher {
+    watcher: RecommendedWatcher,
+}
+
+impl LibraryWatcher {
+    /// Start the watcher. Changes are debounced, applied to `documents` and
+    /// announced through `LIBRARY_CHANGED_EVENT`.
+    pub fn start<R: Runtime>(app: AppHandle<R>, db: DbState) -> Result<Self> {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<notify::Event>();
+
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) => {
+                let _ = tx.send(event);
+            }
+            Err(e) => tracing::warn!("Library watcher error: {}", e),
+        })?;
+
+        tauri::async_runtime::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                let mut paths = BTreeSet::new();
+                collect_paths(&mut paths, event);
+                while let Ok(Some(event)) = tokio::time::timeout(WATCH_DEBOUNCE, rx.recv()).await {
+                    collect_paths(&mut paths, event);
+                }
+                if paths.is_empty() {
+                    continue;
+                }
+
+                let summary = sync_paths(&db, paths).await;
+                if summary.has_changes() {
+                    let _ = app.emit(LIBRARY_CHANGED_EVENT, &summary);
+                }
+            }
+        });
+
+        Ok(Self { watcher })
+    }
+
+    /// Watch a library root recursively
+    pub fn watch(&mut self, path: &str) -> Result<()> {
+        self.watcher.watch(Path::new(path), RecursiveMode::Recursive)?;
+        Ok(())
+    }
+
+    /// Stop watching a library root
+    pub fn unwatch(&mut self, path: &str) -> Result<()> {
+        self.watcher.unwatch(Path::new(path))?;
+        Ok(())
+    }
+}
+
+fn collect_paths(paths: &mut BTreeSet<PathBuf>, event: notify::Event) {
+    // Reads don't change the library
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    paths.extend(event.paths);
+}
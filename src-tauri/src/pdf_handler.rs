@@ -1,8 +1,13 @@
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use lopdf::{decode_text_string, Dictionary, Document, Object, ObjectId};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
+use walkdir::WalkDir;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PDFMetadata {
@@ -28,8 +33,21 @@ pub struct PDFDocument {
     pub metadata: PDFMetadata,
 }
 
+/// One entry of a document outline (bookmarks), in reading order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutlineItem {
+    pub title: String,
+    /// Nesting depth, 1 for top-level entries
+    pub level: u32,
+    /// Target page, when the destination could be resolved
+    pub page_number: Option<u32>,
+}
+
 pub struct PDFHandler;
 
+/// Guards against malformed outlines and name trees nested without end
+const MAX_OUTLINE_DEPTH: u32 = 32;
+
 impl PDFHandler {
     /// Validate if a file is a valid PDF
     pub fn validate_pdf(file_path: &str) -> Result<bool> {
@@ -69,7 +87,7 @@ impl PDFHandler {
         }
     }
     
-    /// Extract basic metadata from PDF file
+    /// Extract metadata from the PDF's Info dictionary, XMP packet and page tree
     pub fn extract_metadata(file_path: &str) -> Result<PDFMetadata> {
         // Validate the PDF first
         Self::validate_pdf(file_path)?;
@@ -77,32 +95,209 @@ impl PDFHandler {
         let path = Path::new(file_path);
         let file_size = fs::metadata(path)?.len();
         
-        // For now, we'll extract basic information
-        // In a full implementation, you'd use a PDF parsing library like pdf-extract or lopdf
-        // For MVP, we'll extract what we can from the file system and provide defaults
-        
         let file_name = path
             .file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("Unknown")
             .to_string();
         
-        // Basic metadata extraction (this would be enhanced with a proper PDF library)
-        let metadata = PDFMetadata {
-            title: Some(file_name.clone()),
+        let mut metadata = PDFMetadata {
+            title: None,
             author: None,
             subject: None,
             keywords: None,
             creation_date: None,
             modification_date: None,
             producer: None,
-            page_count: 1, // Default to 1, will be updated by PDF.js frontend
+            page_count: 1,
             file_size,
         };
         
+        // A file with a valid header can still be damaged or use features lopdf doesn't
+        // support, so fall back to file system information instead of rejecting the import
+        match Document::load(path) {
+            Ok(pdf) => {
+                let page_count = pdf.get_pages().len() as u32;
+                if page_count > 0 {
+                    metadata.page_count = page_count;
+                }
+                
+                if let Ok(info) = pdf.trailer.get_deref(b"Info", &pdf).and_then(Object::as_dict) {
+                    Self::apply_info_dictionary(&mut metadata, info);
+                }
+                
+                // XMP only fills the gaps the Info dictionary leaves
+                if let Some(xmp) = Self::read_xmp_packet(&pdf) {
+                    Self::apply_xmp_metadata(&mut metadata, &xmp);
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to parse PDF structure for {}: {}", file_path, e);
+            }
+        }
+        
+        if metadata.title.is_none() {
+            metadata.title = Some(file_name);
+        }
+        
         Ok(metadata)
     }
     
+    /// Copy the standard Info dictionary entries into the metadata
+    fn apply_info_dictionary(metadata: &mut PDFMetadata, info: &Dictionary) {
+        let text = |key: &[u8]| {
+            info.get(key)
+                .ok()
+                .and_then(|obj| decode_text_string(obj).ok())
+                .and_then(|value| clean_text(&value))
+        };
+        
+        metadata.title = text(b"Title");
+        metadata.author = text(b"Author");
+        metadata.subject = text(b"Subject");
+        metadata.keywords = text(b"Keywords").map(|k| split_keywords(&k)).filter(|k| !k.is_empty());
+        metadata.producer = text(b"Producer");
+        metadata.creation_date = text(b"CreationDate").and_then(|d| parse_pdf_date(&d));
+        metadata.modification_date = text(b"ModDate").and_then(|d| parse_pdf_date(&d));
+    }
+    
+    /// Read the document-level XMP packet referenced from the catalog
+    fn read_xmp_packet(pdf: &Document) -> Option<String> {
+        let stream = pdf
+            .catalog()
+            .ok()?
+            .get_deref(b"Metadata", pdf)
+            .and_then(Object::as_stream)
+            .ok()?;
+        let content = stream.get_plain_content().ok()?;
+        Some(String::from_utf8_lossy(&content).into_owned())
+    }
+    
+    /// Fill any metadata fields still missing from the XMP packet
+    fn apply_xmp_metadata(metadata: &mut PDFMetadata, xmp: &str) {
+        if metadata.title.is_none() {
+            metadata.title = xmp_values(xmp, "dc:title").into_iter().next();
+        }
+        if metadata.author.is_none() {
+            let creators = xmp_values(xmp, "dc:creator");
+            if !creators.is_empty() {
+                metadata.author = Some(creators.join(", "));
+            }
+        }
+        if metadata.subject.is_none() {
+            metadata.subject = xmp_values(xmp, "dc:description").into_iter().next();
+        }
+        if metadata.keywords.is_none() {
+            let mut keywords: Vec<String> = xmp_values(xmp, "pdf:Keywords")
+                .iter()
+                .flat_map(|k| split_keywords(k))
+                .collect();
+            for subject in xmp_values(xmp, "dc:subject") {
+                if !keywords.contains(&subject) {
+                    keywords.push(subject);
+                }
+            }
+            if !keywords.is_empty() {
+                metadata.keywords = Some(keywords);
+            }
+        }
+        if metadata.producer.is_none() {
+            metadata.producer = xmp_values(xmp, "pdf:Producer").into_iter().next();
+        }
+        if metadata.creation_date.is_none() {
+            metadata.creation_date = xmp_values(xmp, "xmp:CreateDate")
+                .into_iter()
+                .next()
+                .and_then(|d| parse_xmp_date(&d));
+        }
+        if metadata.modification_date.is_none() {
+            metadata.modification_date = xmp_values(xmp, "xmp:ModifyDate")
+                .into_iter()
+                .next()
+                .and_then(|d| parse_xmp_date(&d));
+        }
+    }
+    
+    /// Parse a PDF once so several extractions can share it
+    pub fn load_document(file_path: &str) -> Result<Document> {
+        Self::validate_pdf(file_path)?;
+        
+        Document::load(file_path).map_err(|e| anyhow!("Failed to parse PDF {}: {}", file_path, e))
+    }
+    
+    /// Extract the plain text of every page, in page order.
+    /// Pages whose content can't be decoded (scans, unsupported fonts) yield an empty string.
+    pub fn page_texts(pdf: &Document) -> Vec<String> {
+        pdf.get_pages()
+            .keys()
+            .map(|&page_number| match pdf.extract_text(&[page_number]) {
+                Ok(text) => normalize_page_text(&text),
+                Err(e) => {
+                    tracing::debug!("No text extracted from page {}: {}", page_number, e);
+                    String::new()
+                }
+            })
+            .collect()
+    }
+    
+    /// Extract the document outline as a flat list in reading order.
+    /// Nesting is expressed through `OutlineItem::level`; documents without bookmarks yield an empty list.
+    pub fn extract_outline(file_path: &str) -> Result<Vec<OutlineItem>> {
+        Ok(Self::outline(&Self::load_document(file_path)?))
+    }
+    
+    /// Outline of a parsed PDF, as in `extract_outline`
+    pub fn outline(pdf: &Document) -> Vec<OutlineItem> {
+        let Ok(outlines) = pdf
+            .catalog()
+            .and_then(|catalog| catalog.get_deref(b"Outlines", pdf))
+            .and_then(Object::as_dict)
+        else {
+            return vec![];
+        };
+        
+        let page_numbers: HashMap<ObjectId, u32> = pdf
+            .get_pages()
+            .into_iter()
+            .map(|(number, id)| (id, number))
+            .collect();
+        
+        let mut items = Vec::new();
+        let mut visited = HashSet::new();
+        collect_outline_items(pdf, outlines.get(b"First").ok(), 1, &page_numbers, &mut visited, &mut items);
+        
+        items
+    }
+    
+    /// Compute the content fingerprint of a file: the hex-encoded SHA-256 of its bytes.
+    /// The fingerprint identifies a document regardless of where it is stored.
+    pub fn compute_content_hash(file_path: &str) -> Result<String> {
+        let mut file = fs::File::open(file_path)
+            .map_err(|e| anyhow!("Cannot open {}: {}", file_path, e))?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+    
+    /// Recursively list the PDF files below a folder.
+    /// Unreadable entries are skipped rather than aborting the scan.
+    pub fn find_pdf_files(folder: &str) -> Vec<PathBuf> {
+        WalkDir::new(folder)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+            })
+            .map(|entry| entry.into_path())
+            .collect()
+    }
+    
     /// Create a PDF document record
     pub fn create_document(file_path: &str) -> Result<PDFDocument> {
         let metadata = Self::extract_metadata(file_path)?;
@@ -179,13 +374,344 @@ impl PDFHandler {
     }
 }
 
+/// Walk a chain of sibling outline items (and their children) starting at `first`
+fn collect_outline_items(
+    pdf: &Document,
+    first: Option<&Object>,
+    level: u32,
+    page_numbers: &HashMap<ObjectId, u32>,
+    visited: &mut HashSet<ObjectId>,
+    items: &mut Vec<OutlineItem>,
+) {
+    if level > MAX_OUTLINE_DEPTH {
+        return;
+    }
+    
+    let mut next = first.and_then(|o| o.as_reference().ok());
+    // Malformed files can link outline items in a cycle
+    while let Some(id) = next.filter(|id| visited.insert(*id)) {
+        let Ok(item) = pdf.get_object(id).and_then(Object::as_dict) else {
+            break;
+        };
+        
+        let title = item
+            .get(b"Title")
+            .ok()
+            .and_then(|t| decode_text_string(t).ok())
+            .and_then(|t| clean_text(&t))
+            .unwrap_or_else(|| "Untitled".to_string());
+        items.push(OutlineItem {
+            title,
+            level,
+            page_number: resolve_outline_target(pdf, item, page_numbers),
+        });
+        
+        collect_outline_items(pdf, item.get(b"First").ok(), level + 1, page_numbers, visited, items);
+        next = item.get(b"Next").ok().and_then(|o| o.as_reference().ok());
+    }
+}
+
+/// Find the page an outline item points to, through either `/Dest` or a `/GoTo` action
+fn resolve_outline_target(
+    pdf: &Document,
+    item: &Dictionary,
+    page_numbers: &HashMap<ObjectId, u32>,
+) -> Option<u32> {
+    let destination = match item.get(b"Dest") {
+        Ok(dest) => dest,
+        Err(_) => {
+            let action = item.get_deref(b"A", pdf).and_then(Object::as_dict).ok()?;
+            if action.get(b"S").and_then(Object::as_name).ok()? != b"GoTo" {
+                return None;
+            }
+            action.get(b"D").ok()?
+        }
+    };
+    
+    resolve_destination(pdf, destination, page_numbers, 0)
+}
+
+/// Resolve an explicit destination array, or a named destination, to a page number
+fn resolve_destination(
+    pdf: &Document,
+    destination: &Object,
+    page_numbers: &HashMap<ObjectId, u32>,
+    depth: u32,
+) -> Option<u32> {
+    if depth > MAX_OUTLINE_DEPTH {
+        return None;
+    }
+    
+    match destination {
+        Object::Reference(id) => resolve_destination(pdf, pdf.get_object(*id).ok()?, page_numbers, depth + 1),
+        Object::Array(parts) => match parts.first()? {
+            Object::Reference(page_id) => page_numbers.get(page_id).copied(),
+            // Zero-based page index, as used by some producers
+            Object::Integer(index) => u32::try_from(*index).ok().map(|i| i + 1),
+            _ => None,
+        },
+        Object::Dictionary(dict) => resolve_destination(pdf, dict.get(b"D").ok()?, page_numbers, depth + 1),
+        Object::Name(name) | Object::String(name, _) => {
+            let named = lookup_named_destination(pdf, name)?;
+            resolve_destination(pdf, &named, page_numbers, depth + 1)
+        }
+        _ => None,
+    }
+}
+
+/// Look a named destination up in the catalog `/Dests` dictionary or the `/Names` tree
+fn lookup_named_destination(pdf: &Document, name: &[u8]) -> Option<Object> {
+    let catalog = pdf.catalog().ok()?;
+    
+    if let Ok(dests) = catalog.get_deref(b"Dests", pdf).and_then(Object::as_dict) {
+        if let Ok(dest) = dests.get(name) {
+            return Some(dest.clone());
+        }
+    }
+    
+    let tree = catalog
+        .get_deref(b"Names", pdf)
+        .and_then(Object::as_dict)
+        .and_then(|names| names.get_deref(b"Dests", pdf))
+        .and_then(Object::as_dict)
+        .ok()?;
+    search_name_tree(pdf, tree, name, 0)
+}
+
+fn search_name_tree(pdf: &Document, node: &Dictionary, name: &[u8], depth: u32) -> Option<Object> {
+    if depth > MAX_OUTLINE_DEPTH {
+        return None;
+    }
+    
+    if let Ok(entries) = node.get_deref(b"Names", pdf).and_then(Object::as_array) {
+        for pair in entries.chunks(2) {
+            if let [key, value] = pair {
+                if key.as_str().is_ok_and(|k| k == name) {
+                    return Some(value.clone());
+                }
+            }
+        }
+    }
+    
+    let kids = node.get_deref(b"Kids", pdf).and_then(Object::as_array).ok()?;
+    kids.iter()
+        .filter_map(|kid| kid.as_reference().ok())
+        .filter_map(|id| pdf.get_object(id).and_then(Object::as_dict).ok())
+        .find_map(|kid| search_name_tree(pdf, kid, name, depth + 1))
+}
+
+/// Trim a metadata string and drop it if nothing useful is left
+fn clean_text(value: &str) -> Option<String> {
+    let trimmed = value.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+/// Split a keyword string on the separators authoring tools commonly use
+fn split_keywords(value: &str) -> Vec<String> {
+    value
+        .split([',', ';'])
+        .filter_map(clean_text)
+        .collect()
+}
+
+/// Collapse the whitespace lopdf leaves between text runs, keeping line breaks
+fn normalize_page_text(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+        // PostgreSQL TEXT columns reject NUL bytes
+        .replace('\0', "")
+}
+
+/// Convert a PDF date string (`D:YYYYMMDDHHmmSSOHH'mm'`) to RFC 3339
+fn parse_pdf_date(value: &str) -> Option<String> {
+    let value = value.trim();
+    let value = value.strip_prefix("D:").unwrap_or(value);
+    let digits: String = value.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.len() < 4 {
+        return None;
+    }
+
+    // Every field after the year is optional and defaults to its lowest value
+    let field = |start: usize, len: usize, default: u32| -> Option<u32> {
+        match digits.get(start..start + len) {
+            Some(part) => part.parse().ok(),
+            None => Some(default),
+        }
+    };
+    let year: i32 = digits[0..4].parse().ok()?;
+    let date = NaiveDate::from_ymd_opt(year, field(4, 2, 1)?, field(6, 2, 1)?)?;
+    let time = NaiveTime::from_hms_opt(field(8, 2, 0)?, field(10, 2, 0)?, field(12, 2, 0)?)?;
+
+    let rest = &value[digits.len()..];
+    let offset_seconds = match rest.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let offset_digits: String = rest[1..].chars().filter(|c| c.is_ascii_digit()).collect();
+            let hours: i32 = offset_digits.get(0..2).and_then(|h| h.parse().ok()).unwrap_or(0);
+            let minutes: i32 = offset_digits.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+            let seconds = hours * 3600 + minutes * 60;
+            if sign == '-' {
+                -seconds
+            } else {
+                seconds
+            }
+        }
+        _ => 0,
+    };
+
+    let offset = FixedOffset::east_opt(offset_seconds)?;
+    let date_time = offset
+        .from_local_datetime(&NaiveDateTime::new(date, time))
+        .single()?;
+    Some(date_time.to_rfc3339())
+}
+
+/// Normalize an XMP (ISO 8601) date to RFC 3339
+fn parse_xmp_date(value: &str) -> Option<String> {
+    let value = value.trim();
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Some(date_time.to_rfc3339());
+    }
+    // XMP allows dropping the seconds or the whole time component
+    if let Ok(date_time) = DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M%:z") {
+        return Some(date_time.to_rfc3339());
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S") {
+        return Some(naive.and_utc().to_rfc3339());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|naive| naive.and_utc().to_rfc3339())
+}
+
+/// Collect the values of an XMP property, whether it is written as an attribute,
+/// a simple element or an `rdf:Alt`/`rdf:Seq`/`rdf:Bag` container
+fn xmp_values(xmp: &str, property: &str) -> Vec<String> {
+    let mut values = Vec::new();
+
+    // Attribute form: <rdf:Description pdf:Producer="...">
+    let attribute = format!("{}=\"", property);
+    if let Some(start) = xmp.find(&attribute) {
+        let rest = &xmp[start + attribute.len()..];
+        if let Some(end) = rest.find('"') {
+            values.extend(clean_text(&decode_xml_entities(&rest[..end])));
+        }
+        return values;
+    }
+
+    // Element form: <dc:title>...</dc:title>
+    let open = format!("<{}", property);
+    let close = format!("</{}>", property);
+    let Some(start) = xmp.find(&open) else {
+        return values;
+    };
+    let Some(body_start) = xmp[start..].find('>').map(|i| start + i + 1) else {
+        return values;
+    };
+    let Some(body_end) = xmp[body_start..].find(&close).map(|i| body_start + i) else {
+        return values;
+    };
+    let body = &xmp[body_start..body_end];
+
+    if body.contains("<rdf:li") {
+        let mut rest = body;
+        while let Some(li_start) = rest.find("<rdf:li") {
+            let Some(text_start) = rest[li_start..].find('>').map(|i| li_start + i + 1) else {
+                break;
+            };
+            let Some(text_end) = rest[text_start..].find("</rdf:li>").map(|i| text_start + i)
+            else {
+                break;
+            };
+            values.extend(clean_text(&decode_xml_entities(&rest[text_start..text_end])));
+            rest = &rest[text_end..];
+        }
+    } else {
+        values.extend(clean_text(&decode_xml_entities(body)));
+    }
+
+    values
+}
+
+/// Decode the predefined XML entities used in XMP text
+fn decode_xml_entities(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs::File;
+    use lopdf::{dictionary, Stream};
     use std::io::Write;
     use tempfile::tempdir;
     
+    /// Write a minimal PDF with the given number of blank pages and Info dictionary
+    fn write_test_pdf(path: &Path, page_count: usize, info: Option<Dictionary>) {
+        let texts = vec![""; page_count];
+        write_test_pdf_with_text(path, &texts, info);
+    }
+    
+    /// Write a minimal PDF with one page per entry in `texts`
+    fn write_test_pdf_with_text(path: &Path, texts: &[&str], info: Option<Dictionary>) {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+        let kids: Vec<Object> = texts
+            .iter()
+            .map(|text| {
+                let content = format!("BT /F1 12 Tf 72 720 Td ({}) Tj ET", text);
+                let content_id = doc.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                    "Resources" => resources_id,
+                    "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+                })
+                .into()
+            })
+            .collect();
+        let page_count = texts.len();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => page_count as i64,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        if let Some(info) = info {
+            let info_id = doc.add_object(info);
+            doc.trailer.set("Info", info_id);
+        }
+        doc.save(path).unwrap();
+    }
+    
     #[test]
     fn test_pdf_validation() {
         let dir = tempdir().unwrap();
@@ -211,4 +737,189 @@ mod tests {
         let result = PDFHandler::validate_pdf(file_path.to_str().unwrap());
         assert!(result.is_err());
     }
-} 
\ No newline at end of file
+    
+    #[test]
+    fn test_extract_metadata_from_info_dictionary() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("paper.pdf");
+        write_test_pdf(
+            &file_path,
+            3,
+            Some(dictionary! {
+                "Title" => Object::string_literal("Attention Is All You Need"),
+                "Author" => Object::string_literal("Vaswani et al."),
+                "Keywords" => Object::string_literal("transformers; attention, NLP"),
+                "Producer" => Object::string_literal("pdfTeX-1.40.17"),
+                "CreationDate" => Object::string_literal("D:20170612180000+02'00'"),
+            }),
+        );
+        
+        let metadata = PDFHandler::extract_metadata(file_path.to_str().unwrap()).unwrap();
+        assert_eq!(metadata.page_count, 3);
+        assert_eq!(metadata.title.as_deref(), Some("Attention Is All You Need"));
+        assert_eq!(metadata.author.as_deref(), Some("Vaswani et al."));
+        assert_eq!(
+            metadata.keywords,
+            Some(vec!["transformers".to_string(), "attention".to_string(), "NLP".to_string()])
+        );
+        assert_eq!(metadata.producer.as_deref(), Some("pdfTeX-1.40.17"));
+        assert_eq!(metadata.creation_date.as_deref(), Some("2017-06-12T18:00:00+02:00"));
+    }
+    
+    #[test]
+    fn test_extract_metadata_falls_back_to_file_name() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("untitled-notes.pdf");
+        write_test_pdf(&file_path, 2, None);
+        
+        let metadata = PDFHandler::extract_metadata(file_path.to_str().unwrap()).unwrap();
+        assert_eq!(metadata.page_count, 2);
+        assert_eq!(metadata.title.as_deref(), Some("untitled-notes"));
+        assert!(metadata.author.is_none());
+    }
+    
+    #[test]
+    fn test_parse_pdf_date() {
+        assert_eq!(
+            parse_pdf_date("D:20240131093000Z").as_deref(),
+            Some("2024-01-31T09:30:00+00:00")
+        );
+        assert_eq!(
+            parse_pdf_date("D:20240131093000-05'30'").as_deref(),
+            Some("2024-01-31T09:30:00-05:30")
+        );
+        assert_eq!(parse_pdf_date("D:2024").as_deref(), Some("2024-01-01T00:00:00+00:00"));
+        assert!(parse_pdf_date("yesterday").is_none());
+    }
+    
+    #[test]
+    fn test_xmp_metadata_fills_missing_fields() {
+        let xmp = r#"<x:xmpmeta><rdf:RDF>
+            <rdf:Description pdf:Producer="LaTeX &amp; friends" xmp:CreateDate="2021-03-04T05:06:07Z">
+              <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Deep Learning</rdf:li></rdf:Alt></dc:title>
+              <dc:creator><rdf:Seq><rdf:li>Ian Goodfellow</rdf:li><rdf:li>Yoshua Bengio</rdf:li></rdf:Seq></dc:creator>
+              <dc:subject><rdf:Bag><rdf:li>neural networks</rdf:li></rdf:Bag></dc:subject>
+            </rdf:Description>
+        </rdf:RDF></x:xmpmeta>"#;
+        
+        let mut metadata = PDFMetadata {
+            title: Some("Info Title".to_string()),
+            author: None,
+            subject: None,
+            keywords: None,
+            creation_date: None,
+            modification_date: None,
+            producer: None,
+            page_count: 1,
+            file_size: 1,
+        };
+        PDFHandler::apply_xmp_metadata(&mut metadata, xmp);
+        
+        assert_eq!(metadata.title.as_deref(), Some("Info Title"));
+        assert_eq!(metadata.author.as_deref(), Some("Ian Goodfellow, Yoshua Bengio"));
+        assert_eq!(metadata.keywords, Some(vec!["neural networks".to_string()]));
+        assert_eq!(metadata.producer.as_deref(), Some("LaTeX & friends"));
+        assert_eq!(metadata.creation_date.as_deref(), Some("2021-03-04T05:06:07+00:00"));
+    }
+    
+    #[test]
+    fn test_normalize_page_text() {
+        let text = "  Gradient   descent\n\n\tupdates  the\0 weights \n";
+        assert_eq!(normalize_page_text(text), "Gradient descent\nupdates the weights");
+    }
+    
+    #[test]
+    fn test_page_texts() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("lecture.pdf");
+        write_test_pdf_with_text(&file_path, &["Backpropagation", "", "Chain rule"], None);
+        
+        let pdf = PDFHandler::load_document(file_path.to_str().unwrap()).unwrap();
+        let pages = PDFHandler::page_texts(&pdf);
+        assert_eq!(pages.len(), 3);
+        assert!(pages[0].contains("Backpropagation"));
+        assert!(pages[1].is_empty());
+        assert!(pages[2].contains("Chain rule"));
+    }
+    
+    #[test]
+    fn test_compute_content_hash() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("hello.pdf");
+        fs::write(&file_path, b"hello world").unwrap();
+        
+        assert_eq!(
+            PDFHandler::compute_content_hash(file_path.to_str().unwrap()).unwrap(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+    }
+    
+    #[test]
+    fn test_find_pdf_files() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        fs::write(dir.path().join("a.pdf"), b"").unwrap();
+        fs::write(dir.path().join("nested").join("B.PDF"), b"").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"").unwrap();
+        
+        let mut files = PDFHandler::find_pdf_files(dir.path().to_str().unwrap());
+        files.sort();
+        assert_eq!(files, vec![dir.path().join("a.pdf"), dir.path().join("nested").join("B.PDF")]);
+    }
+    
+    #[test]
+    fn test_extract_outline() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("book.pdf");
+        write_test_pdf(&file_path, 3, None);
+        
+        // Chapter 1 -> page 1 (explicit destination)
+        //   Section 1.1 -> page 2 (GoTo action)
+        // Chapter 2 -> page 3 (named destination in the /Names tree)
+        let mut doc = Document::load(&file_path).unwrap();
+        let pages: Vec<ObjectId> = doc.get_pages().values().copied().collect();
+        let outlines_id = doc.new_object_id();
+        let chapter1_id = doc.new_object_id();
+        let section_id = doc.new_object_id();
+        let chapter2_id = doc.new_object_id();
+        doc.objects.insert(section_id, Object::Dictionary(dictionary! {
+            "Title" => Object::string_literal("Section 1.1"),
+            "Parent" => chapter1_id,
+            "A" => dictionary! {
+                "S" => "GoTo",
+                "D" => vec![pages[1].into(), "Fit".into()],
+            },
+        }));
+        doc.objects.insert(chapter1_id, Object::Dictionary(dictionary! {
+            "Title" => Object::string_literal("Chapter 1"),
+            "Parent" => outlines_id,
+            "Dest" => vec![pages[0].into(), "Fit".into()],
+            "First" => section_id,
+            "Last" => section_id,
+            "Next" => chapter2_id,
+        }));
+        doc.objects.insert(chapter2_id, Object::Dictionary(dictionary! {
+            "Title" => Object::string_literal("Chapter 2"),
+            "Parent" => outlines_id,
+            "Dest" => Object::string_literal("ch2"),
+            "Prev" => chapter1_id,
+        }));
+        doc.objects.insert(outlines_id, Object::Dictionary(dictionary! {
+            "Type" => "Outlines",
+            "First" => chapter1_id,
+            "Last" => chapter2_id,
+        }));
+        let catalog = doc.catalog_mut().unwrap();
+        catalog.set("Outlines", outlines_id);
+        catalog.set("Names", dictionary! {
+            "Dests" => dictionary! {
+                "Names" => vec![Object::string_literal("ch2"), vec![pages[2].into(), "Fit".into()].into()],
+            },
+        });
+        doc.save(&file_path).unwrap();
+        
+        let outline = PDFHandler::extract_outline(file_path.to_str().unwrap()).unwrap();
+        let item = |title: &str, level, page| OutlineItem { title: title.to_string(), level, page_number: Some(page) };
+        assert_eq!(outline, vec![item("Chapter 1", 1, 1), item("Section 1.1", 2, 2), item("Chapter 2", 1, 3)]);
+    }
+}
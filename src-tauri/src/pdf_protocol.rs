@@ -0,0 +1,297 @@
+// Custom URI protocol that serves PDF bytes to PDF.js
+//
+// URLs have the form `geniuspdf://localhost/<document-id>` (or
+// `http://geniuspdf.localhost/<document-id>` on Windows). Only documents stored
+// in the `documents` table can be read, and HTTP Range requests are honoured so
+// PDF.js can fetch pages incrementally instead of loading the whole file.
+//
+// A protocol response is built in memory before it is returned, so no response
+// carries more than `MAX_RESPONSE_BYTES` of the file. A longer range is shortened
+// (its Content-Range says so), and a request without a Range header for a larger
+// file is answered as a range over the start of the file.
+use anyhow::{anyhow, Result};
+use std::io::SeekFrom;
+use tauri::http::{header, Method, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, Runtime, UriSchemeResponder};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use uuid::Uuid;
+
+use crate::state::DbState;
+
+/// Scheme name registered with the webview
+pub const PDF_PROTOCOL: &str = "geniuspdf";
+
+/// Most bytes of the file sent in one response
+pub const MAX_RESPONSE_BYTES: u64 = 1024 * 1024;
+
+/// Portion of the file a request asks for
+#[derive(Debug, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// No (usable) Range header: serve the whole file
+    Full,
+    /// Inclusive byte range
+    Partial { start: u64, end: u64 },
+    /// The range lies outside the file
+    Unsatisfiable,
+}
+
+/// Parse a `Range` header value against the size of the file.
+/// Only the first range of a multi-range request is served.
+pub fn parse_range(value: &str, file_size: u64) -> RangeRequest {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Full;
+    };
+    let first = spec.split(',').next().unwrap_or("").trim();
+    let Some((start, end)) = first.split_once('-') else {
+        return RangeRequest::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    if file_size == 0 {
+        return RangeRequest::Unsatisfiable;
+    }
+    let last = file_size - 1;
+
+    match (start.parse::<u64>().ok(), end.parse::<u64>().ok()) {
+        // bytes=-500: the final 500 bytes
+        (None, Some(suffix)) if start.is_empty() => {
+            if suffix == 0 {
+                RangeRequest::Unsatisfiable
+            } else {
+                RangeRequest::Partial { start: file_size.saturating_sub(suffix), end: last }
+            }
+        }
+        // bytes=100-
+        (Some(start), None) if end.is_empty() => {
+            if start > last {
+                RangeRequest::Unsatisfiable
+            } else {
+                RangeRequest::Partial { start, end: last }
+            }
+        }
+        // bytes=100-199
+        (Some(start), Some(end)) if start <= end => {
+            if start > last {
+                RangeRequest::Unsatisfiable
+            } else {
+                RangeRequest::Partial { start, end: end.min(last) }
+            }
+        }
+        // Syntactically invalid ranges are ignored, as HTTP requires
+        _ => RangeRequest::Full,
+    }
+}
+
+/// Entry point registered with `register_asynchronous_uri_scheme_protocol`
+pub fn handle_request<R: Runtime>(
+    app: &AppHandle<R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let response = match build_response(&app, &request).await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::error!("Failed to serve {}: {}", request.uri(), e);
+                text_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to read PDF")
+            }
+        };
+        responder.respond(response);
+    });
+}
+
+async fn build_response<R: Runtime>(
+    app: &AppHandle<R>,
+    request: &Request<Vec<u8>>,
+) -> Result<Response<Vec<u8>>> {
+    if request.method() == Method::OPTIONS {
+        return Ok(with_cors(Response::builder().status(StatusCode::NO_CONTENT)).body(Vec::new())?);
+    }
+
+    let Ok(document_id) = Uuid::parse_str(request.uri().path().trim_matches('/')) else {
+        return Ok(text_response(StatusCode::BAD_REQUEST, "Invalid document id"));
+    };
+
+    // Only files that belong to a known document may be served
+    let file_path = {
+        let db = app.state::<DbState>();
+        let db_guard = db.lock().await;
+        let Some(database) = db_guard.as_ref() else {
+            return Ok(text_response(StatusCode::SERVICE_UNAVAILABLE, "Database not initialized"));
+        };
+        match database.get_document(document_id).await? {
+            Some(document) => document.file_path,
+            None => return Ok(text_response(StatusCode::NOT_FOUND, "Document not found")),
+        }
+    };
+
+    let mut file = match tokio::fs::File::open(&file_path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(text_response(StatusCode::NOT_FOUND, "Document file is missing"));
+        }
+        Err(e) => return Err(anyhow!("Failed to open {}: {}", file_path, e)),
+    };
+    let file_size = file.metadata().await?.len();
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| parse_range(v, file_size))
+        .unwrap_or(RangeRequest::Full);
+
+    file_response(&mut file, file_size, range, request.method() == Method::HEAD).await
+}
+
+/// Serve `range` of an open file, at most `MAX_RESPONSE_BYTES` of it
+async fn file_response(
+    file: &mut tokio::fs::File,
+    file_size: u64,
+    range: RangeRequest,
+    head_only: bool,
+) -> Result<Response<Vec<u8>>> {
+    // A file too large for one response is sent as a range even when all of it was asked for
+    let range = match range {
+        RangeRequest::Full if file_size > MAX_RESPONSE_BYTES => {
+            RangeRequest::Partial { start: 0, end: file_size - 1 }
+        }
+        range => range,
+    };
+
+    let builder = with_cors(Response::builder())
+        .header(header::CONTENT_TYPE, "application/pdf")
+        .header(header::ACCEPT_RANGES, "bytes");
+
+    let (builder, start, length) = match range {
+        RangeRequest::Full => (
+            builder.status(StatusCode::OK).header(header::CONTENT_LENGTH, file_size),
+            0,
+            file_size,
+        ),
+        RangeRequest::Partial { start, end } => {
+            let end = capped_end(start, end);
+            (
+                builder
+                    .status(StatusCode::PARTIAL_CONTENT)
+                    .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, file_size))
+                    .header(header::CONTENT_LENGTH, end - start + 1),
+                start,
+                end - start + 1,
+            )
+        }
+        RangeRequest::Unsatisfiable => {
+            return Ok(builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", file_size))
+                .body(Vec::new())?);
+        }
+    };
+
+    if head_only {
+        return Ok(builder.body(Vec::new())?);
+    }
+
+    let mut body = vec![0u8; length as usize];
+    file.seek(SeekFrom::Start(start)).await?;
+    file.read_exact(&mut body).await?;
+
+    Ok(builder.body(body)?)
+}
+
+/// The last byte served for an inclusive range starting at `start`
+fn capped_end(start: u64, end: u64) -> u64 {
+    end.min(start + MAX_RESPONSE_BYTES - 1)
+}
+
+/// The protocol origin differs from the app origin, so PDF.js needs CORS headers
+fn with_cors(builder: tauri::http::response::Builder) -> tauri::http::response::Builder {
+    builder
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .header(header::ACCESS_CONTROL_ALLOW_METHODS, "GET, HEAD, OPTIONS")
+        .header(header::ACCESS_CONTROL_ALLOW_HEADERS, "Range")
+        .header(
+            header::ACCESS_CONTROL_EXPOSE_HEADERS,
+            "Accept-Ranges, Content-Length, Content-Range",
+        )
+}
+
+fn text_response(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    with_cors(Response::builder())
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(message.as_bytes().to_vec())
+        .expect("static response parts are valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bounded_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), RangeRequest::Partial { start: 0, end: 99 });
+        assert_eq!(parse_range("bytes=900-2000", 1000), RangeRequest::Partial { start: 900, end: 999 });
+        assert_eq!(
+            parse_range("bytes=0-9, 20-29", 1000),
+            RangeRequest::Partial { start: 0, end: 9 }
+        );
+    }
+
+    #[test]
+    fn test_parse_open_and_suffix_ranges() {
+        assert_eq!(parse_range("bytes=500-", 1000), RangeRequest::Partial { start: 500, end: 999 });
+        assert_eq!(parse_range("bytes=-100", 1000), RangeRequest::Partial { start: 900, end: 999 });
+        assert_eq!(parse_range("bytes=-5000", 1000), RangeRequest::Partial { start: 0, end: 999 });
+    }
+
+    #[test]
+    fn test_parse_invalid_ranges() {
+        assert_eq!(parse_range("bytes=1000-", 1000), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 1000), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range("bytes=50-10", 1000), RangeRequest::Full);
+        assert_eq!(parse_range("pages=1-2", 1000), RangeRequest::Full);
+    }
+
+    #[test]
+    fn test_long_ranges_are_capped() {
+        assert_eq!(capped_end(0, 99), 99);
+        assert_eq!(capped_end(0, 100 * MAX_RESPONSE_BYTES), MAX_RESPONSE_BYTES - 1);
+        assert_eq!(capped_end(500, 500 + MAX_RESPONSE_BYTES), 500 + MAX_RESPONSE_BYTES - 1);
+    }
+
+    /// Serve `range` of a temporary file holding `size` bytes
+    async fn serve(size: u64, range: RangeRequest) -> Response<Vec<u8>> {
+        let mut temp = tempfile::tempfile().unwrap();
+        std::io::Write::write_all(&mut temp, &vec![7u8; size as usize]).unwrap();
+        let mut file = tokio::fs::File::from_std(temp);
+        file_response(&mut file, size, range, false).await.unwrap()
+    }
+
+    fn content_length(response: &Response<Vec<u8>>) -> usize {
+        response.headers()[header::CONTENT_LENGTH].to_str().unwrap().parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_content_length_matches_body() {
+        let small = serve(1000, RangeRequest::Full).await;
+        assert_eq!(small.status(), StatusCode::OK);
+        assert_eq!(content_length(&small), 1000);
+        assert_eq!(small.body().len(), 1000);
+
+        let size = 3 * MAX_RESPONSE_BYTES + 10;
+        let large = serve(size, RangeRequest::Full).await;
+        assert_eq!(large.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(content_length(&large), large.body().len());
+        assert_eq!(large.body().len() as u64, MAX_RESPONSE_BYTES);
+        assert_eq!(
+            large.headers()[header::CONTENT_RANGE],
+            format!("bytes 0-{}/{}", MAX_RESPONSE_BYTES - 1, size).as_str()
+        );
+
+        let ranged = serve(size, RangeRequest::Partial { start: 10, end: size - 1 }).await;
+        assert_eq!(content_length(&ranged), ranged.body().len());
+        assert_eq!(ranged.body().len() as u64, MAX_RESPONSE_BYTES);
+    }
+}
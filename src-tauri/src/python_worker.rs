@@ -0,0 +1,359 @@
+// Supervised Python child process for the concept pipeline. The worker script
+// (`python/worker.py`) answers JSON-RPC 2.0 requests, one per line, on stdin/stdout.
+// A crashed worker is started again on the next call, a call that runs past its
+// timeout kills the worker, and `health_check` pings it between calls.
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::time::{Duration, Instant};
+
+/// How long the worker may take to answer a ping, including importing the pipeline at startup
+pub const PING_TIMEOUT: Duration = Duration::from_secs(60);
+/// Starts allowed within `RESTART_WINDOW` before the worker is considered broken
+const MAX_STARTS: usize = 3;
+const RESTART_WINDOW: Duration = Duration::from_secs(60);
+/// Lines of the worker's stderr kept to explain why it exited
+const STDERR_TAIL_LINES: usize = 20;
+
+/// A running worker process
+struct WorkerProcess {
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines the worker wrote to stdout; disconnected once it exits
+    responses: Receiver<String>,
+    stderr_tail: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl WorkerProcess {
+    /// The end of the worker's stderr, for error messages
+    fn stderr_tail(&self) -> String {
+        let tail = self.stderr_tail.lock().unwrap();
+        tail.iter().cloned().collect::<Vec<_>>().join("\n")
+    }
+}
+
+impl Drop for WorkerProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Default)]
+struct WorkerState {
+    process: Option<WorkerProcess>,
+    /// When the worker was last started, most recent last
+    starts: VecDeque<Instant>,
+}
+
+/// Runs `worker.py` with a Python interpreter and restarts it when it crashes or hangs.
+/// Calls are answered one at a time.
+pub struct PythonWorker {
+    python: PathBuf,
+    script: PathBuf,
+    state: Mutex<WorkerState>,
+    next_id: AtomicU64,
+    restarts: AtomicU32,
+}
+
+impl PythonWorker {
+    pub fn new(python: PathBuf, script: PathBuf) -> Self {
+        Self {
+            python,
+            script,
+            state: Mutex::new(WorkerState::default()),
+            next_id: AtomicU64::new(1),
+            restarts: AtomicU32::new(0),
+        }
+    }
+
+    /// How many times the worker was started again after crashing or hanging
+    pub fn restarts(&self) -> u32 {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    fn spawn(&self, state: &mut WorkerState) -> Result<()> {
+        let now = Instant::now();
+        while state.starts.front().is_some_and(|started| now.duration_since(*started) > RESTART_WINDOW) {
+            state.starts.pop_front();
+        }
+        if state.starts.len() >= MAX_STARTS {
+            return Err(anyhow!(
+                "Python worker exited {} times within {}s; not starting it again yet",
+                MAX_STARTS,
+                RESTART_WINDOW.as_secs()
+            ));
+        }
+        if !state.starts.is_empty() {
+            self.restarts.fetch_add(1, Ordering::Relaxed);
+        }
+        state.starts.push_back(now);
+
+        let mut child = Command::new(&self.python)
+            .arg("-u")
+            .arg(&self.script)
+            .current_dir(self.script.parent().unwrap_or(&self.script))
+            .env("PYTHONUNBUFFERED", "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow!("Failed to start Python worker with {}: {}", self.python.display(), e))?;
+        tracing::info!("Started Python worker {} ({})", child.id(), self.script.display());
+
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("Python worker has no stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("Python worker has no stdout"))?;
+        let stderr = child.stderr.take().ok_or_else(|| anyhow!("Python worker has no stderr"))?;
+
+        let (sender, responses) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let stderr_tail = Arc::new(Mutex::new(VecDeque::new()));
+        let tail = stderr_tail.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                tracing::info!(target: "python_worker", "{}", line);
+                let mut tail = tail.lock().unwrap();
+                if tail.len() == STDERR_TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(line);
+            }
+        });
+
+        state.process = Some(WorkerProcess { child, stdin, responses, stderr_tail });
+        Ok(())
+    }
+
+    /// Start the worker if it isn't running and check that it answers
+    pub fn start(&self) -> Result<Value> {
+        self.call("ping", Value::Null, PING_TIMEOUT)
+    }
+
+    /// Call a worker method and wait up to `timeout` for its result. A worker that isn't
+    /// running is started first; one that exits or doesn't answer in time is stopped
+    /// and started again by the next call.
+    pub fn call(&self, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+        let mut state = self.state.lock().unwrap();
+        self.call_locked(&mut state, method, params, timeout)
+    }
+
+    fn call_locked(&self, state: &mut WorkerState, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+        let exited = match state.process.as_mut() {
+            Some(process) => process.child.try_wait()?.is_some(),
+            None => true,
+        };
+        if exited {
+            state.process = None;
+            self.spawn(state)?;
+        }
+        let process = state.process.as_mut().expect("worker was just started");
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let sent = writeln!(process.stdin, "{}", request).and_then(|_| process.stdin.flush());
+        if let Err(e) = sent {
+            let stderr = process.stderr_tail();
+            state.process = None;
+            return Err(anyhow!("Failed to send {} to the Python worker: {}\n{}", method, e, stderr));
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let line = match process.responses.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    state.process = None;
+                    return Err(anyhow!(
+                        "Python worker didn't finish {} within {}s and was stopped",
+                        method,
+                        timeout.as_secs()
+                    ));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    let status = process.child.wait().map(|s| s.to_string()).unwrap_or_default();
+                    let stderr = process.stderr_tail();
+                    state.process = None;
+                    return Err(anyhow!("Python worker exited during {} ({})\n{}", method, status, stderr));
+                }
+            };
+
+            let response: Value = match serde_json::from_str(&line) {
+                Ok(response) => response,
+                Err(_) => {
+                    tracing::warn!(target: "python_worker", "Ignoring non-protocol output: {}", line);
+                    continue;
+                }
+            };
+            // Answers to earlier calls that timed out can't arrive, since those workers were stopped
+            if response.get("id").and_then(Value::as_u64) != Some(id) {
+                tracing::warn!(target: "python_worker", "Ignoring response to another request: {}", line);
+                continue;
+            }
+            return rpc_result(response);
+        }
+    }
+
+    /// Ping the worker unless a call is running. A worker that doesn't answer is
+    /// started again. Returns `None` when it was busy.
+    pub fn health_check(&self) -> Result<Option<Value>> {
+        let mut state = match self.state.try_lock() {
+            Ok(state) => state,
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        };
+        match self.call_locked(&mut state, "ping", Value::Null, PING_TIMEOUT) {
+            Ok(pong) => Ok(Some(pong)),
+            // The worker was stopped, so start it now rather than on the next call
+            Err(e) if state.process.is_none() => {
+                tracing::warn!("Python worker failed its health check: {}", e);
+                self.call_locked(&mut state, "ping", Value::Null, PING_TIMEOUT).map(Some)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// The result of a JSON-RPC response, or its error as an `Err`
+fn rpc_result(mut response: Value) -> Result<Value> {
+    if let Some(error) = response.get("error") {
+        let message = error.get("message").and_then(Value::as_str).unwrap_or("Unknown error");
+        return Err(match error.get("code").and_then(Value::as_i64) {
+            Some(code) => anyhow!("Python worker error {}: {}", code, message),
+            None => anyhow!("Python worker error: {}", message),
+        });
+    }
+    response
+        .get_mut("result")
+        .map(Value::take)
+        .ok_or_else(|| anyhow!("Python worker response has neither result nor error"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::{tempdir, TempDir};
+
+    /// A worker that answers `ping` with its pid and misbehaves on request
+    const STUB_WORKER: &str = r#"
+import json, os, sys, time
+
+for line in sys.stdin:
+    request = json.loads(line)
+    method = request["method"]
+    response = {"jsonrpc": "2.0", "id": request["id"]}
+    if method == "ping":
+        response["result"] = {"pid": os.getpid()}
+    elif method == "exit":
+        sys.exit(3)
+    elif method == "sleep":
+        time.sleep(30)
+    elif method == "noisy":
+        print("Loading model...", flush=True)
+        response["result"] = "done"
+    else:
+        response["error"] = {"code": -32601, "message": "Method not found: " + method}
+    print(json.dumps(response), flush=True)
+"#;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn stub_worker(source: &str) -> (TempDir, PythonWorker) {
+        let dir = tempdir().unwrap();
+        let script = dir.path().join("worker.py");
+        std::fs::write(&script, source).unwrap();
+        (dir, PythonWorker::new(PathBuf::from("python3"), script))
+    }
+
+    fn pid(worker: &PythonWorker) -> u64 {
+        worker.call("ping", Value::Null, TIMEOUT).unwrap()["pid"].as_u64().unwrap()
+    }
+
+    #[test]
+    fn test_rpc_result() {
+        let ok = json!({ "jsonrpc": "2.0", "id": 1, "result": { "pid": 42 } });
+        assert_eq!(rpc_result(ok).unwrap(), json!({ "pid": 42 }));
+
+        let null = json!({ "jsonrpc": "2.0", "id": 1, "result": null });
+        assert_eq!(rpc_result(null).unwrap(), Value::Null);
+
+        let failed = json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32601, "message": "Method not found: nope" } });
+        assert_eq!(rpc_result(failed).unwrap_err().to_string(), "Python worker error -32601: Method not found: nope");
+
+        assert!(rpc_result(json!({ "jsonrpc": "2.0", "id": 1 })).is_err());
+    }
+
+    #[test]
+    fn test_worker_answers_calls() {
+        let (_dir, worker) = stub_worker(STUB_WORKER);
+        let first = pid(&worker);
+
+        let error = worker.call("missing", Value::Null, TIMEOUT).unwrap_err();
+        assert_eq!(error.to_string(), "Python worker error -32601: Method not found: missing");
+        assert_eq!(pid(&worker), first);
+        assert_eq!(worker.restarts(), 0);
+    }
+
+    #[test]
+    fn test_worker_skips_non_json_output() {
+        let (_dir, worker) = stub_worker(STUB_WORKER);
+        let first = pid(&worker);
+
+        assert_eq!(worker.call("noisy", Value::Null, TIMEOUT).unwrap(), json!("done"));
+        assert_eq!(pid(&worker), first);
+        assert_eq!(worker.restarts(), 0);
+    }
+
+    #[test]
+    fn test_exited_worker_is_restarted() {
+        let (_dir, worker) = stub_worker(STUB_WORKER);
+        let first = pid(&worker);
+
+        let error = worker.call("exit", Value::Null, TIMEOUT).unwrap_err();
+        assert!(error.to_string().starts_with("Python worker exited during exit ("), "{}", error);
+
+        assert_ne!(pid(&worker), first);
+        assert_eq!(worker.restarts(), 1);
+    }
+
+    #[test]
+    fn test_hung_worker_is_stopped_and_restarted() {
+        let (_dir, worker) = stub_worker(STUB_WORKER);
+        let first = pid(&worker);
+
+        let error = worker.call("sleep", Value::Null, Duration::from_secs(1)).unwrap_err();
+        assert_eq!(error.to_string(), "Python worker didn't finish sleep within 1s and was stopped");
+
+        assert_ne!(pid(&worker), first);
+        assert_eq!(worker.restarts(), 1);
+    }
+
+    #[test]
+    fn test_crashing_worker_is_not_restarted_forever() {
+        // Exits after reading its first request
+        let (_dir, worker) = stub_worker("import sys\nsys.stdin.readline()\nsys.exit(1)\n");
+
+        for restarts in 0..MAX_STARTS as u32 {
+            let error = worker.call("ping", Value::Null, TIMEOUT).unwrap_err();
+            assert!(error.to_string().starts_with("Python worker exited during ping ("), "{}", error);
+            assert_eq!(worker.restarts(), restarts);
+        }
+
+        let error = worker.call("ping", Value::Null, TIMEOUT).unwrap_err();
+        assert_eq!(error.to_string(), "Python worker exited 3 times within 60s; not starting it again yet");
+        assert_eq!(worker.restarts(), MAX_STARTS as u32 - 1);
+    }
+}
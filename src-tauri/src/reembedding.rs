@@ -0,0 +1,129 @@
+// Re-embedding: after the embedding model or dimension changes, every concept is
+// embedded again in the background. Vectors are staged per job so the work survives
+// restarts, and are swapped into `concepts` in a single transaction at the end.
+// Document pages carry no embeddings, so concepts are the only thing to re-embed.
+use anyhow::{anyhow, bail, Result};
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::database::{Database, EmbeddingJob};
+use crate::embeddings::{concept_embedding_text, provider_from_preferences};
+
+/// Emitted after every staged batch with the job's `EmbeddingJob`
+pub const REEMBEDDING_PROGRESS_EVENT: &str = "reembedding-progress";
+/// Emitted when a job completes, fails or is cancelled, with its final `EmbeddingJob`
+pub const REEMBEDDING_COMPLETE_EVENT: &str = "reembedding-complete";
+
+/// Concepts embedded per provider request
+const BATCH_SIZE: i64 = 32;
+/// How often the swap is retried when concepts were added while it waited
+const MAX_SWAP_ATTEMPTS: usize = 5;
+
+/// Only one task works on the running job at a time
+static RUN_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Get a job for the configured embedding model ready to run: the running job if it
+/// already targets that model, otherwise a resumed failed job or a fresh one.
+/// A running job for a different model is cancelled.
+pub async fn prepare_job(database: &Database) -> Result<EmbeddingJob> {
+    let provider = provider_from_preferences(database).await?;
+    let dimension = i32::try_from(provider.dimension())?;
+
+    if let Some(latest) = database.get_latest_embedding_job().await? {
+        let same_model = latest.model == provider.model() && latest.dimension == dimension;
+        match latest.status.as_str() {
+            "running" if same_model => return Ok(latest),
+            "running" => {
+                tracing::info!("Cancelling re-embedding job {} for {}", latest.id, latest.model);
+                database.cancel_embedding_job(latest.id).await?;
+            }
+            "failed" if same_model => return database.restart_embedding_job(latest.id).await,
+            _ => {}
+        }
+    }
+
+    database.create_embedding_job(provider.model(), dimension).await
+}
+
+/// Run a job in the background, reporting through the re-embedding events
+pub fn spawn_job<R: Runtime>(app: AppHandle<R>, database: Database, job_id: Uuid) {
+    tauri::async_runtime::spawn(async move {
+        let _running = RUN_LOCK.lock().await;
+
+        if let Err(e) = run_job(&app, &database, job_id).await {
+            tracing::error!("Re-embedding job {} failed: {}", job_id, e);
+            if let Err(e) = database.fail_embedding_job(job_id, &e.to_string()).await {
+                tracing::error!("Failed to record re-embedding failure: {}", e);
+            }
+        }
+
+        match database.get_embedding_job(job_id).await {
+            Ok(Some(job)) => {
+                let _ = app.emit(REEMBEDDING_COMPLETE_EVENT, &job);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::error!("Failed to load re-embedding job {}: {}", job_id, e),
+        }
+    });
+}
+
+/// Continue a job that was running when the app last stopped
+pub async fn resume_running_job<R: Runtime>(app: AppHandle<R>, database: Database) {
+    match database.get_latest_embedding_job().await {
+        Ok(Some(job)) if job.status == "running" => {
+            tracing::info!(
+                "Resuming re-embedding job {} ({}/{} concepts)",
+                job.id,
+                job.processed_items,
+                job.total_items
+            );
+            spawn_job(app, database, job.id);
+        }
+        Ok(_) => {}
+        Err(e) => tracing::error!("Failed to check for an unfinished re-embedding job: {}", e),
+    }
+}
+
+async fn run_job<R: Runtime>(app: &AppHandle<R>, database: &Database, job_id: Uuid) -> Result<()> {
+    let provider = provider_from_preferences(database).await?;
+    let mut swap_attempts = 0;
+
+    loop {
+        let job = database
+            .get_embedding_job(job_id)
+            .await?
+            .ok_or_else(|| anyhow!("Re-embedding job {} not found", job_id))?;
+        if job.status != "running" {
+            return Ok(());
+        }
+        if job.model != provider.model() || usize::try_from(job.dimension).ok() != Some(provider.dimension()) {
+            bail!("Embedding settings changed to {} while re-embedding for {}", provider.model(), job.model);
+        }
+
+        let batch = database.get_concepts_to_reembed(job_id, BATCH_SIZE).await?;
+        if batch.is_empty() {
+            if database.swap_staged_embeddings(job_id).await? {
+                tracing::info!("Re-embedded all concepts with {}", job.model);
+                database.rebuild_vector_index().await?;
+                return Ok(());
+            }
+            swap_attempts += 1;
+            if swap_attempts >= MAX_SWAP_ATTEMPTS {
+                bail!("Concepts kept changing while swapping in the new embeddings");
+            }
+            continue;
+        }
+
+        let texts: Vec<String> = batch
+            .iter()
+            .map(|(_, name, description)| concept_embedding_text(name, description))
+            .collect();
+        let embeddings = provider.embed(&texts).await?;
+        let entries: Vec<(Uuid, Vec<f32>)> = batch.into_iter().map(|(id, _, _)| id).zip(embeddings).collect();
+        database.stage_concept_embeddings(job_id, &entries).await?;
+
+        let job = database.refresh_embedding_job_progress(job_id).await?;
+        let _ = app.emit(REEMBEDDING_PROGRESS_EVENT, &job);
+    }
+}
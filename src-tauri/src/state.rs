@@ -3,9 +3,13 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use crate::database::Database;
 use crate::langraph_bridge::LangGraphBridge;
+use crate::library::LibraryWatcher;
 
 // Global database instance
 pub type DbState = Arc<Mutex<Option<Database>>>;
 
 // Global LangGraph bridge instance
-pub type LangGraphState = Arc<Mutex<Option<LangGraphBridge>>>; 
\ No newline at end of file
+pub type LangGraphState = Arc<Mutex<Option<LangGraphBridge>>>;
+
+// Watcher for the configured library roots
+pub type LibraryWatcherState = Arc<Mutex<Option<LibraryWatcher>>>; 
\ No newline at end of file
@@ -1,6 +1,6 @@
 import { useState, useEffect, useCallback } from 'react';
 import { Document, Page, pdfjs } from 'react-pdf';
-import { readPDFFile } from '@/lib/api';
+import { getPDFStreamUrl } from '@/lib/api';
 import { Button } from '@/components/ui/button';
 import { Input } from '@/components/ui/input';
 import { Badge } from '@/components/ui/badge';
@@ -25,6 +25,34 @@ pdfjs.GlobalWorkerOptions.workerSrc = new URL(
   import.meta.url,
 ).toString();
 
+type PDFSource = { range: InstanceType<typeof pdfjs.PDFDataRangeTransport> };
+
+// The geniuspdf protocol sends at most 1 MB per response, so the document is read
+// through Range requests only, each repeated until the whole range has arrived
+const createPDFSource = (url: string, length: number): PDFSource => {
+  const transport = new pdfjs.PDFDataRangeTransport(length, null);
+  transport.requestDataRange = async (begin: number, end: number) => {
+    let offset = begin;
+    try {
+      while (offset < end) {
+        const response = await fetch(url, { headers: { Range: `bytes=${offset}-${end - 1}` } });
+        if (!response.ok) {
+          throw new Error(`HTTP ${response.status}`);
+        }
+        const chunk = new Uint8Array(await response.arrayBuffer());
+        if (chunk.length === 0) {
+          break;
+        }
+        transport.onDataRange(offset, chunk);
+        offset += chunk.length;
+      }
+    } catch (err) {
+      console.error(`Failed to read PDF bytes ${offset}-${end - 1}:`, err);
+    }
+  };
+  return { range: transport };
+};
+
 interface PDFViewerProps {
   document?: DocumentType;
   onDocumentLoad?: (document: DocumentType) => void;
@@ -48,7 +76,7 @@ const PDFViewer: React.FC<PDFViewerProps> = ({
   const [loading, setLoading] = useState<boolean>(false);
   const [error, setError] = useState<string | null>(null);
   const [pageInput, setPageInput] = useState<string>('1');
-  const [pdfData, setPdfData] = useState<string | null>(null);
+  const [pdfData, setPdfData] = useState<PDFSource | null>(null);
   
   const { toast } = useToast();
   
@@ -79,30 +107,14 @@ const PDFViewer: React.FC<PDFViewerProps> = ({
     }
   }, [document]);
 
-  // Load PDF data when document changes
+  // Point PDF.js at the streaming protocol when document changes
   useEffect(() => {
-    if (document?.filePath) {
+    if (document?.id) {
       setLoading(true);
       setError(null);
-      setPdfData(null);
-      
-      readPDFFile(document.filePath)
-        .then((data) => {
-          setPdfData(data);
-          setLoading(false);
-        })
-        .catch((error) => {
-          console.error('Failed to load PDF data:', error);
-          setError('Failed to load PDF file');
-          setLoading(false);
-          toast({
-            title: "Failed to Load PDF",
-            description: "Could not read the PDF file. Please check file permissions.",
-            variant: "destructive",
-          });
-        });
+      setPdfData(createPDFSource(getPDFStreamUrl(document.id), document.fileSize));
     }
-  }, [document?.filePath, toast]);
+  }, [document?.id]);
 
   // Handle successful PDF load
   const onDocumentLoadSuccess = useCallback(({ numPages }: { numPages: number }) => {
@@ -1,5 +1,6 @@
 import { useState } from "react";
-import type { ChatMessage, HighlightedContext } from "@/lib/types";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { AnalysisJob, ChatMessage, HighlightedContext } from "@/lib/types";
 import { useToast } from "@/hooks/use-toast";
 import { 
   addChatMessage, 
@@ -9,6 +10,14 @@ import {
   analyzeChatSession
 } from "@/lib/api";
 
+// What the analysis progress shows at each stage of a job
+const ANALYSIS_STAGES: Record<AnalysisJob['processingStages'][number]['stage'], { label: string; progress: number }> = {
+  loading: { label: "Processing conversation data...", progress: 15 },
+  extracting: { label: "Extracting key concepts...", progress: 30 },
+  embedding: { label: "Comparing with your knowledge...", progress: 70 },
+  storing: { label: "Finalizing results...", progress: 90 }
+};
+
 interface UseChatActionsProps {
   currentChatSessionId: string | null;
   messages: ChatMessage[];
@@ -167,62 +176,62 @@ export const useChatActions = ({
   const handleAnalyze = async () => {
     if (!currentChatSessionId) return;
     
+    const unlisten: UnlistenFn[] = [];
     try {
       setIsAnalyzing(true);
       setAnalysisProgress(0);
-      setAnalysisStartTime(Date.now());
+      const startTime = Date.now();
+      setAnalysisStartTime(startTime);
       
-      // Stage 1: Initializing
-      setAnalysisStage("Initializing analysis...");
-      setAnalysisProgress(10);
+      setAnalysisStage("Queued for analysis...");
+      setAnalysisProgress(5);
       
       toast({
         title: "Analysis Starting",
         description: "Preparing your conversation for AI analysis...",
       });
 
-      // Simulate brief delay for initialization
-      await new Promise(resolve => setTimeout(resolve, 500));
-      
-      // Stage 2: Processing
-      setAnalysisStage("Processing conversation data...");
-      setAnalysisProgress(30);
-      
-      // Update toast with progress
-      toast({
-        title: "Processing Data",
-        description: "Analyzing conversation content and highlighted text...",
-      });
-
-      // Trigger LangGraph concept extraction
-      const result = await analyzeChatSession(currentChatSessionId);
-      
-      // Stage 3: Extracting concepts
-      setAnalysisStage("Extracting key concepts...");
-      setAnalysisProgress(60);
-      
-      toast({
-        title: "Extracting Concepts",
-        description: "AI is identifying important concepts from your conversation...",
-      });
+      // Analysis runs in the background; follow the job through its events.
+      // Listeners are in place before the job exists so no event is missed.
+      let jobId: string | null = null;
+      let resolveFinished: (job: AnalysisJob) => void = () => {};
+      const finishedJobs = new Map<string, AnalysisJob>();
+      unlisten.push(
+        await listen<AnalysisJob>('analysis-progress', ({ payload }) => {
+          if (payload.id !== jobId) return;
+          const stage = payload.processingStages[payload.processingStages.length - 1]?.stage;
+          if (stage) {
+            setAnalysisStage(ANALYSIS_STAGES[stage].label);
+            setAnalysisProgress(ANALYSIS_STAGES[stage].progress);
+          }
+        }),
+        await listen<AnalysisJob>('analysis-complete', ({ payload }) => {
+          finishedJobs.set(payload.id, payload);
+          if (payload.id === jobId) resolveFinished(payload);
+        })
+      );
 
-      // Simulate processing time for concept extraction
-      await new Promise(resolve => setTimeout(resolve, 800));
-      
-      // Stage 4: Finalizing
-      setAnalysisStage("Finalizing results...");
-      setAnalysisProgress(90);
+      const job = await analyzeChatSession(currentChatSessionId);
+      jobId = job.id;
+      const result = finishedJobs.get(job.id)
+        ?? await new Promise<AnalysisJob>(resolve => { resolveFinished = resolve; });
       
-      if (result.success) {
-        // Stage 5: Complete
+      if (result.status === 'complete') {
         setAnalysisStage("Analysis complete!");
         setAnalysisProgress(100);
         
-        const processingTime = analysisStartTime ? Date.now() - analysisStartTime : 0;
+        const processingTime = Date.now() - startTime;
+        const summary = !result.result
+          ? ''
+          : 'proposalsCreated' in result.result
+            ? ` (${result.result.proposalsCreated} waiting for review)`
+            : result.result.changes
+              ? ` (${result.result.changes.added.length} added, ${result.result.changes.updated.length} updated, ${result.result.changes.unlinked.length} unlinked)`
+              : '';
         
         toast({
           title: "Analysis Complete",
-          description: `Successfully extracted ${result.conceptsExtracted || 0} concepts in ${Math.round(processingTime / 1000)}s`,
+          description: `Successfully extracted ${result.extractedConceptCount} concepts${summary} in ${Math.round(processingTime / 1000)}s`,
         });
         
         // Navigate to knowledge tab after a brief delay
@@ -233,8 +242,11 @@ export const useChatActions = ({
           onAnalyze();
         }, 1000);
         
+      } else if (result.status === 'cancelled') {
+        setAnalysisStage("Analysis cancelled");
+        setAnalysisProgress(0);
       } else {
-        throw new Error(result.error || "Analysis failed");
+        throw new Error(result.errorMessage || "Analysis failed");
       }
     } catch (error) {
       console.error('Failed to analyze chat:', error);
@@ -247,6 +259,7 @@ export const useChatActions = ({
         variant: "destructive",
       });
     } finally {
+      unlisten.forEach(stop => stop());
       // Reset analysis state after completion or error
       setTimeout(() => {
         setIsAnalyzing(false);
@@ -1,12 +1,30 @@
 // Tauri API functions for GeniusReads
 // Handles communication between React frontend and Rust backend
 
-import { invoke } from '@tauri-apps/api/core';
+import { invoke, convertFileSrc } from '@tauri-apps/api/core';
 import type { 
   Document, 
   TauriResponse, 
+  AnalysisJob,
   AppError,
-  Concept
+  Concept,
+  ConceptGraph,
+  ConceptNeighbor,
+  ConceptProposal,
+  ConceptRelationshipType,
+  ConceptSearchResult,
+  ConceptSplitPart,
+  ConceptUpdate,
+  EmbeddingCacheStats,
+  EmbeddingJob,
+  EmbeddingProviderTest,
+  EmbeddingSettings,
+  Highlight,
+  InterruptedAnalysisAction,
+  NoteFilter,
+  SimilarConcept,
+  TextSelection,
+  UserNote
 } from './types';
 
 // ============================================================================
@@ -92,6 +110,12 @@ export const readPDFFile = async (filePath: string): Promise<string> => {
   }
 };
 
+// URL served by the `geniuspdf` protocol; supports HTTP Range requests so
+// PDF.js can load large documents incrementally
+export const getPDFStreamUrl = (documentId: string): string => {
+  return convertFileSrc(documentId, 'geniuspdf');
+};
+
 export const updateDocumentState = async (
   documentId: string,
   currentPage: number,
@@ -490,6 +514,176 @@ export const updateChatSessionTitle = async (chatSessionId: string, title: strin
   }
 };
 
+// ============================================================================
+// Highlights
+// ============================================================================
+
+const toHighlight = (raw: any): Highlight => ({
+  ...raw,
+  note: raw.note ?? undefined,
+  createdAt: new Date(raw.createdAt),
+  updatedAt: new Date(raw.updatedAt)
+});
+
+export const createHighlight = async (
+  selection: Omit<TextSelection, 'id' | 'createdAt'>,
+  color?: string,
+  note?: string
+): Promise<Highlight> => {
+  try {
+    const result = await invoke<any>('create_highlight', {
+      highlight: { ...selection, color, note }
+    });
+    return toHighlight(result);
+  } catch (error) {
+    console.error('Failed to create highlight:', error);
+    throw new Error(`Failed to create highlight: ${error}`);
+  }
+};
+
+export const getPageHighlights = async (documentId: string, pageNumber: number): Promise<Highlight[]> => {
+  try {
+    const result = await invoke<any[]>('get_page_highlights', { documentId, pageNumber });
+    return result.map(toHighlight);
+  } catch (error) {
+    console.error('Failed to get page highlights:', error);
+    throw new Error(`Failed to get page highlights: ${error}`);
+  }
+};
+
+export const getDocumentHighlights = async (documentId: string): Promise<Highlight[]> => {
+  try {
+    const result = await invoke<any[]>('get_document_highlights', { documentId });
+    return result.map(toHighlight);
+  } catch (error) {
+    console.error('Failed to get document highlights:', error);
+    throw new Error(`Failed to get document highlights: ${error}`);
+  }
+};
+
+// Omitted fields are left unchanged; an empty note removes it
+export const updateHighlight = async (
+  highlightId: string,
+  changes: { color?: string; note?: string }
+): Promise<Highlight> => {
+  try {
+    const result = await invoke<any>('update_highlight', { highlightId, ...changes });
+    return toHighlight(result);
+  } catch (error) {
+    console.error('Failed to update highlight:', error);
+    throw new Error(`Failed to update highlight: ${error}`);
+  }
+};
+
+export const deleteHighlight = async (highlightId: string): Promise<boolean> => {
+  try {
+    return await invoke<boolean>('delete_highlight', { highlightId });
+  } catch (error) {
+    console.error('Failed to delete highlight:', error);
+    throw new Error(`Failed to delete highlight: ${error}`);
+  }
+};
+
+// ============================================================================
+// Notes
+// ============================================================================
+
+const toUserNote = (raw: any): UserNote => ({
+  ...raw,
+  selectionId: raw.selectionId ?? undefined,
+  position: raw.position ?? undefined,
+  createdAt: new Date(raw.createdAt),
+  updatedAt: new Date(raw.updatedAt)
+});
+
+// Either pageNumber or selectionId must be given; the selection's page is used otherwise
+export const createNote = async (note: {
+  documentId: string;
+  noteText: string;
+  pageNumber?: number;
+  selectionId?: string;
+  noteType?: UserNote['noteType'];
+  position?: { x: number; y: number };
+  tags?: string[];
+}): Promise<UserNote> => {
+  try {
+    const result = await invoke<any>('create_note', { note });
+    return toUserNote(result);
+  } catch (error) {
+    console.error('Failed to create note:', error);
+    throw new Error(`Failed to create note: ${error}`);
+  }
+};
+
+export const getNote = async (noteId: string): Promise<UserNote | null> => {
+  try {
+    const result = await invoke<any | null>('get_note', { noteId });
+    return result ? toUserNote(result) : null;
+  } catch (error) {
+    console.error('Failed to get note:', error);
+    throw new Error(`Failed to get note: ${error}`);
+  }
+};
+
+// Omitted fields are left unchanged
+export const updateNote = async (
+  noteId: string,
+  changes: Partial<Pick<UserNote, 'noteText' | 'noteType' | 'tags' | 'position'>>
+): Promise<UserNote> => {
+  try {
+    const result = await invoke<any>('update_note', { noteId, changes });
+    return toUserNote(result);
+  } catch (error) {
+    console.error('Failed to update note:', error);
+    throw new Error(`Failed to update note: ${error}`);
+  }
+};
+
+export const deleteNote = async (noteId: string): Promise<boolean> => {
+  try {
+    return await invoke<boolean>('delete_note', { noteId });
+  } catch (error) {
+    console.error('Failed to delete note:', error);
+    throw new Error(`Failed to delete note: ${error}`);
+  }
+};
+
+export const listNotes = async (filter?: NoteFilter): Promise<UserNote[]> => {
+  try {
+    const result = await invoke<any[]>('list_notes', { filter });
+    return result.map(toUserNote);
+  } catch (error) {
+    console.error('Failed to list notes:', error);
+    throw new Error(`Failed to list notes: ${error}`);
+  }
+};
+
+export const searchNotes = async (
+  query: string,
+  documentId?: string,
+  maxResults?: number
+): Promise<UserNote[]> => {
+  try {
+    const result = await invoke<any[]>('search_notes', { query, documentId, maxResults });
+    return result.map(toUserNote);
+  } catch (error) {
+    console.error('Failed to search notes:', error);
+    throw new Error(`Failed to search notes: ${error}`);
+  }
+};
+
+export const exportNotes = async (
+  filter?: NoteFilter,
+  format: 'markdown' | 'json' = 'markdown'
+): Promise<string> => {
+  try {
+    return await invoke<string>('export_notes', { filter, format });
+  } catch (error) {
+    console.error('Failed to export notes:', error);
+    throw new Error(`Failed to export notes: ${error}`);
+  }
+};
+
 // ============================================================================
 // Navigation State Management
 // ============================================================================
@@ -619,6 +813,86 @@ export const getUserPreferences = async (): Promise<{
   }
 };
 
+export const getEmbeddingSettings = async (): Promise<EmbeddingSettings> => {
+  try {
+    const result = await invoke<any>('get_embedding_settings');
+    return {
+      provider: result.provider,
+      baseUrl: result.baseUrl ?? undefined,
+      model: result.model ?? undefined,
+      dimension: result.dimension ?? undefined
+    };
+  } catch (error) {
+    console.error('Failed to get embedding settings:', error);
+    throw new Error(`Failed to get embedding settings: ${error}`);
+  }
+};
+
+export const saveEmbeddingSettings = async (settings: EmbeddingSettings): Promise<void> => {
+  try {
+    await invoke('save_embedding_settings', { settings });
+  } catch (error) {
+    console.error('Failed to save embedding settings:', error);
+    throw new Error(`Failed to save embedding settings: ${error}`);
+  }
+};
+
+export const testEmbeddingProvider = async (): Promise<EmbeddingProviderTest> => {
+  try {
+    return await invoke<EmbeddingProviderTest>('test_embedding_provider');
+  } catch (error) {
+    console.error('Failed to test embedding provider:', error);
+    throw new Error(`Failed to test embedding provider: ${error}`);
+  }
+};
+
+export const getEmbeddingCacheStats = async (): Promise<EmbeddingCacheStats[]> => {
+  try {
+    return await invoke<EmbeddingCacheStats[]>('get_embedding_cache_stats');
+  } catch (error) {
+    console.error('Failed to get embedding cache stats:', error);
+    throw new Error(`Failed to get embedding cache stats: ${error}`);
+  }
+};
+
+// Drops cached vectors of models that are no longer configured or stored on concepts
+export const pruneEmbeddingCache = async (): Promise<number> => {
+  try {
+    return await invoke<number>('prune_embedding_cache');
+  } catch (error) {
+    console.error('Failed to prune embedding cache:', error);
+    throw new Error(`Failed to prune embedding cache: ${error}`);
+  }
+};
+
+// Re-embeds all concepts in the background; listen for 'reembedding-progress' / 'reembedding-complete'
+export const startReembedding = async (): Promise<EmbeddingJob> => {
+  try {
+    return await invoke<EmbeddingJob>('start_reembedding');
+  } catch (error) {
+    console.error('Failed to start re-embedding:', error);
+    throw new Error(`Failed to start re-embedding: ${error}`);
+  }
+};
+
+export const getReembeddingStatus = async (): Promise<EmbeddingJob | null> => {
+  try {
+    return await invoke<EmbeddingJob | null>('get_reembedding_status');
+  } catch (error) {
+    console.error('Failed to get re-embedding status:', error);
+    throw new Error(`Failed to get re-embedding status: ${error}`);
+  }
+};
+
+export const cancelReembedding = async (jobId: string): Promise<boolean> => {
+  try {
+    return await invoke<boolean>('cancel_reembedding', { jobId });
+  } catch (error) {
+    console.error('Failed to cancel re-embedding:', error);
+    throw new Error(`Failed to cancel re-embedding: ${error}`);
+  }
+};
+
 // ============================================================================
 // Database Commands (Future Implementation)
 // ============================================================================
@@ -809,16 +1083,79 @@ export async function testTauriCommunication(): Promise<{
 // LangGraph Concept Extraction API
 // ============================================================================
 
-export const analyzeChatSession = async (chatSessionId: string): Promise<any> => {
+// Queues the analysis and returns its job; listen for 'analysis-progress' / 'analysis-complete'
+export const analyzeChatSession = async (chatSessionId: string): Promise<AnalysisJob> => {
   try {
-    const result = await invoke<any>('analyze_chat_session', { chatSessionId });
-    return result;
+    return await invoke<AnalysisJob>('analyze_chat_session', { chatSessionId });
   } catch (error) {
     console.error('Failed to analyze chat session:', error);
     throw new Error(`Failed to analyze chat session: ${error}`);
   }
 };
 
+export const getAnalysisJob = async (jobId: string): Promise<AnalysisJob | null> => {
+  try {
+    return await invoke<AnalysisJob | null>('get_analysis_job', { jobId });
+  } catch (error) {
+    console.error('Failed to get analysis job:', error);
+    throw new Error(`Failed to get analysis job: ${error}`);
+  }
+};
+
+export const listAnalysisJobs = async (chatSessionId?: string, limit?: number): Promise<AnalysisJob[]> => {
+  try {
+    return await invoke<AnalysisJob[]>('list_analysis_jobs', { chatSessionId, limit });
+  } catch (error) {
+    console.error('Failed to list analysis jobs:', error);
+    throw new Error(`Failed to list analysis jobs: ${error}`);
+  }
+};
+
+export const cancelAnalysisJob = async (jobId: string): Promise<boolean> => {
+  try {
+    return await invoke<boolean>('cancel_analysis_job', { jobId });
+  } catch (error) {
+    console.error('Failed to cancel analysis:', error);
+    throw new Error(`Failed to cancel analysis: ${error}`);
+  }
+};
+
+export const retryAnalysis = async (jobId: string): Promise<AnalysisJob> => {
+  try {
+    return await invoke<AnalysisJob>('retry_analysis', { jobId });
+  } catch (error) {
+    console.error('Failed to retry analysis:', error);
+    throw new Error(`Failed to retry analysis: ${error}`);
+  }
+};
+
+export const analyzeAllPending = async (): Promise<AnalysisJob[]> => {
+  try {
+    return await invoke<AnalysisJob[]>('analyze_all_pending');
+  } catch (error) {
+    console.error('Failed to queue pending analyses:', error);
+    throw new Error(`Failed to queue pending analyses: ${error}`);
+  }
+};
+
+export const getInterruptedAnalysisAction = async (): Promise<InterruptedAnalysisAction> => {
+  try {
+    return await invoke<InterruptedAnalysisAction>('get_interrupted_analysis_action');
+  } catch (error) {
+    console.error('Failed to get interrupted analysis action:', error);
+    throw new Error(`Failed to get interrupted analysis action: ${error}`);
+  }
+};
+
+export const saveInterruptedAnalysisAction = async (action: InterruptedAnalysisAction): Promise<void> => {
+  try {
+    await invoke('save_interrupted_analysis_action', { action });
+  } catch (error) {
+    console.error('Failed to save interrupted analysis action:', error);
+    throw new Error(`Failed to save interrupted analysis action: ${error}`);
+  }
+};
+
 export const getExtractionConcepts = async (): Promise<Concept[]> => {
   try {
     const result = await invoke<any[]>('get_extraction_concepts');
@@ -870,6 +1207,130 @@ export const getConceptById = async (conceptId: string): Promise<Concept | null>
   }
 };
 
+// Convert a stored concept returned by the editing commands
+const toConcept = (concept: any): Concept => ({
+  id: concept.id,
+  name: concept.name,
+  description: concept.description,
+  tags: concept.tags || [],
+  confidenceScore: concept.confidence_score || 0,
+  sourceChatCount: concept.source_chat_count || 0,
+  createdAt: new Date(concept.created_at),
+  updatedAt: new Date(concept.updated_at)
+});
+
+export const updateConcept = async (conceptId: string, changes: ConceptUpdate): Promise<Concept> => {
+  try {
+    return toConcept(await invoke<any>('update_concept', { conceptId, changes }));
+  } catch (error) {
+    console.error('Failed to update concept:', error);
+    throw new Error(`Failed to update concept: ${error}`);
+  }
+};
+
+export const mergeConcepts = async (
+  targetConceptId: string,
+  sourceConceptId: string,
+  changes?: ConceptUpdate
+): Promise<Concept> => {
+  try {
+    return toConcept(await invoke<any>('merge_concepts', { targetConceptId, sourceConceptId, changes }));
+  } catch (error) {
+    console.error('Failed to merge concepts:', error);
+    throw new Error(`Failed to merge concepts: ${error}`);
+  }
+};
+
+export const splitConcept = async (conceptId: string, parts: ConceptSplitPart[]): Promise<Concept[]> => {
+  try {
+    const result = await invoke<any[]>('split_concept', { conceptId, parts });
+    return result.map(toConcept);
+  } catch (error) {
+    console.error('Failed to split concept:', error);
+    throw new Error(`Failed to split concept: ${error}`);
+  }
+};
+
+export const deleteConcept = async (conceptId: string): Promise<boolean> => {
+  try {
+    return await invoke<boolean>('delete_concept', { conceptId });
+  } catch (error) {
+    console.error('Failed to delete concept:', error);
+    throw new Error(`Failed to delete concept: ${error}`);
+  }
+};
+
+// ============================================================================
+// Concept Review API
+// ============================================================================
+
+export const listConceptProposals = async (
+  status?: ConceptProposal['status'],
+  chatSessionId?: string
+): Promise<ConceptProposal[]> => {
+  try {
+    return await invoke<ConceptProposal[]>('list_concept_proposals', { status, chatSessionId });
+  } catch (error) {
+    console.error('Failed to list concept proposals:', error);
+    throw new Error(`Failed to list concept proposals: ${error}`);
+  }
+};
+
+export const acceptConceptProposal = async (proposalId: string): Promise<ConceptProposal> => {
+  try {
+    return await invoke<ConceptProposal>('accept_concept_proposal', { proposalId });
+  } catch (error) {
+    console.error('Failed to accept concept proposal:', error);
+    throw new Error(`Failed to accept concept proposal: ${error}`);
+  }
+};
+
+export const updateConceptProposal = async (proposalId: string, changes: ConceptUpdate): Promise<ConceptProposal> => {
+  try {
+    return await invoke<ConceptProposal>('update_concept_proposal', { proposalId, changes });
+  } catch (error) {
+    console.error('Failed to update concept proposal:', error);
+    throw new Error(`Failed to update concept proposal: ${error}`);
+  }
+};
+
+// Without a target concept the proposal becomes a new concept when accepted
+export const retargetConceptProposal = async (proposalId: string, targetConceptId?: string): Promise<ConceptProposal> => {
+  try {
+    return await invoke<ConceptProposal>('retarget_concept_proposal', { proposalId, targetConceptId });
+  } catch (error) {
+    console.error('Failed to retarget concept proposal:', error);
+    throw new Error(`Failed to retarget concept proposal: ${error}`);
+  }
+};
+
+export const rejectConceptProposal = async (proposalId: string): Promise<ConceptProposal> => {
+  try {
+    return await invoke<ConceptProposal>('reject_concept_proposal', { proposalId });
+  } catch (error) {
+    console.error('Failed to reject concept proposal:', error);
+    throw new Error(`Failed to reject concept proposal: ${error}`);
+  }
+};
+
+export const getConceptReviewEnabled = async (): Promise<boolean> => {
+  try {
+    return await invoke<boolean>('get_concept_review_enabled');
+  } catch (error) {
+    console.error('Failed to get concept review setting:', error);
+    throw new Error(`Failed to get concept review setting: ${error}`);
+  }
+};
+
+export const saveConceptReviewEnabled = async (enabled: boolean): Promise<void> => {
+  try {
+    await invoke('save_concept_review_enabled', { enabled });
+  } catch (error) {
+    console.error('Failed to save concept review setting:', error);
+    throw new Error(`Failed to save concept review setting: ${error}`);
+  }
+};
+
 // ============================================================================
 // Vector Similarity Search API
 // ============================================================================
@@ -878,14 +1339,13 @@ export const findSimilarConcepts = async (
   conceptId: string, 
   similarityThreshold?: number, 
   maxResults?: number
-): Promise<any[]> => {
+): Promise<SimilarConcept[]> => {
   try {
-    const result = await invoke<any[]>('find_similar_concepts', { 
+    return await invoke<SimilarConcept[]>('find_similar_concepts', { 
       conceptId, 
       similarityThreshold, 
       maxResults 
     });
-    return result;
   } catch (error) {
     console.error('Failed to find similar concepts:', error);
     throw new Error(`Failed to find similar concepts: ${error}`);
@@ -896,7 +1356,7 @@ export const searchConceptsByText = async (
   queryText: string, 
   similarityThreshold?: number, 
   maxResults?: number
-): Promise<Concept[]> => {
+): Promise<ConceptSearchResult[]> => {
   try {
     const result = await invoke<any[]>('search_concepts_by_text', { 
       queryText, 
@@ -904,17 +1364,12 @@ export const searchConceptsByText = async (
       maxResults 
     });
     return result.map((concept: any) => ({
-      id: concept.id,
-      name: concept.name,
-      description: concept.description,
+      ...concept,
       tags: concept.tags || [],
-      confidenceScore: concept.confidence_score || 0,
-      sourceChatCount: concept.source_chat_count || 0,
-      createdAt: new Date(concept.created_at),
-      updatedAt: new Date(concept.updated_at),
-      sourceChatTitles: concept.source_chat_titles || [],
-      linkedChatCount: concept.linked_chat_count || 0,
-      avgRelevanceScore: concept.avg_relevance_score || 0
+      semanticScore: concept.semanticScore ?? undefined,
+      lexicalScore: concept.lexicalScore ?? undefined,
+      createdAt: new Date(concept.createdAt),
+      updatedAt: new Date(concept.updatedAt)
     }));
   } catch (error) {
     console.error('Failed to search concepts by text:', error);
@@ -922,6 +1377,67 @@ export const searchConceptsByText = async (
   }
 };
 
+// ============================================================================
+// Concept Relationship Graph API
+// ============================================================================
+
+export const getConceptNeighbors = async (
+  conceptId: string,
+  relationshipTypes?: ConceptRelationshipType[]
+): Promise<ConceptNeighbor[]> => {
+  try {
+    return await invoke<ConceptNeighbor[]>('get_concept_neighbors', { conceptId, relationshipTypes });
+  } catch (error) {
+    console.error('Failed to get concept neighbors:', error);
+    throw new Error(`Failed to get concept neighbors: ${error}`);
+  }
+};
+
+export const getConceptSubgraph = async (
+  conceptId: string,
+  depth?: number,
+  maxNodes?: number,
+  relationshipTypes?: ConceptRelationshipType[]
+): Promise<ConceptGraph> => {
+  try {
+    return await invoke<ConceptGraph>('get_concept_subgraph', { conceptId, depth, maxNodes, relationshipTypes });
+  } catch (error) {
+    console.error('Failed to get concept subgraph:', error);
+    throw new Error(`Failed to get concept subgraph: ${error}`);
+  }
+};
+
+// Resolves to null when the concepts aren't connected within maxDepth steps
+export const findConceptPath = async (
+  fromConceptId: string,
+  toConceptId: string,
+  maxDepth?: number,
+  relationshipTypes?: ConceptRelationshipType[]
+): Promise<ConceptGraph | null> => {
+  try {
+    return await invoke<ConceptGraph | null>('find_concept_path', {
+      fromConceptId,
+      toConceptId,
+      maxDepth,
+      relationshipTypes
+    });
+  } catch (error) {
+    console.error('Failed to find concept path:', error);
+    throw new Error(`Failed to find concept path: ${error}`);
+  }
+};
+
+export const exportKnowledgeGraph = async (
+  format: 'graphml' | 'dot' | 'jsonld' = 'graphml'
+): Promise<string> => {
+  try {
+    return await invoke<string>('export_knowledge_graph', { format });
+  } catch (error) {
+    console.error('Failed to export knowledge graph:', error);
+    throw new Error(`Failed to export knowledge graph: ${error}`);
+  }
+};
+
 // ============================================================================
 // Enhanced Concept-Chat Linking Functions
 // ============================================================================
@@ -63,6 +63,13 @@ export interface TextSelection {
   createdAt: Date;
 }
 
+// A saved highlight; stored independently of any chat session
+export interface Highlight extends TextSelection {
+  color: string; // #rrggbb
+  note?: string;
+  updatedAt: Date;
+}
+
 export interface Question {
   id: string;
   documentId: string;
@@ -153,6 +160,90 @@ export interface ConceptSourceChat {
   createdAt: Date;
 }
 
+// A concept found by hybrid (embedding + full-text) search
+export interface ConceptSearchResult extends Concept {
+  score: number; // Blended relevance, 0-1
+  semanticScore?: number;
+  lexicalScore?: number;
+  matchReasons: Array<'semantic' | 'full-text' | 'name'>;
+}
+
+// A neighbor of a concept in embedding space
+export interface SimilarConcept {
+  id: string;
+  name: string;
+  description: string;
+  tags: string[];
+  confidenceScore: number;
+  similarityScore: number; // Cosine similarity, 1 = identical
+}
+
+// Changes to a concept; omitted fields stay unchanged
+export interface ConceptUpdate {
+  name?: string;
+  description?: string;
+  tags?: string[];
+}
+
+// One of the concepts a concept is split into
+export interface ConceptSplitPart {
+  name: string;
+  description: string;
+  tags?: string[];
+  chatSessionIds?: string[]; // Defaults to every chat of the original concept
+}
+
+// A concept an analysis extracted, waiting for review when concept review is on
+export interface ConceptProposal {
+  id: string;
+  analysisJobId: string;
+  chatSessionId: string;
+  name: string;
+  description: string;
+  tags: string[];
+  confidenceScore: number;
+  relatedConcepts: string[];
+  decision: 'new' | 'merge';
+  targetConceptId?: string;
+  targetConceptName?: string;
+  similarityScore?: number; // Unset when the target was matched by name or picked by hand
+  status: 'pending' | 'accepted' | 'rejected';
+  conceptId?: string; // The concept an accepted proposal became
+  createdAt: string;
+  reviewedAt?: string;
+}
+
+export type ConceptRelationshipType = 'related' | 'prerequisite' | 'builds_on' | 'similar' | 'opposite';
+
+export interface ConceptGraphNode {
+  id: string;
+  name: string;
+  description: string;
+  tags: string[];
+  confidenceScore: number;
+  sourceChatCount: number;
+}
+
+export interface ConceptGraphEdge {
+  id: string;
+  sourceConceptId: string;
+  targetConceptId: string;
+  relationshipType: ConceptRelationshipType;
+  similarityScore: number;
+}
+
+// Nodes and edges of a subgraph; for a path, both are in order from start to end
+export interface ConceptGraph {
+  nodes: ConceptGraphNode[];
+  edges: ConceptGraphEdge[];
+}
+
+export interface ConceptNeighbor {
+  concept: ConceptGraphNode;
+  relationship: ConceptGraphEdge;
+  direction: 'outgoing' | 'incoming';
+}
+
 export interface ConceptDetail extends Concept {
   sourceChats: ConceptSourceChat[];
 }
@@ -160,6 +251,7 @@ export interface ConceptDetail extends Concept {
 export interface UserNote {
   id: string;
   documentId: string;
+  documentTitle: string;
   selectionId?: string; // Optional link to text selection
   pageNumber: number;
   noteText: string;
@@ -174,6 +266,19 @@ export interface UserNote {
   updatedAt: Date;
 }
 
+// Criteria for listing notes; all fields combine with AND
+export interface NoteFilter {
+  documentId?: string;
+  pageNumber?: number;
+  noteType?: UserNote['noteType'];
+  tag?: string;
+  createdAfter?: string; // ISO 8601
+  createdBefore?: string; // ISO 8601
+  query?: string;
+  limit?: number;
+  offset?: number;
+}
+
 // ============================================================================
 // Search and Discovery Types
 // ============================================================================
@@ -246,6 +351,82 @@ export interface UserPreferences {
   maxRecentDocuments: number;
 }
 
+// Which embedding backend turns concepts and search queries into vectors;
+// unset fields use the provider defaults
+export interface EmbeddingSettings {
+  provider: 'openai' | 'ollama' | 'hashing';
+  baseUrl?: string; // OpenAI-compatible or Ollama server
+  model?: string;
+  dimension?: number; // Required for models the app doesn't know
+}
+
+export interface EmbeddingCacheStats {
+  model: string;
+  entries: number;
+  hits: number;
+  misses: number;
+  lastUsedAt?: string;
+}
+
+// Progress of re-embedding all concepts with the configured model; also the
+// payload of the 'reembedding-progress' and 'reembedding-complete' events
+export interface EmbeddingJob {
+  id: string;
+  model: string;
+  dimension: number;
+  status: 'running' | 'complete' | 'failed' | 'cancelled';
+  totalItems: number;
+  processedItems: number;
+  errorMessage?: string;
+  startedAt: string;
+  updatedAt: string;
+  completedAt?: string;
+}
+
+export interface AnalysisConceptChange {
+  conceptId: string;
+  name: string;
+}
+
+// A background concept analysis of a chat session; also the payload of the
+// 'analysis-progress' and 'analysis-complete' events
+export interface AnalysisJob {
+  id: string;
+  chatSessionId: string;
+  status: 'pending' | 'processing' | 'complete' | 'failed' | 'cancelled';
+  processingStages: Array<{ stage: 'loading' | 'extracting' | 'embedding' | 'storing'; startedAt: string }>;
+  extractedConceptCount: number;
+  errorMessage?: string;
+  result?: {
+    newConceptsCreated: number;
+    conceptsLinked: number;
+    relationshipsCreated: number;
+    // How the concepts linked to the chat changed; missing on jobs from before it was recorded
+    changes?: {
+      added: AnalysisConceptChange[];
+      updated: AnalysisConceptChange[];
+      unlinked: AnalysisConceptChange[];
+    };
+  } | {
+    // With concept review on, the concepts were stored as proposals instead
+    proposalsCreated: number;
+  };
+  createdAt: string;
+  startedAt?: string;
+  completedAt?: string;
+  processingTimeMs?: number;
+}
+
+// What startup does with analyses that were running when the app quit
+export type InterruptedAnalysisAction = 'fail' | 'requeue';
+
+export interface EmbeddingProviderTest {
+  model: string;
+  dimension: number;
+  conceptColumnDimension: number | null;
+  compatible: boolean; // Whether vectors fit concepts.embedding
+}
+
 // ============================================================================
 // API and Communication Types
 // ============================================================================
@@ -412,6 +593,7 @@ export interface HighlightedContext {
     width: number;
     height: number;
   }[];
+  sectionTitle?: string; // Outline path, e.g. "Chapter 2 > Gradient Descent"
   createdAt: Date;
 }
 